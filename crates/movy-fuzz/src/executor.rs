@@ -210,12 +210,9 @@ where
 
         let (stage_idx, success) = match effects.status() {
             ExecutionStatus::Failure { command, .. } => (
-                // command index may be out of bound when meeting non-aborted error
-                if command.is_some_and(|c| c < input.sequence().commands.len()) {
-                    command.clone()
-                } else {
-                    None
-                },
+                // command index is into the ptb, which builds the vector inputs ahead, and may
+                // be out of bound when meeting non-aborted error
+                command.and_then(|c| input.sequence().sequence_command(c)),
                 false,
             ),
             _ => (None, true),
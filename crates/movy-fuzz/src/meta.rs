@@ -12,6 +12,7 @@ use log::debug;
 use movy_replay::{
     db::{ObjectStoreCachedStore, ObjectStoreInfo},
     env::SuiTestingEnv,
+    exec::SuiProtocol,
     meta::Metadata,
 };
 use movy_sui::database::cache::ObjectSuiStoreCommit;
@@ -22,13 +23,13 @@ use movy_types::{
         MovePackageAbi, MoveStructAbi,
     },
     error::MovyError,
-    input::{FunctionIdent, MoveAddress, MoveTypeTag},
+    input::{FunctionIdent, MoveAddress, MoveSequence, MoveSequenceCall, MoveTypeTag},
 };
 use serde::{Deserialize, Serialize};
 use serde_json_any_key::any_key_map;
 use sui_types::storage::{BackingPackageStore, BackingStore, ObjectStore};
 
-use crate::{r#const::INIT_FUNCTION_SCORE, utils::SuperRand};
+use crate::{r#const::INIT_FUNCTION_SCORE, oracles::sui::OracleConfig, utils::SuperRand};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectWithversion {
@@ -499,6 +500,11 @@ pub struct FuzzMetadata {
     pub checkpoint: u64,
     pub epoch: u64,
     pub epoch_ms: u64,
+    #[serde(default)]
+    pub protocol: SuiProtocol,
+
+    #[serde(default)]
+    pub oracle_config: OracleConfig,
 }
 
 impl Deref for FuzzMetadata {
@@ -531,7 +537,9 @@ impl FuzzMetadata {
         checkpoint: u64,
         epoch: u64,
         epoch_ms: u64,
+        protocol: SuiProtocol,
         filters: TargetFilters,
+        oracle_config: OracleConfig,
     ) -> Result<Self, MovyError>
     where
         T: ObjectStoreCachedStore
@@ -560,7 +568,9 @@ impl FuzzMetadata {
             checkpoint,
             epoch,
             epoch_ms,
+            protocol,
             filters,
+            oracle_config,
         ))
     }
 
@@ -576,7 +586,9 @@ impl FuzzMetadata {
         checkpoint: u64,
         epoch: u64,
         epoch_ms: u64,
+        protocol: SuiProtocol,
         filters: TargetFilters,
+        oracle_config: OracleConfig,
     ) -> Self {
         let specific_function_scores: BTreeMap<FunctionIdent, u64> = function_scores
             .into_iter()
@@ -617,6 +629,8 @@ impl FuzzMetadata {
             checkpoint,
             epoch,
             epoch_ms,
+            protocol,
+            oracle_config,
         }
     }
 
@@ -649,6 +663,30 @@ impl FuzzMetadata {
             })
     }
 
+    /// Check a seed against the metadata and the objects in `db` before it is executed,
+    /// the mutators expect every call to be known and every object to exist. References
+    /// to owned objects are refreshed as the seed may come from an earlier checkpoint.
+    pub fn check_seed<T: ObjectStoreInfo>(
+        &self,
+        db: &T,
+        mut sequence: MoveSequence,
+    ) -> Result<MoveSequence, MovyError> {
+        for cmd in sequence.commands.iter() {
+            if let MoveSequenceCall::Call(call) = cmd
+                && self
+                    .get_function(&call.module_id, &call.module_name, &call.function)
+                    .is_none()
+            {
+                return Err(MovyError::InvalidSeed(format!(
+                    "function {}::{}::{} is not in the metadata",
+                    call.module_id, call.module_name, call.function
+                )));
+            }
+        }
+        db.refresh_object_inputs(&mut sequence)?;
+        Ok(sequence)
+    }
+
     pub fn generate_magic_number_pool(&self) -> BTreeSet<Vec<u8>> {
         BTreeSet::new()
     }
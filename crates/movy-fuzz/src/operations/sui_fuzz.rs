@@ -7,22 +7,23 @@ use crate::mutators::arg::ArgMutator;
 use crate::mutators::sequence::SequenceMutator;
 use crate::operations::fuzz::{OkFeedback, code_observer};
 use crate::oracles::sui::{
-    BoolJudgementOracle, InfiniteLoopOracle, OverflowOracle, PrecisionLossOracle, ProceedsOracle,
-    TypeConversionOracle, TypedBugOracle,
+    AbortCodeOracle, BoolJudgementOracle, DynamicFieldGrowthOracle, EventInvariantOracle,
+    GasGriefingOracle, HotPotatoOracle, InfiniteLoopOracle, OracleConfig, OverflowOracle,
+    PrecisionLossOracle, ProceedsOracle, TypeConversionOracle, TypedBugOracle,
 };
 use crate::sched::MoveFuzzInputScore;
 use crate::state::{ExtraNonSerdeFuzzState, HasExtraState, HasFuzzEnv, SuperState};
 use crate::utils::{AppendOutcomeFeedback, SelectiveCorpus};
 use libafl::{
     Evaluator, Fuzzer, HasMetadata, StdFuzzer,
-    corpus::{InMemoryCorpus, InMemoryOnDiskCorpus},
+    corpus::{Corpus, InMemoryCorpus, InMemoryOnDiskCorpus},
     events::{ProgressReporter, SimpleEventManager},
     feedback_and_fast,
     feedbacks::{CrashFeedback, ExitKindFeedback, MaxMapPow2Feedback},
     monitors::SimpleMonitor,
     schedulers::WeightedScheduler,
     stages::{CalibrationStage, StdMutationalStage},
-    state::StdState,
+    state::{HasExecutions, HasSolutions, StdState},
 };
 use libafl_bolts::tuples::tuple_list;
 use log::{info, warn};
@@ -32,13 +33,25 @@ use movy_replay::exec::SuiExecutor;
 use movy_replay::tracer::oracle::{CouldDisabledOralce, SuiGeneralOracle};
 use movy_sui::database::cache::{CachedStore, ObjectSuiStoreCommit};
 use movy_types::error::MovyError;
+use movy_types::input::MoveSequence;
 use sui_types::storage::BackingStore;
 use sui_types::storage::{BackingPackageStore, ObjectStore};
 
+#[derive(Debug, Clone)]
+pub struct FuzzSummary {
+    pub executions: u64,
+    /// Solutions found by the initial seeds alone
+    pub seed_solutions: usize,
+    pub solutions: usize,
+    /// Time to the first solution found by mutating, not counting the initial seeds
+    pub first_solution: Option<Duration>,
+}
+
 pub fn oracles<T, S, E>(
     typed_bug_abort: bool,
     disable_profit_oracle: bool,
     disable_defects_oracle: bool,
+    config: &OracleConfig,
 ) -> impl for<'a> SuiGeneralOracle<CachedStore<&'a T>, S>
 where
     T: 'static + ObjectStore,
@@ -52,6 +65,17 @@ where
         CouldDisabledOralce::new(OverflowOracle, disable_defects_oracle),
         CouldDisabledOralce::new(ProceedsOracle::default(), disable_profit_oracle),
         CouldDisabledOralce::new(TypedBugOracle::new(typed_bug_abort), disable_defects_oracle),
+        CouldDisabledOralce::new(
+            AbortCodeOracle::new(config.clone()),
+            disable_defects_oracle || !config.report_aborts,
+        ),
+        CouldDisabledOralce::new(HotPotatoOracle::default(), disable_defects_oracle),
+        CouldDisabledOralce::new(
+            EventInvariantOracle::new(config.event_invariants.clone()),
+            disable_defects_oracle,
+        ),
+        CouldDisabledOralce::new(GasGriefingOracle::default(), disable_defects_oracle),
+        CouldDisabledOralce::new(DynamicFieldGrowthOracle::default(), disable_defects_oracle),
     )
 }
 
@@ -63,7 +87,8 @@ fn fuzz_impl<T>(
     typed_bug_abort: bool,
     disable_profit_oracle: bool,
     disable_defects_oracle: bool,
-) -> Result<(), MovyError>
+    seeds: Vec<MoveSequence>,
+) -> Result<FuzzSummary, MovyError>
 where
     T: ObjectStoreCachedStore
         + ObjectStoreInfo
@@ -113,6 +138,7 @@ where
         &mut crash_feedback,
     )?;
     let attacker = meta.attacker;
+    let oracle_config = meta.oracle_config.clone();
 
     let mut state = SuperState::new(state, env);
 
@@ -123,7 +149,10 @@ where
     );
     state.add_metadata::<FuzzMetadata>(meta);
 
-    let executor_inner = SuiExecutor::new(state.fuzz_env().inner().clone())?;
+    let executor_inner = SuiExecutor::new_with_protocol(
+        state.fuzz_env().inner().clone(),
+        state.fuzz_state().protocol,
+    )?;
 
     let sched: WeightedScheduler<_, MoveFuzzInputScore, _> =
         WeightedScheduler::new(&mut state, &code_observer);
@@ -135,6 +164,7 @@ where
             typed_bug_abort,
             disable_profit_oracle,
             disable_defects_oracle,
+            &oracle_config,
         ),
         epoch: state.fuzz_state().epoch,
         epoch_ms: state.fuzz_state().epoch_ms,
@@ -150,9 +180,37 @@ where
     let mut fuzzer = StdFuzzer::new(sched, corpus_feedback, crash_feedback);
     let mut mgr = SimpleEventManager::new(SimpleMonitor::new(|s| info!("{}", s)));
 
-    info!("Adding initial input...");
-    let initial_input = MoveFuzzInput::new();
-    fuzzer.add_input(&mut state, &mut executor, &mut mgr, initial_input)?;
+    let seeds = seeds
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, sequence)| {
+            match state
+                .fuzz_state()
+                .check_seed(state.fuzz_env().inner(), sequence)
+            {
+                Ok(sequence) => Some(sequence),
+                Err(e) => {
+                    warn!("Skip seed {} due to {}", idx, e);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    if seeds.is_empty() {
+        info!("Adding initial input...");
+        let initial_input = MoveFuzzInput::new();
+        fuzzer.add_input(&mut state, &mut executor, &mut mgr, initial_input)?;
+    } else {
+        info!("Adding {} initial seeds...", seeds.len());
+        for sequence in seeds {
+            let input = MoveFuzzInput {
+                sequence,
+                ..Default::default()
+            };
+            fuzzer.add_input(&mut state, &mut executor, &mut mgr, input)?;
+        }
+    }
+    let seed_solutions = state.solutions().count();
 
     // if let Some(flash) = &self.flash {
     //     let flash_wrapper = FlashWrapper::from_str_with_store(flash, &db)?;
@@ -204,6 +262,7 @@ where
         .unwrap();
 
     let start = std::time::SystemTime::now();
+    let mut first_solution = None;
     let mut cycle = 1usize;
     loop {
         if let Some(limit) = time_limit {
@@ -223,6 +282,14 @@ where
         // Clear per-round execution outcome to avoid leaking stage indices into the next round.
         state.extra_state_mut().global_outcome = None;
 
+        if first_solution.is_none() && state.solutions().count() > seed_solutions {
+            let elapsed = std::time::SystemTime::now()
+                .duration_since(start)
+                .expect("non mono clock?!");
+            info!("First solution found after {:?}", elapsed);
+            first_solution = Some(elapsed);
+        }
+
         info!("Cycle {} done", cycle);
         cycle += 1;
         mgr.report_progress(&mut state)?;
@@ -235,7 +302,12 @@ where
         report.flamegraph(file).unwrap();
     }
 
-    Ok(())
+    Ok(FuzzSummary {
+        executions: *state.executions(),
+        seed_solutions,
+        solutions: state.solutions().count(),
+        first_solution,
+    })
 }
 
 pub fn fuzz(
@@ -253,7 +325,8 @@ pub fn fuzz(
     typed_bug_abort: bool,
     disable_profit_oracle: bool,
     disable_defects_oracle: bool,
-) -> Result<(), MovyError> {
+    seeds: Vec<MoveSequence>,
+) -> Result<FuzzSummary, MovyError> {
     fuzz_impl(
         meta,
         env,
@@ -262,6 +335,6 @@ pub fn fuzz(
         typed_bug_abort,
         disable_profit_oracle,
         disable_defects_oracle,
-    )?;
-    Ok(())
+        seeds,
+    )
 }
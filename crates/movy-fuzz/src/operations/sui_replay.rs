@@ -12,18 +12,22 @@ use libafl_bolts::tuples::tuple_list;
 use movy_replay::{
     db::{ObjectStoreCachedStore, ObjectStoreInfo, ObjectStoreMintObject},
     env::SuiTestingEnv,
+    event::NotifierTracer,
     exec::SuiExecutor,
-    tracer::tree::TreeTracer,
+    tracer::{
+        debug::{Breakpoint, DebugTracer},
+        tree::{TreeTraceResult, TreeTracer},
+    },
 };
 use movy_sui::database::cache::ObjectSuiStoreCommit;
-use movy_types::error::MovyError;
+use movy_types::{error::MovyError, input::MoveSequence};
 use sui_types::{
     effects::TransactionEffectsAPI,
     storage::{BackingPackageStore, BackingStore, ObjectStore},
 };
 
 use crate::{
-    executor::SuiFuzzExecutor,
+    executor::{FuzzModuleProvider, SuiFuzzExecutor},
     input::MoveFuzzInput,
     meta::{FuzzMetadata, HasFuzzMetadata},
     operations::fuzz::{OkFeedback, code_observer},
@@ -36,12 +40,12 @@ pub fn sui_plain_replay_seed<T>(
     meta: FuzzMetadata,
     seed: MoveFuzzInput,
     trace: bool,
-) -> Result<(), MovyError>
+) -> Result<Option<TreeTraceResult>, MovyError>
 where
     T: ObjectStore + BackingStore + ObjectSuiStoreCommit + ObjectStoreMintObject + ObjectStoreInfo,
 {
     let inner = env.into_inner();
-    let executor = SuiExecutor::new(inner)?;
+    let executor = SuiExecutor::new_with_protocol(inner, meta.protocol)?;
     let tracer = if trace { Some(TreeTracer::new()) } else { None };
     let out = executor.run_ptb_with_gas(
         seed.sequence.to_ptb()?,
@@ -52,10 +56,35 @@ where
         tracer,
     )?;
     log::info!("Replay status is {:?}", &out.results.effects.status());
-    if let Some(tracer) = out.tracer {
-        println!("Trace:\n{}", &tracer.take_inner().pprint());
-    }
+    Ok(out.tracer.map(|tracer| tracer.take_inner()))
+}
 
+/// Replay the sequence under the interactive debugger, which stops at the breakpoints.
+pub fn sui_debug_replay_sequence<T>(
+    env: SuiTestingEnv<T>,
+    meta: FuzzMetadata,
+    sequence: MoveSequence,
+    breakpoints: Vec<Breakpoint>,
+) -> Result<(), MovyError>
+where
+    T: ObjectStore + BackingStore + ObjectSuiStoreCommit + ObjectStoreMintObject + ObjectStoreInfo,
+{
+    let inner = env.into_inner();
+    let executor = SuiExecutor::new_with_protocol(inner, meta.protocol)?;
+    let provider = FuzzModuleProvider::new(&executor.db);
+    let tracer = NotifierTracer::with_provider(DebugTracer::new(breakpoints), provider);
+    let out = executor.run_ptb_with_gas(
+        sequence.to_ptb()?,
+        meta.epoch,
+        meta.epoch_ms,
+        meta.attacker.into(),
+        meta.gas_id.into(),
+        Some(tracer),
+    )?;
+    println!(
+        "Execution finished with {:?}",
+        &out.results.effects.status()
+    );
     Ok(())
 }
 
@@ -95,17 +124,21 @@ where
         &mut crash_feedback,
     )?;
     let attacker = meta.attacker;
+    let oracle_config = meta.oracle_config.clone();
 
     let mut state = SuperState::new(state, env);
 
     state.add_metadata::<FuzzMetadata>(meta);
 
-    let executor_inner = SuiExecutor::new(state.fuzz_env().inner().clone())?;
+    let executor_inner = SuiExecutor::new_with_protocol(
+        state.fuzz_env().inner().clone(),
+        state.fuzz_state().protocol,
+    )?;
     let mut executor = SuiFuzzExecutor {
         executor: executor_inner,
         ob: tuple_list!(code_observer),
         attacker,
-        oracles: super::sui_fuzz::oracles(false, false, false),
+        oracles: super::sui_fuzz::oracles(false, false, false, &oracle_config),
         epoch: state.fuzz_state().epoch,
         epoch_ms: state.fuzz_state().epoch_ms,
         ph: std::marker::PhantomData,
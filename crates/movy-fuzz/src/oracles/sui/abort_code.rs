@@ -0,0 +1,380 @@
+use std::fmt::Display;
+
+use log::debug;
+use move_binary_format::{
+    CompiledModule,
+    file_format::{Bytecode, SignatureToken},
+};
+use move_core_types::{account_address::AccountAddress, language_storage::ModuleId};
+use move_trace_format::format::TraceEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
+use movy_types::{
+    error::MovyError,
+    input::{MoveAddress, MoveSequence},
+    oracle::{FindingLocation, OracleFinding, Severity},
+};
+use sui_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    execution_status::{ExecutionFailureStatus, ExecutionStatus, MoveLocation},
+    storage::ObjectStore,
+};
+
+use crate::meta::HasFuzzMetadata;
+
+use super::config::OracleConfig;
+
+// Move 2024 clever errors: [tag:1][reserved:15][line:16][identifier:16][constant:16]
+const CLEVER_ERROR_TAG: u64 = 0x8000_0000_0000_0000;
+const CLEVER_ERROR_NO_IDENTIFIER: usize = 0xffff;
+
+// 0x2::dynamic_field::{EFieldDoesNotExist, EFieldTypeMismatch}
+const DYNAMIC_FIELD_DOES_NOT_EXIST: u64 = 1;
+const DYNAMIC_FIELD_TYPE_MISMATCH: u64 = 2;
+// 0x1::vector::EINDEX_OUT_OF_BOUNDS
+const VECTOR_INDEX_OUT_OF_BOUNDS: u64 = 0x20000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbortKind {
+    Arithmetic,
+    VectorIndexOutOfBounds,
+    DynamicFieldMissing,
+    DynamicFieldTypeMismatch,
+    Primitive,
+    Business,
+}
+
+impl AbortKind {
+    /// Whether this abort is never part of a sane business logic.
+    pub fn is_unexpected(&self) -> bool {
+        !matches!(self, Self::Business)
+    }
+}
+
+impl Display for AbortKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Arithmetic => "arithmetic",
+            Self::VectorIndexOutOfBounds => "vector_index_out_of_bounds",
+            Self::DynamicFieldMissing => "dynamic_field_missing",
+            Self::DynamicFieldTypeMismatch => "dynamic_field_type_mismatch",
+            Self::Primitive => "primitive",
+            Self::Business => "business",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbortClassification {
+    pub module: ModuleId,
+    pub function: String,
+    pub pc: u16,
+    pub code: Option<u64>,
+    /// Name of the error constant, only known for clever errors.
+    pub constant: Option<String>,
+    pub constant_index: Option<usize>,
+    pub line: Option<u16>,
+    pub kind: AbortKind,
+}
+
+fn load_module<T: ObjectStore, S: HasFuzzMetadata>(
+    db: &T,
+    state: &S,
+    module: &ModuleId,
+) -> Option<CompiledModule> {
+    let address = MoveAddress::from(*module.address());
+    let package = state
+        .fuzz_state()
+        .module_address_to_package
+        .get(&address)
+        .copied()
+        .unwrap_or(address);
+    let object = db.get_object(&ObjectID::from(package))?;
+    let bytes = object
+        .data
+        .try_as_package()?
+        .serialized_module_map()
+        .get(module.name().as_str())?
+        .clone();
+    CompiledModule::deserialize_with_defaults(&bytes).ok()
+}
+
+fn function_name(module: Option<&CompiledModule>, location: &MoveLocation) -> String {
+    if let Some(name) = &location.function_name {
+        return name.clone();
+    }
+    module
+        .and_then(|m| {
+            m.function_defs()
+                .get(location.function as usize)
+                .map(|f| m.identifier_at(m.function_handle_at(f.function).name))
+        })
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("f#{}", location.function))
+}
+
+fn classify_instruction(module: Option<&CompiledModule>, location: &MoveLocation) -> AbortKind {
+    let instruction = module
+        .and_then(|m| m.function_defs().get(location.function as usize))
+        .and_then(|f| f.code.as_ref())
+        .and_then(|c| c.code.get(location.instruction as usize));
+    match instruction {
+        Some(
+            Bytecode::Add
+            | Bytecode::Sub
+            | Bytecode::Mul
+            | Bytecode::Div
+            | Bytecode::Mod
+            | Bytecode::Shl
+            | Bytecode::Shr
+            | Bytecode::CastU8
+            | Bytecode::CastU16
+            | Bytecode::CastU32
+            | Bytecode::CastU64
+            | Bytecode::CastU128
+            | Bytecode::CastU256,
+        ) => AbortKind::Arithmetic,
+        Some(
+            Bytecode::VecImmBorrow(_)
+            | Bytecode::VecMutBorrow(_)
+            | Bytecode::VecPopBack(_)
+            | Bytecode::VecSwap(_)
+            | Bytecode::VecUnpack(_, _),
+        ) => AbortKind::VectorIndexOutOfBounds,
+        _ => AbortKind::Primitive,
+    }
+}
+
+/// Resolve the error constant name of a clever abort code.
+fn decode_clever_error(module: &CompiledModule, code: u64) -> Option<(Option<String>, u16)> {
+    if code & CLEVER_ERROR_TAG == 0 {
+        return None;
+    }
+    let line = ((code >> 32) & 0xffff) as u16;
+    let identifier = ((code >> 16) & 0xffff) as usize;
+    let name = if identifier == CLEVER_ERROR_NO_IDENTIFIER {
+        None
+    } else {
+        module.identifiers.get(identifier).map(|i| i.to_string())
+    };
+    Some((name, line))
+}
+
+/// Find the `u64` constant carrying a legacy abort code.
+fn find_code_constant(module: &CompiledModule, code: u64) -> Option<usize> {
+    module.constant_pool.iter().position(|c| {
+        c.type_ == SignatureToken::U64
+            && <[u8; 8]>::try_from(c.data.as_slice()).is_ok_and(|b| u64::from_le_bytes(b) == code)
+    })
+}
+
+pub fn classify_abort<T: ObjectStore, S: HasFuzzMetadata>(
+    db: &T,
+    state: &S,
+    error: &ExecutionFailureStatus,
+) -> Option<AbortClassification> {
+    match error {
+        ExecutionFailureStatus::MoveAbort(location, code) => {
+            let module = load_module(db, state, &location.module);
+            let kind = match (
+                *location.module.address(),
+                location.module.name().as_str(),
+                *code,
+            ) {
+                (AccountAddress::TWO, "dynamic_field", DYNAMIC_FIELD_DOES_NOT_EXIST) => {
+                    AbortKind::DynamicFieldMissing
+                }
+                (AccountAddress::TWO, "dynamic_field", DYNAMIC_FIELD_TYPE_MISMATCH) => {
+                    AbortKind::DynamicFieldTypeMismatch
+                }
+                (AccountAddress::ONE, "vector", VECTOR_INDEX_OUT_OF_BOUNDS) => {
+                    AbortKind::VectorIndexOutOfBounds
+                }
+                _ => AbortKind::Business,
+            };
+            let (constant, line) = module
+                .as_ref()
+                .and_then(|m| decode_clever_error(m, *code))
+                .map(|(name, line)| (name, Some(line)))
+                .unwrap_or((None, None));
+            let constant_index = module.as_ref().and_then(|m| find_code_constant(m, *code));
+            Some(AbortClassification {
+                module: location.module.clone(),
+                function: function_name(module.as_ref(), location),
+                pc: location.instruction,
+                code: Some(*code),
+                constant,
+                constant_index,
+                line,
+                kind,
+            })
+        }
+        ExecutionFailureStatus::MovePrimitiveRuntimeError(location) => {
+            let location = location.0.as_ref()?;
+            let module = load_module(db, state, &location.module);
+            Some(AbortClassification {
+                module: location.module.clone(),
+                function: function_name(module.as_ref(), location),
+                pc: location.instruction,
+                code: None,
+                constant: None,
+                constant_index: None,
+                line: None,
+                kind: classify_instruction(module.as_ref(), location),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Classifies every abort and reports the ones which are not expected: arithmetic
+/// errors, out of bound vector accesses and missing dynamic fields, plus any
+/// business abort not in the allowlist if configured so. Only installed with
+/// `report_aborts`, see [`OracleConfig`].
+#[derive(Debug, Clone, Default)]
+pub struct AbortCodeOracle {
+    pub config: OracleConfig,
+}
+
+impl AbortCodeOracle {
+    pub fn new(config: OracleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<T, S> SuiGeneralOracle<T, S> for AbortCodeOracle
+where
+    S: HasFuzzMetadata,
+    T: ObjectStore,
+{
+    fn pre_execution(
+        &mut self,
+        _db: &T,
+        _state: &mut S,
+        _sequence: &MoveSequence,
+    ) -> Result<(), MovyError> {
+        Ok(())
+    }
+
+    fn event(
+        &mut self,
+        _event: &TraceEvent,
+        _trace_state: &TraceState,
+        _symbol_stack: &ConcolicState,
+        _current_function: Option<&movy_types::input::FunctionIdent>,
+        _state: &mut S,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        Ok(vec![])
+    }
+
+    fn done_execution(
+        &mut self,
+        db: &T,
+        state: &mut S,
+        effects: &TransactionEffects,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        let ExecutionStatus::Failure { error, .. } = effects.status() else {
+            return Ok(vec![]);
+        };
+        let Some(abort) = classify_abort(db, state, error) else {
+            return Ok(vec![]);
+        };
+        debug!("Abort classified as {:?}", &abort);
+        if self.config.is_expected_abort(&abort) {
+            return Ok(vec![]);
+        }
+        let severity = if abort.kind.is_unexpected() {
+            Severity::Medium
+        } else if self.config.report_unlisted_aborts {
+            Severity::Informational
+        } else {
+            return Ok(vec![]);
+        };
+        let location = FindingLocation::module(
+            (*abort.module.address()).into(),
+            abort.module.name().as_str(),
+        )
+        .with_function(&abort.function)
+        .with_offset(abort.pc);
+        Ok(vec![
+            OracleFinding::new(
+                "AbortCodeOracle",
+                "abort_code",
+                severity,
+                format!("Unexpected {} abort", abort.kind),
+            )
+            .with_location(location)
+            .with_extra(json!({
+                "kind": abort.kind.to_string(),
+                "code": abort.code,
+                "constant": abort.constant,
+                "constant_index": abort.constant_index,
+                "line": abort.line,
+            })),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use move_binary_format::file_format::{Constant, SignatureToken, empty_module};
+    use move_core_types::identifier::Identifier;
+
+    use super::{
+        CLEVER_ERROR_NO_IDENTIFIER, CLEVER_ERROR_TAG, decode_clever_error, find_code_constant,
+    };
+
+    fn clever_code(line: u64, identifier: u64, constant: u64) -> u64 {
+        CLEVER_ERROR_TAG | (line << 32) | (identifier << 16) | constant
+    }
+
+    #[test]
+    fn test_decode_clever_error() {
+        let mut module = empty_module();
+        let identifier = module.identifiers.len() as u64;
+        module
+            .identifiers
+            .push(Identifier::new("EInsufficientLiquidity").unwrap());
+
+        assert_eq!(
+            decode_clever_error(&module, clever_code(42, identifier, 0)),
+            Some((Some("EInsufficientLiquidity".to_string()), 42))
+        );
+        // `assert!(cond)` without an error constant only carries the line
+        assert_eq!(
+            decode_clever_error(
+                &module,
+                clever_code(7, CLEVER_ERROR_NO_IDENTIFIER as u64, 0xffff)
+            ),
+            Some((None, 7))
+        );
+        // An identifier out of the pool is not resolved but the line still is
+        assert_eq!(
+            decode_clever_error(&module, clever_code(9, identifier + 1, 0)),
+            Some((None, 9))
+        );
+        // Legacy abort codes are not clever errors
+        assert_eq!(decode_clever_error(&module, 3), None);
+    }
+
+    #[test]
+    fn test_find_code_constant() {
+        let mut module = empty_module();
+        module.constant_pool.push(Constant {
+            type_: SignatureToken::U8,
+            data: vec![3],
+        });
+        module.constant_pool.push(Constant {
+            type_: SignatureToken::U64,
+            data: 3u64.to_le_bytes().to_vec(),
+        });
+
+        assert_eq!(find_code_constant(&module, 3), Some(1));
+        assert_eq!(find_code_constant(&module, 4), None);
+    }
+}
@@ -1,6 +1,5 @@
 use move_binary_format::file_format::Bytecode;
 use move_trace_format::format::TraceEvent;
-use serde_json::json;
 use sui_types::effects::TransactionEffects;
 use z3::{
     DeclKind,
@@ -18,7 +17,7 @@ use movy_types::{
     oracle::{OracleFinding, Severity},
 };
 
-use super::common::{format_vulnerability_info, to_module_func};
+use super::common::location_of;
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct BoolJudgementOracle;
@@ -46,7 +45,6 @@ impl<T, S> SuiGeneralOracle<T, S> for BoolJudgementOracle {
                 pc, instruction, ..
             } => {
                 let stack_syms = &symbol_stack.stack;
-                let current = current_function.and_then(to_module_func);
                 let loss = match instruction {
                     Bytecode::Eq
                     | Bytecode::Neq
@@ -71,16 +69,15 @@ impl<T, S> SuiGeneralOracle<T, S> for BoolJudgementOracle {
                     _ => false,
                 };
                 if loss {
-                    let info = format_vulnerability_info(
-                        "Unnecessary bool judgement (two constants)",
-                        current.as_ref(),
-                        Some(*pc),
-                    );
-                    Ok(vec![OracleFinding {
-                        oracle: "BoolJudgementOracle".to_string(),
-                        severity: Severity::Minor,
-                        extra: json!(info),
-                    }])
+                    Ok(vec![
+                        OracleFinding::new(
+                            "BoolJudgementOracle",
+                            "bool_judgement",
+                            Severity::Minor,
+                            "Unnecessary bool judgement (two constants)",
+                        )
+                        .with_location(location_of(current_function, Some(*pc))),
+                    ])
                 } else {
                     Ok(vec![])
                 }
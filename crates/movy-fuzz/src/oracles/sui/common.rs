@@ -1,25 +1,15 @@
 use move_core_types::language_storage::ModuleId;
 use move_core_types::{account_address::AccountAddress, identifier::Identifier};
-use movy_types::input::FunctionIdent;
+use movy_types::{input::FunctionIdent, oracle::FindingLocation};
 
-pub fn format_vulnerability_info(
-    base: &str,
-    current_function: Option<&(ModuleId, String)>,
-    pc: Option<u16>,
-) -> String {
-    let mut parts = vec![base.to_string()];
-    if let Some((module_id, function)) = current_function {
-        parts.push(format!(
-            "location={}::{}::{}",
-            module_id.address().to_canonical_string(true),
-            module_id.name(),
-            function
-        ));
+pub fn location_of(current_function: Option<&FunctionIdent>, pc: Option<u16>) -> FindingLocation {
+    match current_function {
+        Some(function) => FindingLocation::function(function, pc),
+        None => FindingLocation {
+            offset: pc,
+            ..Default::default()
+        },
     }
-    if let Some(pc) = pc {
-        parts.push(format!("pc={}", pc));
-    }
-    parts.join(" | ")
 }
 
 pub fn to_module_func(fid: &FunctionIdent) -> Option<(ModuleId, String)> {
@@ -0,0 +1,369 @@
+use std::{fmt::Display, str::FromStr};
+
+use move_core_types::language_storage::ModuleId;
+use movy_types::{
+    abi::MoveModuleId,
+    input::{MoveAddress, MoveStructTag},
+    oracle::Severity,
+};
+use serde::{Deserialize, Serialize};
+
+use super::abort_code::{AbortClassification, AbortKind};
+
+/// User supplied oracle configuration, loaded from `--oracle-config` and
+/// persisted in the fuzz metadata so that replays see the same rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OracleConfig {
+    /// Report arithmetic, out of bound vector and missing dynamic field aborts. Off by default
+    /// since random inputs trip over them all the time.
+    #[serde(default)]
+    pub report_aborts: bool,
+    /// Aborts that are part of the business logic and should never be reported.
+    #[serde(default)]
+    pub expected_aborts: Vec<ExpectedAbort>,
+    /// With `report_aborts`, also report plain `MoveAbort`s that are not in `expected_aborts`.
+    #[serde(default)]
+    pub report_unlisted_aborts: bool,
+    /// Invariants checked against the decoded events of every successful execution.
+    #[serde(default)]
+    pub event_invariants: Vec<EventInvariant>,
+}
+
+/// An allowlist entry, every given field must match.
+///
+/// `module` is either a bare module name (`pool`) or `<address>::<module>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExpectedAbort {
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub code: Option<u64>,
+    #[serde(default)]
+    pub constant: Option<String>,
+    #[serde(default)]
+    pub kind: Option<AbortKind>,
+}
+
+/// A declarative invariant over a single event type.
+///
+/// `event` is `Name`, `module::Name` or `<address>::module::Name`. For every matching
+/// event where all `when` predicates hold, all `require` predicates must hold too. An
+/// empty `require` forbids the event altogether, e.g. an event emitted in a transaction
+/// with `$sender == $attacker`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventInvariant {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub event: String,
+    #[serde(default)]
+    pub when: Vec<EventPredicate>,
+    #[serde(default)]
+    pub require: Vec<EventPredicate>,
+    #[serde(default)]
+    pub severity: Option<Severity>,
+}
+
+/// `<field> <op> <rhs>`, where `field` is a dotted path into the decoded event, or
+/// `$sender` for the sender of the transaction which emitted it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPredicate {
+    pub field: String,
+    pub op: CompareOp,
+    pub rhs: EventOperand,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Display for CompareOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Either another field of the same event, or a literal. The literals `"$attacker"` and
+/// `"$sender"` are replaced by the attacker address of the campaign and the sender of the
+/// transaction respectively.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EventOperand {
+    Field { field: String },
+    Literal(serde_json::Value),
+}
+
+impl Display for EventOperand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Field { field } => f.write_str(field),
+            Self::Literal(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl Display for EventPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.field, self.op, self.rhs)
+    }
+}
+
+impl EventInvariant {
+    pub fn matches_type(&self, ty: &MoveStructTag) -> bool {
+        let parts: Vec<_> = self.event.split("::").collect();
+        match parts.as_slice() {
+            [name] => *name == ty.name,
+            [module, name] => *module == ty.module && *name == ty.name,
+            [address, module, name] => {
+                MoveAddress::from_str(address).is_ok_and(|a| a == ty.address)
+                    && *module == ty.module
+                    && *name == ty.name
+            }
+            _ => false,
+        }
+    }
+}
+
+fn module_matches(pattern: &str, module: &ModuleId) -> bool {
+    if let Ok(id) = MoveModuleId::from_str(pattern) {
+        id.module_address == MoveAddress::from(*module.address())
+            && id.module_name == module.name().as_str()
+    } else {
+        pattern == module.name().as_str()
+    }
+}
+
+impl ExpectedAbort {
+    pub fn matches(&self, abort: &AbortClassification) -> bool {
+        if let Some(module) = &self.module
+            && !module_matches(module, &abort.module)
+        {
+            return false;
+        }
+        if let Some(function) = &self.function
+            && function != &abort.function
+        {
+            return false;
+        }
+        if let Some(code) = self.code
+            && abort.code != Some(code)
+        {
+            return false;
+        }
+        if let Some(constant) = &self.constant
+            && abort.constant.as_ref() != Some(constant)
+        {
+            return false;
+        }
+        if let Some(kind) = self.kind
+            && abort.kind != kind
+        {
+            return false;
+        }
+        true
+    }
+}
+
+impl OracleConfig {
+    pub fn is_expected_abort(&self, abort: &AbortClassification) -> bool {
+        self.expected_aborts.iter().any(|e| e.matches(abort))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    };
+    use movy_types::{
+        input::{MoveAddress, MoveStructTag},
+        oracle::Severity,
+    };
+    use serde_json::json;
+
+    use super::{CompareOp, EventInvariant, EventOperand, ExpectedAbort, OracleConfig};
+    use crate::oracles::sui::abort_code::{AbortClassification, AbortKind};
+
+    fn abort(kind: AbortKind) -> AbortClassification {
+        AbortClassification {
+            module: ModuleId::new(
+                AccountAddress::from_hex_literal("0x42").unwrap(),
+                Identifier::new("pool").unwrap(),
+            ),
+            function: "swap".to_string(),
+            pc: 12,
+            code: Some(3),
+            constant: Some("EInsufficientLiquidity".to_string()),
+            constant_index: Some(0),
+            line: Some(42),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_expected_abort_matches() {
+        let abort = abort(AbortKind::Business);
+
+        assert!(ExpectedAbort::default().matches(&abort));
+        assert!(
+            ExpectedAbort {
+                module: Some("pool".to_string()),
+                function: Some("swap".to_string()),
+                code: Some(3),
+                constant: Some("EInsufficientLiquidity".to_string()),
+                kind: Some(AbortKind::Business),
+            }
+            .matches(&abort)
+        );
+        assert!(
+            ExpectedAbort {
+                module: Some("0x42::pool".to_string()),
+                ..Default::default()
+            }
+            .matches(&abort)
+        );
+
+        // Every given field has to match
+        assert!(
+            !ExpectedAbort {
+                module: Some("0x43::pool".to_string()),
+                ..Default::default()
+            }
+            .matches(&abort)
+        );
+        assert!(
+            !ExpectedAbort {
+                module: Some("pool".to_string()),
+                code: Some(4),
+                ..Default::default()
+            }
+            .matches(&abort)
+        );
+        assert!(
+            !ExpectedAbort {
+                constant: Some("ENotOwner".to_string()),
+                ..Default::default()
+            }
+            .matches(&abort)
+        );
+        assert!(
+            !ExpectedAbort {
+                kind: Some(AbortKind::Arithmetic),
+                ..Default::default()
+            }
+            .matches(&abort)
+        );
+    }
+
+    #[test]
+    fn test_is_expected_abort() {
+        let config = OracleConfig {
+            expected_aborts: vec![
+                ExpectedAbort {
+                    function: Some("withdraw".to_string()),
+                    ..Default::default()
+                },
+                ExpectedAbort {
+                    kind: Some(AbortKind::Business),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert!(config.is_expected_abort(&abort(AbortKind::Business)));
+        assert!(!config.is_expected_abort(&abort(AbortKind::Arithmetic)));
+        assert!(!OracleConfig::default().is_expected_abort(&abort(AbortKind::Business)));
+    }
+
+    #[test]
+    fn test_parse_event_invariants() {
+        let config: OracleConfig = serde_json::from_value(json!({
+            "event_invariants": [
+                {
+                    "name": "attacker never swaps",
+                    "event": "pool::Swapped",
+                    "when": [{ "field": "$sender", "op": "eq", "rhs": "$attacker" }],
+                    "severity": "Critical"
+                },
+                {
+                    "event": "0x42::pool::Withdrawn",
+                    "require": [
+                        { "field": "amount", "op": "le", "rhs": { "field": "reserve.value" } },
+                        { "field": "amount", "op": "gt", "rhs": 0 }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+        assert!(config.expected_aborts.is_empty());
+        let [forbidden, bounded] = config.event_invariants.as_slice() else {
+            panic!("expect 2 invariants, got {:?}", config.event_invariants);
+        };
+
+        assert_eq!(forbidden.name.as_deref(), Some("attacker never swaps"));
+        assert_eq!(forbidden.severity, Some(Severity::Critical));
+        assert!(forbidden.require.is_empty());
+        assert_eq!(forbidden.when[0].to_string(), "$sender == \"$attacker\"");
+
+        assert_eq!(bounded.name, None);
+        assert_eq!(bounded.severity, None);
+        assert!(bounded.when.is_empty());
+        assert_eq!(bounded.require[0].op, CompareOp::Le);
+        assert!(
+            matches!(&bounded.require[0].rhs, EventOperand::Field { field } if field == "reserve.value")
+        );
+        assert_eq!(bounded.require[0].to_string(), "amount <= reserve.value");
+        assert_eq!(bounded.require[1].op, CompareOp::Gt);
+        assert!(matches!(&bounded.require[1].rhs, EventOperand::Literal(v) if v == &json!(0)));
+
+        // Both the event type and the operators are mandatory
+        assert!(serde_json::from_value::<EventInvariant>(json!({ "name": "no event" })).is_err());
+        assert!(
+            serde_json::from_value::<EventInvariant>(json!({
+                "event": "Swapped",
+                "require": [{ "field": "amount", "op": "contains", "rhs": 0 }]
+            }))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_event_invariant_matches_type() {
+        let withdrawn = MoveStructTag {
+            address: MoveAddress::from_str("0x42").unwrap(),
+            module: "pool".to_string(),
+            name: "Withdrawn".to_string(),
+            tys: vec![],
+        };
+        let invariant = |event: &str| -> EventInvariant {
+            serde_json::from_value(json!({ "event": event })).unwrap()
+        };
+
+        assert!(invariant("Withdrawn").matches_type(&withdrawn));
+        assert!(invariant("pool::Withdrawn").matches_type(&withdrawn));
+        assert!(invariant("0x42::pool::Withdrawn").matches_type(&withdrawn));
+        assert!(!invariant("Deposited").matches_type(&withdrawn));
+        assert!(!invariant("vault::Withdrawn").matches_type(&withdrawn));
+        assert!(!invariant("0x43::pool::Withdrawn").matches_type(&withdrawn));
+        assert!(!invariant("not an address::pool::Withdrawn").matches_type(&withdrawn));
+        assert!(!invariant("0x42::pool::Withdrawn::extra").matches_type(&withdrawn));
+    }
+}
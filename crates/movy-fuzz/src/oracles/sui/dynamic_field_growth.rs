@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::debug;
+use move_core_types::{
+    account_address::AccountAddress,
+    annotated_value::{MoveStruct, MoveValue},
+};
+use move_trace_format::format::TraceEvent;
+use serde_json::json;
+
+use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
+use movy_types::{
+    error::MovyError,
+    input::{
+        FunctionIdent, InputArgument, MoveSequence, MoveSequenceCall, SequenceArgument,
+        SuiObjectInputArgument,
+    },
+    oracle::{FindingLocation, OracleFinding, Severity},
+};
+use sui_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    object::Owner,
+    storage::ObjectStore,
+};
+
+use crate::meta::HasFuzzMetadata;
+
+// Calls in one sequence growing an object before it is reported
+const MIN_GROWING_CALLS: u64 = 2;
+// Bound on Field -> child -> Field ... ownership chains
+const MAX_OWNER_DEPTH: usize = 8;
+
+/// Whether every call taking the object added a child which none removed, i.e. the children
+/// grow linearly with the calls instead of staying bounded like inserting then removing, or
+/// inserting only when absent.
+fn grows_per_call(calls: u64, added: u64, removed: u64) -> bool {
+    calls >= MIN_GROWING_CALLS && added.saturating_sub(removed) >= calls
+}
+
+fn collect_uids(value: &MoveValue, out: &mut Vec<AccountAddress>) {
+    match value {
+        MoveValue::Struct(st) => collect_struct_uids(st, out),
+        MoveValue::Vector(vs) => {
+            for v in vs.iter() {
+                collect_uids(v, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `UID { id: ID { bytes: address } }`, including the ones of nested `Table`s and `Bag`s.
+fn collect_struct_uids(st: &MoveStruct, out: &mut Vec<AccountAddress>) {
+    if st.type_.address == AccountAddress::TWO
+        && st.type_.module.as_str() == "object"
+        && st.type_.name.as_str() == "UID"
+    {
+        if let Some((_, MoveValue::Struct(id))) = st.fields.first()
+            && let Some((_, MoveValue::Address(addr))) = id.fields.first()
+        {
+            out.push(*addr);
+        }
+        return;
+    }
+    for (_, v) in st.fields.iter() {
+        collect_uids(v, out);
+    }
+}
+
+/// Counts the child objects (dynamic fields, `Table`/`Bag` entries) created and removed under
+/// each shared object input within one execution, and reports the objects every call of the
+/// sequence grows, i.e. attackers can bloat them for free by repeating the call.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicFieldGrowthOracle {
+    /// UIDs stored in the shared inputs of the current execution, to the shared object.
+    uids: BTreeMap<ObjectID, ObjectID>,
+    /// The calls of the current execution taking each shared input.
+    calls: BTreeMap<ObjectID, Vec<FunctionIdent>>,
+    max_per_tx: BTreeMap<ObjectID, u64>,
+    reported: BTreeSet<ObjectID>,
+}
+
+impl DynamicFieldGrowthOracle {
+    fn resolve(&self, owners: &BTreeMap<ObjectID, ObjectID>, mut id: ObjectID) -> Option<ObjectID> {
+        for _ in 0..MAX_OWNER_DEPTH {
+            if let Some(shared) = self.uids.get(&id) {
+                return Some(*shared);
+            }
+            id = *owners.get(&id)?;
+        }
+        None
+    }
+}
+
+impl<T, S> SuiGeneralOracle<T, S> for DynamicFieldGrowthOracle
+where
+    S: HasFuzzMetadata,
+    T: ObjectStore,
+{
+    fn pre_execution(
+        &mut self,
+        db: &T,
+        state: &mut S,
+        sequence: &MoveSequence,
+    ) -> Result<(), MovyError> {
+        self.uids.clear();
+        self.calls.clear();
+        for (idx, input) in sequence.inputs.iter().enumerate() {
+            let InputArgument::Object(_, SuiObjectInputArgument::SharedObject { id, .. }) = input
+            else {
+                continue;
+            };
+            let calls = sequence
+                .commands
+                .iter()
+                .filter_map(|cmd| match cmd {
+                    MoveSequenceCall::Call(call)
+                        if call
+                            .arguments
+                            .contains(&SequenceArgument::Input(idx as u16)) =>
+                    {
+                        Some(FunctionIdent::new(
+                            &call.module_id,
+                            &call.module_name,
+                            &call.function,
+                        ))
+                    }
+                    _ => None,
+                })
+                .collect();
+            self.calls.insert(*id, calls);
+            self.uids.insert(*id, *id);
+            let Some(object) = db.get_object(id) else {
+                continue;
+            };
+            if let Some(decoded) = state.fuzz_state().decode_sui_object(&object)? {
+                let mut uids = vec![];
+                collect_struct_uids(&decoded, &mut uids);
+                for uid in uids {
+                    self.uids.insert(ObjectID::from(uid), *id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn event(
+        &mut self,
+        _event: &TraceEvent,
+        _trace_state: &TraceState,
+        _symbol_stack: &ConcolicState,
+        _current_function: Option<&FunctionIdent>,
+        _state: &mut S,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        Ok(vec![])
+    }
+
+    fn done_execution(
+        &mut self,
+        db: &T,
+        _state: &mut S,
+        effects: &TransactionEffects,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        if !effects.status().is_ok() || self.uids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut owners = BTreeMap::new();
+        let created = effects.created();
+        for (oref, owner) in created.iter() {
+            if let Owner::ObjectOwner(parent) = owner {
+                owners.insert(oref.0, ObjectID::from(*parent));
+            }
+        }
+        let mut added: BTreeMap<ObjectID, u64> = BTreeMap::new();
+        for (oref, _) in created.iter() {
+            if !owners.contains_key(&oref.0) {
+                continue;
+            }
+            if let Some(shared) = self.resolve(&owners, oref.0) {
+                *added.entry(shared).or_default() += 1;
+            }
+        }
+
+        // Deleted children are only known by their version before this transaction
+        let deleted: BTreeSet<_> = effects.deleted().into_iter().map(|oref| oref.0).collect();
+        let mut removed: BTreeMap<ObjectID, u64> = BTreeMap::new();
+        for (id, version) in effects.modified_at_versions() {
+            if !deleted.contains(&id) {
+                continue;
+            }
+            let Some(object) = db.get_object_by_key(&id, version) else {
+                continue;
+            };
+            if let Owner::ObjectOwner(parent) = object.owner()
+                && let Some(shared) = self.resolve(&owners, ObjectID::from(*parent))
+            {
+                *removed.entry(shared).or_default() += 1;
+            }
+        }
+
+        let mut findings = vec![];
+        for (shared, count) in added {
+            let max_per_tx = self.max_per_tx.entry(shared).or_default();
+            *max_per_tx = (*max_per_tx).max(count);
+            let removed = removed.get(&shared).copied().unwrap_or_default();
+            let Some(calls) = self.calls.get(&shared) else {
+                continue;
+            };
+            if !grows_per_call(calls.len() as u64, count, removed)
+                || self.reported.contains(&shared)
+            {
+                continue;
+            }
+            debug!(
+                "Shared object {} got {} children from {} calls",
+                shared,
+                count - removed,
+                calls.len()
+            );
+            self.reported.insert(shared);
+            findings.push(
+                OracleFinding::new(
+                    "DynamicFieldGrowthOracle",
+                    "dynamic_field_growth",
+                    Severity::Medium,
+                    "Shared object children grow with every attacker call",
+                )
+                .with_location(FindingLocation::function(
+                    calls.last().expect("grows with calls"),
+                    None,
+                ))
+                .with_extra(json!({
+                    "object": shared.to_canonical_string(true),
+                    "calls": calls.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+                    "children_added": count,
+                    "children_removed": removed,
+                    "max_per_tx": *max_per_tx,
+                })),
+            );
+        }
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::grows_per_call;
+
+    #[test]
+    fn test_grows_per_call() {
+        // Every call adds a child
+        assert!(grows_per_call(3, 3, 0));
+        assert!(grows_per_call(2, 5, 1));
+        // Inserting then removing in the same sequence is bounded
+        assert!(!grows_per_call(4, 2, 2));
+        assert!(!grows_per_call(2, 2, 1));
+        // So is inserting only when absent, whatever the calls
+        assert!(!grows_per_call(3, 1, 0));
+        // A single call says nothing about repeating it
+        assert!(!grows_per_call(1, 1, 0));
+        assert!(!grows_per_call(0, 0, 0));
+    }
+}
@@ -0,0 +1,346 @@
+use std::str::FromStr;
+
+use log::{debug, trace};
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::StructTag,
+    u256::U256,
+};
+use move_trace_format::format::TraceEvent;
+use serde_json::{Value, json};
+
+use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
+use movy_types::{
+    error::MovyError,
+    input::{FunctionIdent, MoveAddress, MoveSequence},
+    oracle::{Event, FindingLocation, OracleFinding, Severity},
+};
+use sui_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    storage::ObjectStore,
+};
+
+use crate::{
+    meta::HasFuzzMetadata,
+    state::{ExtraNonSerdeFuzzState, HasExtraState},
+};
+
+use super::config::{CompareOp, EventInvariant, EventOperand, EventPredicate};
+
+const ATTACKER_VARIABLE: &str = "$attacker";
+const SENDER_VARIABLE: &str = "$sender";
+
+/// The values of the `$` variables predicates may refer to.
+#[derive(Debug, Clone, Copy)]
+struct Variables {
+    attacker: MoveAddress,
+    sender: MoveAddress,
+}
+
+impl Variables {
+    fn get(&self, name: &str) -> Option<MoveAddress> {
+        match name {
+            ATTACKER_VARIABLE => Some(self.attacker),
+            SENDER_VARIABLE => Some(self.sender),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+enum Scalar {
+    Bool(bool),
+    Number(U256),
+    Address(AccountAddress),
+    Text(String),
+}
+
+impl Scalar {
+    fn from_json(value: &Value, vars: &Variables) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(Self::Bool(*b)),
+            Value::Number(n) => n.as_u64().map(|n| Self::Number(U256::from(n))),
+            Value::String(s) => Some(if let Some(v) = vars.get(s) {
+                Self::Address(AccountAddress::from(v))
+            } else if let Ok(n) = U256::from_str(s) {
+                Self::Number(n)
+            } else if let Ok(addr) = AccountAddress::from_hex_literal(s) {
+                Self::Address(addr)
+            } else {
+                Self::Text(s.clone())
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Resolve a dotted path, looking through the `fields` wrapper of nested structs.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = value;
+    for segment in path.split('.') {
+        current = match current.get(segment) {
+            Some(v) => v,
+            None => current.get("fields")?.get(segment)?,
+        };
+    }
+    Some(current)
+}
+
+/// A field of the event, or one of the variables which are not part of it.
+fn field_value(event: &Value, field: &str, vars: &Variables) -> Option<Scalar> {
+    if let Some(v) = vars.get(field) {
+        return Some(Scalar::Address(AccountAddress::from(v)));
+    }
+    Scalar::from_json(lookup(event, field)?, vars)
+}
+
+/// `None` if the predicate can not be evaluated on this event.
+fn evaluate(predicate: &EventPredicate, event: &Value, vars: &Variables) -> Option<bool> {
+    let lhs = field_value(event, &predicate.field, vars)?;
+    let rhs = match &predicate.rhs {
+        EventOperand::Field { field } => field_value(event, field, vars)?,
+        EventOperand::Literal(v) => Scalar::from_json(v, vars)?,
+    };
+    if std::mem::discriminant(&lhs) != std::mem::discriminant(&rhs) {
+        return match predicate.op {
+            CompareOp::Eq => Some(false),
+            CompareOp::Ne => Some(true),
+            _ => None,
+        };
+    }
+    Some(match predicate.op {
+        CompareOp::Eq => lhs == rhs,
+        CompareOp::Ne => lhs != rhs,
+        CompareOp::Lt => lhs < rhs,
+        CompareOp::Le => lhs <= rhs,
+        CompareOp::Gt => lhs > rhs,
+        CompareOp::Ge => lhs >= rhs,
+    })
+}
+
+/// The violated predicates of `invariant`, `None` if it does not apply to this event.
+fn check_invariant<'a>(
+    invariant: &'a EventInvariant,
+    event: &Value,
+    vars: &Variables,
+) -> Option<Vec<&'a EventPredicate>> {
+    for predicate in invariant.when.iter() {
+        if evaluate(predicate, event, vars) != Some(true) {
+            return None;
+        }
+    }
+    if invariant.require.is_empty() {
+        return Some(vec![]);
+    }
+    let violated: Vec<_> = invariant
+        .require
+        .iter()
+        .filter(|p| evaluate(p, event, vars) == Some(false))
+        .collect();
+    if violated.is_empty() {
+        None
+    } else {
+        Some(violated)
+    }
+}
+
+fn to_sui_event(event: &Event, sender: MoveAddress) -> Result<sui_types::event::Event, MovyError> {
+    Ok(sui_types::event::Event {
+        package_id: ObjectID::from(event.ty.address),
+        transaction_module: Identifier::new(event.ty.module.clone())
+            .map_err(|e| MovyError::InvalidIdentifier(e.to_string()))?,
+        sender: sender.into(),
+        type_: StructTag::try_from(event.ty.clone())?,
+        contents: event.contents.clone(),
+    })
+}
+
+/// Checks the user supplied `event_invariants` against the decoded events, which
+/// allows writing invariants for packages that can not be recompiled with
+/// `movy::oracle::Crash` events.
+#[derive(Debug, Clone, Default)]
+pub struct EventInvariantOracle {
+    pub invariants: Vec<EventInvariant>,
+}
+
+impl EventInvariantOracle {
+    pub fn new(invariants: Vec<EventInvariant>) -> Self {
+        Self { invariants }
+    }
+}
+
+impl<T, S, E> SuiGeneralOracle<T, S> for EventInvariantOracle
+where
+    S: HasExtraState<ExtraState = ExtraNonSerdeFuzzState<E>> + HasFuzzMetadata,
+    T: ObjectStore,
+{
+    fn pre_execution(
+        &mut self,
+        _db: &T,
+        _state: &mut S,
+        _sequence: &MoveSequence,
+    ) -> Result<(), MovyError> {
+        Ok(())
+    }
+
+    fn event(
+        &mut self,
+        _event: &TraceEvent,
+        _trace_state: &TraceState,
+        _symbol_stack: &ConcolicState,
+        _current_function: Option<&FunctionIdent>,
+        _state: &mut S,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        Ok(vec![])
+    }
+
+    fn done_execution(
+        &mut self,
+        _db: &T,
+        state: &mut S,
+        effects: &TransactionEffects,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        if self.invariants.is_empty() {
+            return Ok(vec![]);
+        }
+        let Some(global_outcome) = state.extra_state().global_outcome.as_ref() else {
+            return Ok(vec![]);
+        };
+        if !global_outcome.exec.allowed_success {
+            return Ok(vec![]);
+        }
+        let meta = state.fuzz_state();
+        // The gas coin is always owned by the sender as nothing is sponsored
+        let sender = effects
+            .gas_object()
+            .1
+            .get_owner_address()
+            .map(MoveAddress::from)
+            .unwrap_or(meta.attacker);
+        let vars = Variables {
+            attacker: meta.attacker,
+            sender,
+        };
+        let mut findings = vec![];
+        for event in global_outcome.exec.events.iter() {
+            let invariants: Vec<_> = self
+                .invariants
+                .iter()
+                .filter(|i| i.matches_type(&event.ty))
+                .collect();
+            if invariants.is_empty() {
+                continue;
+            }
+            let Some((ty, decoded)) = meta.decode_sui_event(&to_sui_event(event, sender)?)? else {
+                trace!("Event {} can not be decoded", &event.ty);
+                continue;
+            };
+            for invariant in invariants {
+                let Some(violated) = check_invariant(invariant, &decoded, &vars) else {
+                    continue;
+                };
+                debug!(
+                    "Event invariant {:?} violated by {}",
+                    invariant.name,
+                    ty.to_canonical_string(true)
+                );
+                let name = invariant.name.as_deref().unwrap_or(&invariant.event);
+                findings.push(
+                    OracleFinding::new(
+                        "EventInvariantOracle",
+                        "event_invariant",
+                        invariant.severity.clone().unwrap_or(Severity::Major),
+                        format!("Event invariant {} violated", name),
+                    )
+                    .with_location(FindingLocation::module(
+                        ty.address.into(),
+                        ty.module.as_str(),
+                    ))
+                    .with_extra(json!({
+                        "invariant": name,
+                        "event": ty.to_canonical_string(true),
+                        "violated": violated.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                        "fields": decoded,
+                    })),
+                );
+            }
+        }
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use movy_types::input::MoveAddress;
+    use serde_json::{Value, json};
+
+    use super::{Variables, check_invariant};
+    use crate::oracles::sui::config::EventInvariant;
+
+    fn invariant(value: Value) -> EventInvariant {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn violations(
+        invariant: &EventInvariant,
+        event: &Value,
+        vars: &Variables,
+    ) -> Option<Vec<String>> {
+        check_invariant(invariant, event, vars)
+            .map(|violated| violated.iter().map(|p| p.to_string()).collect())
+    }
+
+    #[test]
+    fn test_check_invariant() {
+        let attacker = MoveAddress::from_str("0xa").unwrap();
+        let victim = MoveAddress::from_str("0xb").unwrap();
+        let by_attacker = Variables {
+            attacker,
+            sender: attacker,
+        };
+        let by_victim = Variables {
+            attacker,
+            sender: victim,
+        };
+        let event = json!({
+            "amount": "100",
+            "recipient": attacker.to_string(),
+            "reserve": { "type": "0x2::balance::Balance<0x2::sui::SUI>", "fields": { "value": "50" } },
+        });
+
+        // `$sender` is the sender of the transaction, not a field of the event
+        let forbidden = invariant(json!({
+            "event": "Withdrawn",
+            "when": [{ "field": "$sender", "op": "eq", "rhs": "$attacker" }],
+        }));
+        assert_eq!(violations(&forbidden, &event, &by_attacker), Some(vec![]));
+        assert_eq!(violations(&forbidden, &event, &by_victim), None);
+
+        let own_funds = invariant(json!({
+            "event": "Withdrawn",
+            "require": [{ "field": "recipient", "op": "eq", "rhs": "$sender" }],
+        }));
+        assert_eq!(violations(&own_funds, &event, &by_attacker), None);
+        assert_eq!(
+            violations(&own_funds, &event, &by_victim),
+            Some(vec!["recipient == \"$sender\"".to_string()])
+        );
+
+        // Nested struct fields are looked up through their `fields`, and predicates on
+        // missing fields are never violated
+        let bounded = invariant(json!({
+            "event": "Withdrawn",
+            "require": [
+                { "field": "amount", "op": "le", "rhs": { "field": "reserve.value" } },
+                { "field": "amount", "op": "gt", "rhs": 0 },
+                { "field": "fee", "op": "lt", "rhs": 10 },
+            ],
+        }));
+        assert_eq!(
+            violations(&bounded, &event, &by_attacker),
+            Some(vec!["amount <= reserve.value".to_string()])
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::debug;
+use move_trace_format::{
+    format::{TraceEvent, TraceValue},
+    value::SerializableMoveValue,
+};
+use serde_json::json;
+
+use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
+use movy_types::{
+    error::MovyError,
+    input::{FunctionIdent, MoveSequence},
+    oracle::{FindingLocation, OracleFinding, Severity},
+};
+use sui_types::effects::{TransactionEffects, TransactionEffectsAPI};
+
+// Samples kept per function across executions
+const MAX_SAMPLES: usize = 64;
+// Sizes below this are too noisy to tell linear from superlinear
+const MIN_SIZE: u64 = 4;
+// instructions(k * n) > instructions(n) * k ^ SUPERLINEAR_EXPONENT
+const SUPERLINEAR_EXPONENT: f64 = 1.5;
+// Samples of a function before its gas distribution tells anything
+const MIN_DISTRIBUTION_SAMPLES: usize = 8;
+// A call costing more than GAS_OUTLIER_FACTOR times the median gas of its function
+const GAS_OUTLIER_FACTOR: u64 = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CallSizes {
+    /// Total vector elements in the arguments passed by value.
+    vector_len: u64,
+    /// Largest integer argument, i.e. a potential loop bound.
+    max_integer: u64,
+    /// Total vector elements behind the referenced objects.
+    state_len: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GasSample {
+    sizes: CallSizes,
+    gas: u64,
+    instructions: u64,
+}
+
+#[derive(Debug, Clone)]
+struct OpenCall {
+    function: FunctionIdent,
+    sizes: CallSizes,
+    gas_left: u64,
+    instructions: u64,
+}
+
+fn vector_len(value: &SerializableMoveValue) -> u64 {
+    match value {
+        SerializableMoveValue::Vector(vs) => {
+            vs.len() as u64 + vs.iter().map(vector_len).sum::<u64>()
+        }
+        SerializableMoveValue::Struct(st) => st.fields.iter().map(|(_, v)| vector_len(v)).sum(),
+        _ => 0,
+    }
+}
+
+fn integer(value: &SerializableMoveValue) -> u64 {
+    match value {
+        SerializableMoveValue::U8(v) => *v as u64,
+        SerializableMoveValue::U16(v) => *v as u64,
+        SerializableMoveValue::U32(v) => *v as u64,
+        SerializableMoveValue::U64(v) => *v,
+        _ => 0,
+    }
+}
+
+fn call_sizes(parameters: &[TraceValue]) -> CallSizes {
+    let mut sizes = CallSizes::default();
+    for param in parameters {
+        match param {
+            TraceValue::RuntimeValue { value } => {
+                sizes.vector_len += vector_len(value);
+                sizes.max_integer = sizes.max_integer.max(integer(value));
+            }
+            TraceValue::ImmRef { snapshot, .. } | TraceValue::MutRef { snapshot, .. } => {
+                sizes.state_len += vector_len(snapshot);
+            }
+        }
+    }
+    sizes
+}
+
+/// Whether `large` used superlinearly more instructions than `small` along one size
+/// dimension, all the others being equal.
+fn superlinear(small: &GasSample, large: &GasSample, small_size: u64, large_size: u64) -> bool {
+    if small_size < MIN_SIZE || large_size < small_size * 2 || small.instructions == 0 {
+        return false;
+    }
+    let size_ratio = large_size as f64 / small_size as f64;
+    let instruction_ratio = large.instructions as f64 / small.instructions as f64;
+    instruction_ratio > size_ratio.powf(SUPERLINEAR_EXPONENT)
+}
+
+/// Gas above which a call of the function is an outlier of what was observed so far, none
+/// until there are enough samples.
+fn outlier_threshold(samples: &[GasSample]) -> Option<u64> {
+    if samples.len() < MIN_DISTRIBUTION_SAMPLES {
+        return None;
+    }
+    let mut gas = samples.iter().map(|s| s.gas).collect::<Vec<_>>();
+    gas.sort_unstable();
+    Some(gas[gas.len() / 2].max(1) * GAS_OUTLIER_FACTOR)
+}
+
+/// Measures the gas and instructions of every PTB command and reports functions whose
+/// cost grows superlinearly with attacker controlled inputs (vector lengths or integer
+/// loop bounds), or whose cost grows with the size of the state they are given up to a
+/// point where calls by other users risk to run out of gas.
+#[derive(Debug, Clone, Default)]
+pub struct GasGriefingOracle {
+    samples: BTreeMap<FunctionIdent, Vec<GasSample>>,
+    reported: BTreeSet<(FunctionIdent, &'static str)>,
+    calls: Vec<OpenCall>,
+    depth: usize,
+    instructions: u64,
+    findings: Vec<OracleFinding>,
+}
+
+impl GasGriefingOracle {
+    fn check(&mut self, function: &FunctionIdent, sample: GasSample) {
+        let samples = self.samples.entry(function.clone()).or_default();
+        let dimensions: [(&'static str, fn(&CallSizes) -> u64); 2] = [
+            ("vector_len", |s| s.vector_len),
+            ("max_integer", |s| s.max_integer),
+        ];
+        for (dimension, size) in dimensions {
+            if self.reported.contains(&(function.clone(), dimension)) {
+                continue;
+            }
+            let witness = samples.iter().find(|other| {
+                let equal_others = match dimension {
+                    "vector_len" => other.sizes.max_integer == sample.sizes.max_integer,
+                    _ => other.sizes.vector_len == sample.sizes.vector_len,
+                } && other.sizes.state_len == sample.sizes.state_len;
+                equal_others
+                    && (superlinear(other, &sample, size(&other.sizes), size(&sample.sizes))
+                        || superlinear(&sample, other, size(&sample.sizes), size(&other.sizes)))
+            });
+            if let Some(witness) = witness {
+                debug!("Superlinear gas in {} along {}", function, dimension);
+                self.reported.insert((function.clone(), dimension));
+                self.findings.push(
+                    OracleFinding::new(
+                        "GasGriefingOracle",
+                        "gas_griefing",
+                        Severity::Medium,
+                        "Gas grows superlinearly with attacker controlled input",
+                    )
+                    .with_location(FindingLocation::function(function, None))
+                    .with_extra(json!({
+                        "dimension": dimension,
+                        "samples": [
+                            {"size": size(&witness.sizes), "gas": witness.gas, "instructions": witness.instructions},
+                            {"size": size(&sample.sizes), "gas": sample.gas, "instructions": sample.instructions},
+                        ],
+                    })),
+                );
+            }
+        }
+
+        // Calls on a larger state costing far more than the function usually does, while the
+        // calls on the smallest state seen stay cheap
+        if !self.reported.contains(&(function.clone(), "state_len"))
+            && let Some(threshold) = outlier_threshold(samples)
+            && sample.gas > threshold
+            && let Some(smallest) = samples.iter().min_by_key(|s| s.sizes.state_len)
+            && smallest.sizes.state_len < sample.sizes.state_len
+            && smallest.gas <= threshold
+        {
+            debug!("Gas of {} grows with its state", function);
+            self.reported.insert((function.clone(), "state_len"));
+            self.findings.push(
+                OracleFinding::new(
+                    "GasGriefingOracle",
+                    "gas_griefing",
+                    Severity::Medium,
+                    "Gas grows with shared state, later calls may exceed the gas limit",
+                )
+                .with_location(FindingLocation::function(function, None))
+                .with_extra(json!({
+                    "threshold": threshold,
+                    "samples": [
+                        {"state_len": smallest.sizes.state_len, "gas": smallest.gas, "instructions": smallest.instructions},
+                        {"state_len": sample.sizes.state_len, "gas": sample.gas, "instructions": sample.instructions},
+                    ],
+                })),
+            );
+        }
+
+        if samples.len() >= MAX_SAMPLES {
+            samples.remove(0);
+        }
+        samples.push(sample);
+    }
+}
+
+impl<T, S> SuiGeneralOracle<T, S> for GasGriefingOracle {
+    fn pre_execution(
+        &mut self,
+        _db: &T,
+        _state: &mut S,
+        _sequence: &MoveSequence,
+    ) -> Result<(), MovyError> {
+        self.calls.clear();
+        self.depth = 0;
+        self.instructions = 0;
+        self.findings.clear();
+        Ok(())
+    }
+
+    fn event(
+        &mut self,
+        event: &TraceEvent,
+        _trace_state: &TraceState,
+        _symbol_stack: &ConcolicState,
+        _current_function: Option<&FunctionIdent>,
+        _state: &mut S,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        match event {
+            TraceEvent::OpenFrame { frame, gas_left } => {
+                if self.depth == 0 && !frame.is_native {
+                    self.calls.push(OpenCall {
+                        function: FunctionIdent::new(
+                            &(*frame.module.address()).into(),
+                            frame.module.name().as_str(),
+                            &frame.function_name,
+                        ),
+                        sizes: call_sizes(&frame.parameters),
+                        gas_left: *gas_left,
+                        instructions: self.instructions,
+                    });
+                }
+                self.depth += 1;
+            }
+            TraceEvent::CloseFrame { gas_left, .. } => {
+                self.depth = self.depth.saturating_sub(1);
+                if self.depth == 0
+                    && let Some(call) = self.calls.pop()
+                {
+                    let sample = GasSample {
+                        sizes: call.sizes,
+                        gas: call.gas_left.saturating_sub(*gas_left),
+                        instructions: self.instructions - call.instructions,
+                    };
+                    self.check(&call.function, sample);
+                }
+            }
+            TraceEvent::Instruction { .. } => {
+                self.instructions += 1;
+            }
+            _ => {}
+        }
+        Ok(vec![])
+    }
+
+    fn done_execution(
+        &mut self,
+        _db: &T,
+        _state: &mut S,
+        effects: &TransactionEffects,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        let findings = std::mem::take(&mut self.findings);
+        if !findings.is_empty() {
+            debug!(
+                "Computation cost of the griefing execution: {}",
+                effects.gas_cost_summary().computation_cost
+            );
+        }
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use movy_types::input::{FunctionIdent, MoveAddress};
+
+    use super::{CallSizes, GasGriefingOracle, GasSample};
+
+    fn ident() -> FunctionIdent {
+        FunctionIdent::new(&MoveAddress::from_str("0xb0b").unwrap(), "pool", "settle")
+    }
+
+    fn sample(vector_len: u64, state_len: u64, gas: u64, instructions: u64) -> GasSample {
+        GasSample {
+            sizes: CallSizes {
+                vector_len,
+                max_integer: 0,
+                state_len,
+            },
+            gas,
+            instructions,
+        }
+    }
+
+    fn rules(oracle: &GasGriefingOracle) -> Vec<String> {
+        oracle
+            .findings
+            .iter()
+            .map(|f| {
+                f.extra["dimension"]
+                    .as_str()
+                    .unwrap_or("state_len")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_superlinear_vector_len() {
+        let mut oracle = GasGriefingOracle::default();
+        oracle.check(&ident(), sample(4, 0, 100, 16));
+        oracle.check(&ident(), sample(16, 0, 1600, 256));
+        assert_eq!(rules(&oracle), vec!["vector_len"]);
+
+        let mut oracle = GasGriefingOracle::default();
+        oracle.check(&ident(), sample(4, 0, 100, 40));
+        oracle.check(&ident(), sample(16, 0, 400, 160));
+        assert!(rules(&oracle).is_empty());
+    }
+
+    #[test]
+    fn test_state_growth_outlier() {
+        let mut oracle = GasGriefingOracle::default();
+        for state_len in 0..8 {
+            oracle.check(&ident(), sample(0, state_len, 1000 + state_len, 10));
+        }
+        // Within the usual cost of the function
+        oracle.check(&ident(), sample(0, 100, 3000, 10));
+        assert!(rules(&oracle).is_empty());
+        oracle.check(&ident(), sample(0, 1000, 50_000, 10));
+        assert_eq!(rules(&oracle), vec!["state_len"]);
+        assert_eq!(oracle.findings[0].extra["threshold"], 4016);
+    }
+
+    #[test]
+    fn test_state_growth_needs_distribution() {
+        let mut oracle = GasGriefingOracle::default();
+        oracle.check(&ident(), sample(0, 1, 1000, 10));
+        oracle.check(&ident(), sample(0, 1000, 50_000, 10));
+        assert!(rules(&oracle).is_empty());
+    }
+}
@@ -0,0 +1,617 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::debug;
+use move_core_types::{
+    account_address::AccountAddress,
+    annotated_value::{MoveStruct, MoveValue},
+    language_storage::StructTag,
+};
+use move_trace_format::{
+    format::{TraceEvent, TraceValue},
+    value::SerializableMoveValue,
+};
+use serde_json::{Value, json};
+
+use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
+use movy_types::{
+    abi::{MoveAbiSignatureToken, MoveAbility},
+    error::MovyError,
+    input::{
+        FunctionIdent, InputArgument, MoveAddress, MoveSequence, MoveStructTag, MoveTypeTag,
+        SuiObjectInputArgument,
+    },
+    oracle::{FindingLocation, OracleFinding, Severity},
+};
+use sui_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    storage::ObjectStore,
+};
+
+use crate::meta::{FuzzMetadata, HasFuzzMetadata};
+
+#[derive(Debug, Clone)]
+struct HotPotatoReceipt {
+    ty: StructTag,
+    value: SerializableMoveValue,
+    producer: FunctionIdent,
+    // Balances of the shared objects the receipt refers to, as passed to its producer
+    before: BTreeMap<ObjectID, BTreeMap<String, u64>>,
+    // The innermost function taking the receipt by value, i.e. the one destroying it, with
+    // the depth of its frame
+    consumer: Option<(FunctionIdent, usize)>,
+    consumed: bool,
+    // Balances right after the consumer returned, from the next frame borrowing the object
+    after: BTreeMap<ObjectID, BTreeMap<String, u64>>,
+}
+
+/// Tracks hot potato receipts returned by the commands of a PTB until they are destroyed,
+/// and checks that the shared objects they refer to get back the balances they had before
+/// the receipt was produced once its consumer returns, and that receipts are only
+/// destroyed by their designated consumers.
+#[derive(Debug, Clone, Default)]
+pub struct HotPotatoOracle {
+    shared: BTreeSet<ObjectID>,
+    frames: Vec<(FunctionIdent, Vec<TraceValue>)>,
+    receipts: Vec<HotPotatoReceipt>,
+}
+
+fn is_hot_potato(meta: &FuzzMetadata, ty: &StructTag) -> bool {
+    meta.get_abilities(&ty.address.into(), ty.module.as_str(), ty.name.as_str())
+        .is_some_and(|a| a.is_hot_potato() && !a.contains(MoveAbility::KEY))
+}
+
+fn collect_addresses(value: &SerializableMoveValue, out: &mut BTreeSet<AccountAddress>) {
+    match value {
+        SerializableMoveValue::Address(addr) => {
+            out.insert(*addr);
+        }
+        SerializableMoveValue::Struct(st) => {
+            for (_, v) in st.fields.iter() {
+                collect_addresses(v, out);
+            }
+        }
+        SerializableMoveValue::Vector(vs) => {
+            for v in vs.iter() {
+                collect_addresses(v, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `id: UID { id: ID { bytes: address } }` is always the first field of an object.
+fn object_id(value: &SerializableMoveValue) -> Option<AccountAddress> {
+    let mut current = value;
+    for _ in 0..3 {
+        match current {
+            SerializableMoveValue::Struct(st) => current = &st.fields.first()?.1,
+            SerializableMoveValue::Address(addr) => return Some(*addr),
+            _ => return None,
+        }
+    }
+    match current {
+        SerializableMoveValue::Address(addr) => Some(*addr),
+        _ => None,
+    }
+}
+
+fn collect_balances(value: &MoveValue, path: String, out: &mut BTreeMap<String, u64>) {
+    match value {
+        MoveValue::Struct(st) => collect_struct_balances(st, path, out),
+        MoveValue::Vector(vs) => {
+            for (idx, v) in vs.iter().enumerate() {
+                collect_balances(v, format!("{}[{}]", path, idx), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_struct_balances(st: &MoveStruct, path: String, out: &mut BTreeMap<String, u64>) {
+    if st.type_.address == AccountAddress::TWO
+        && st.type_.module.as_str() == "balance"
+        && st.type_.name.as_str() == "Balance"
+    {
+        if let Some((_, MoveValue::U64(v))) = st.fields.first() {
+            *out.entry(path).or_default() += *v;
+        }
+        return;
+    }
+    for (name, v) in st.fields.iter() {
+        collect_balances(v, format!("{}.{}", path, name), out);
+    }
+}
+
+/// Same as [`collect_struct_balances`] on a traced value.
+fn collect_trace_balances(
+    value: &SerializableMoveValue,
+    path: String,
+    out: &mut BTreeMap<String, u64>,
+) {
+    match value {
+        SerializableMoveValue::Struct(st) => {
+            if st.type_.address == AccountAddress::TWO
+                && st.type_.module.as_str() == "balance"
+                && st.type_.name.as_str() == "Balance"
+            {
+                if let Some((_, SerializableMoveValue::U64(v))) = st.fields.first() {
+                    *out.entry(path).or_default() += *v;
+                }
+                return;
+            }
+            for (name, v) in st.fields.iter() {
+                collect_trace_balances(v, format!("{}.{}", path, name), out);
+            }
+        }
+        SerializableMoveValue::Vector(vs) => {
+            for (idx, v) in vs.iter().enumerate() {
+                collect_trace_balances(v, format!("{}[{}]", path, idx), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn trace_balances(value: &SerializableMoveValue) -> BTreeMap<String, u64> {
+    let mut out = BTreeMap::new();
+    collect_trace_balances(value, String::new(), &mut out);
+    out
+}
+
+fn lost_balances(before: &BTreeMap<String, u64>, after: &BTreeMap<String, u64>) -> Vec<Value> {
+    before
+        .iter()
+        .filter_map(|(path, amount)| {
+            let now = after.get(path).copied().unwrap_or_default();
+            (now < *amount).then(|| json!({"field": path, "before": amount, "after": now}))
+        })
+        .collect()
+}
+
+fn object_balances<T: ObjectStore>(
+    db: &T,
+    meta: &FuzzMetadata,
+    id: &ObjectID,
+) -> Option<BTreeMap<String, u64>> {
+    let object = db.get_object(id)?;
+    let decoded = meta.decode_sui_object(&object).ok()??;
+    let mut out = BTreeMap::new();
+    collect_struct_balances(&decoded, String::new(), &mut out);
+    Some(out)
+}
+
+fn same_function(meta: &FuzzMetadata, lhs: &FunctionIdent, rhs: &FunctionIdent) -> bool {
+    let normalize = |addr: &MoveAddress| {
+        meta.module_address_to_package
+            .get(addr)
+            .copied()
+            .unwrap_or(*addr)
+    };
+    lhs.0.module_name == rhs.0.module_name
+        && lhs.1 == rhs.1
+        && normalize(&lhs.0.module_address) == normalize(&rhs.0.module_address)
+}
+
+/// Functions taking the receipt by value with its concrete type, i.e. the ones supposed to
+/// destroy it.
+fn expected_consumers(meta: &FuzzMetadata, ty: &StructTag) -> Vec<FunctionIdent> {
+    let token = MoveAbiSignatureToken::from_type_tag_lossy(&MoveTypeTag::Struct(
+        MoveStructTag::from(ty.clone()),
+    ));
+    meta.type_graph
+        .find_consumers(&token, false)
+        .into_iter()
+        .filter(|(_, f)| {
+            f.parameters.iter().any(|p| {
+                !matches!(
+                    p,
+                    MoveAbiSignatureToken::Reference(_)
+                        | MoveAbiSignatureToken::MutableReference(_)
+                        | MoveAbiSignatureToken::TypeParameter(_, _)
+                ) && p.partial_extract_ty_args(&token).is_some()
+            })
+        })
+        .map(|(m, f)| FunctionIdent(m.clone(), f.name.clone()))
+        .collect()
+}
+
+impl HotPotatoOracle {
+    fn open_frame(
+        &mut self,
+        ident: FunctionIdent,
+        params: &[TraceValue],
+        is_receipt: impl Fn(&StructTag) -> bool,
+    ) {
+        let depth = self.frames.len();
+        for param in params.iter() {
+            let TraceValue::RuntimeValue { value } = param else {
+                // The first borrow of an object after a consumer returned shows its balances
+                let Some(id) = object_id(param.snapshot()).map(ObjectID::from) else {
+                    continue;
+                };
+                for receipt in self.receipts.iter_mut().filter(|r| r.consumed) {
+                    if receipt.before.contains_key(&id) && !receipt.after.contains_key(&id) {
+                        receipt.after.insert(id, trace_balances(param.snapshot()));
+                    }
+                }
+                continue;
+            };
+            let SerializableMoveValue::Struct(st) = value else {
+                continue;
+            };
+            if !is_receipt(&st.type_) {
+                continue;
+            }
+            // Wrappers pass the receipt on, the last one to take it destroys it
+            if let Some(receipt) = self
+                .receipts
+                .iter_mut()
+                .find(|r| !r.consumed && &r.value == value)
+            {
+                debug!("Receipt {} passed to {}", st.type_, &ident);
+                receipt.consumer = Some((ident.clone(), depth));
+            }
+        }
+        self.frames.push((ident, params.to_vec()));
+    }
+
+    fn close_frame(&mut self, returns: &[TraceValue], is_receipt: impl Fn(&StructTag) -> bool) {
+        let Some((ident, params)) = self.frames.pop() else {
+            return;
+        };
+        let depth = self.frames.len();
+        for receipt in self.receipts.iter_mut().filter(|r| !r.consumed) {
+            if receipt
+                .consumer
+                .as_ref()
+                .is_none_or(|(consumer, at)| *at != depth || consumer != &ident)
+            {
+                continue;
+            }
+            if returns.iter().any(|ret| ret.snapshot() == &receipt.value) {
+                // Handed back to the caller
+                receipt.consumer = None;
+            } else {
+                debug!("Receipt {} consumed by {}", receipt.ty, &ident);
+                receipt.consumed = true;
+            }
+        }
+        // Only receipts passed by the PTB itself are tracked
+        if depth != 0 {
+            return;
+        }
+        for ret in returns.iter() {
+            let TraceValue::RuntimeValue {
+                value: value @ SerializableMoveValue::Struct(st),
+            } = ret
+            else {
+                continue;
+            };
+            if !is_receipt(&st.type_)
+                || self
+                    .receipts
+                    .iter()
+                    .any(|r| !r.consumed && r.consumer.is_none() && &r.value == value)
+            {
+                continue;
+            }
+            let mut referred = BTreeSet::new();
+            collect_addresses(value, &mut referred);
+            for param in params.iter() {
+                if let TraceValue::MutRef { snapshot, .. } = param
+                    && let Some(id) = object_id(snapshot)
+                {
+                    referred.insert(id);
+                }
+            }
+            let mut before = BTreeMap::new();
+            for param in params.iter() {
+                if matches!(param, TraceValue::RuntimeValue { .. }) {
+                    continue;
+                }
+                if let Some(id) = object_id(param.snapshot())
+                    && referred.contains(&id)
+                    && self.shared.contains(&ObjectID::from(id))
+                {
+                    let balances = trace_balances(param.snapshot());
+                    if !balances.is_empty() {
+                        before.insert(ObjectID::from(id), balances);
+                    }
+                }
+            }
+            debug!("Receipt {} created by {}", st.type_, &ident);
+            self.receipts.push(HotPotatoReceipt {
+                ty: st.type_.clone(),
+                value: value.clone(),
+                producer: ident.clone(),
+                before,
+                consumer: None,
+                consumed: false,
+                after: BTreeMap::new(),
+            });
+        }
+    }
+
+    /// `(receipt, object, lost balances)` of the objects not paid back once the consumers
+    /// returned, `current` giving the balances of objects not borrowed afterwards.
+    fn unpaid(
+        &self,
+        current: impl Fn(&ObjectID) -> Option<BTreeMap<String, u64>>,
+    ) -> Vec<(&HotPotatoReceipt, ObjectID, Vec<Value>)> {
+        let mut out = vec![];
+        for receipt in self.receipts.iter().filter(|r| r.consumed) {
+            for (id, before) in receipt.before.iter() {
+                let Some(after) = receipt.after.get(id).cloned().or_else(|| current(id)) else {
+                    continue;
+                };
+                let lost = lost_balances(before, &after);
+                if !lost.is_empty() {
+                    out.push((receipt, *id, lost));
+                }
+            }
+        }
+        out
+    }
+}
+
+impl<T, S> SuiGeneralOracle<T, S> for HotPotatoOracle
+where
+    S: HasFuzzMetadata,
+    T: ObjectStore,
+{
+    fn pre_execution(
+        &mut self,
+        _db: &T,
+        _state: &mut S,
+        sequence: &MoveSequence,
+    ) -> Result<(), MovyError> {
+        self.frames.clear();
+        self.receipts.clear();
+        self.shared.clear();
+        for input in sequence.inputs.iter() {
+            if let InputArgument::Object(_, SuiObjectInputArgument::SharedObject { id, .. }) = input
+            {
+                self.shared.insert(*id);
+            }
+        }
+        Ok(())
+    }
+
+    fn event(
+        &mut self,
+        event: &TraceEvent,
+        _trace_state: &TraceState,
+        _symbol_stack: &ConcolicState,
+        _current_function: Option<&FunctionIdent>,
+        state: &mut S,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        let meta = state.fuzz_state();
+        match event {
+            TraceEvent::OpenFrame { frame, .. } => {
+                let ident = FunctionIdent::new(
+                    &(*frame.module.address()).into(),
+                    frame.module.name().as_str(),
+                    &frame.function_name,
+                );
+                self.open_frame(ident, &frame.parameters, |ty| is_hot_potato(meta, ty));
+            }
+            TraceEvent::CloseFrame { return_, .. } => {
+                self.close_frame(return_, |ty| is_hot_potato(meta, ty));
+            }
+            _ => {}
+        }
+        Ok(vec![])
+    }
+
+    fn done_execution(
+        &mut self,
+        db: &T,
+        state: &mut S,
+        effects: &TransactionEffects,
+    ) -> Result<Vec<OracleFinding>, MovyError> {
+        if !effects.status().is_ok() {
+            return Ok(vec![]);
+        }
+        let meta = state.fuzz_state();
+        let mut findings = vec![];
+        for receipt in self.receipts.iter().filter(|r| r.consumed) {
+            let Some((consumer, _)) = &receipt.consumer else {
+                continue;
+            };
+            let expected = expected_consumers(meta, &receipt.ty);
+            if !expected.is_empty() && !expected.iter().any(|f| same_function(meta, f, consumer)) {
+                findings.push(
+                    OracleFinding::new(
+                        "HotPotatoOracle",
+                        "hot_potato",
+                        Severity::Major,
+                        "Hot potato destroyed by an unexpected consumer",
+                    )
+                    .with_location(FindingLocation::function(consumer, None))
+                    .with_extra(json!({
+                        "receipt": receipt.ty.to_canonical_string(true),
+                        "producer": receipt.producer.to_string(),
+                        "expected_consumers": expected.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+                    })),
+                );
+            }
+        }
+
+        for (receipt, id, lost) in self.unpaid(|id| object_balances(db, meta, id)) {
+            let Some((consumer, _)) = &receipt.consumer else {
+                continue;
+            };
+            findings.push(
+                OracleFinding::new(
+                    "HotPotatoOracle",
+                    "hot_potato",
+                    Severity::Critical,
+                    "Shared object was not paid back when the hot potato was destroyed",
+                )
+                .with_location(FindingLocation::function(consumer, None))
+                .with_extra(json!({
+                    "receipt": receipt.ty.to_canonical_string(true),
+                    "producer": receipt.producer.to_string(),
+                    "object": id.to_canonical_string(true),
+                    "balances": lost,
+                })),
+            );
+        }
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::StructTag,
+    };
+    use move_trace_format::{
+        format::{Location, TraceValue},
+        value::{SerializableMoveValue, SimplifiedMoveStruct},
+    };
+    use movy_types::input::{FunctionIdent, MoveAddress};
+    use sui_types::base_types::ObjectID;
+
+    use super::HotPotatoOracle;
+
+    fn tag(address: AccountAddress, module: &str, name: &str) -> StructTag {
+        StructTag {
+            address,
+            module: Identifier::new(module).unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params: vec![],
+        }
+    }
+
+    fn value(ty: StructTag, fields: Vec<(&str, SerializableMoveValue)>) -> SerializableMoveValue {
+        SerializableMoveValue::Struct(SimplifiedMoveStruct {
+            type_: ty,
+            fields: fields
+                .into_iter()
+                .map(|(name, v)| (Identifier::new(name).unwrap(), v))
+                .collect(),
+        })
+    }
+
+    fn pool_id() -> AccountAddress {
+        AccountAddress::from_hex_literal("0xb0b").unwrap()
+    }
+
+    fn pool(reserve: u64) -> TraceValue {
+        let id = value(
+            tag(AccountAddress::TWO, "object", "UID"),
+            vec![(
+                "id",
+                value(
+                    tag(AccountAddress::TWO, "object", "ID"),
+                    vec![("bytes", SerializableMoveValue::Address(pool_id()))],
+                ),
+            )],
+        );
+        let balance = value(
+            tag(AccountAddress::TWO, "balance", "Balance"),
+            vec![("value", SerializableMoveValue::U64(reserve))],
+        );
+        TraceValue::MutRef {
+            location: Location::Local(0, 0),
+            snapshot: value(
+                tag(pool_id(), "pool", "Pool"),
+                vec![("id", id), ("reserve", balance)],
+            ),
+        }
+    }
+
+    fn receipt(amount: u64) -> TraceValue {
+        TraceValue::RuntimeValue {
+            value: value(
+                tag(pool_id(), "pool", "Receipt"),
+                vec![
+                    ("pool", SerializableMoveValue::Address(pool_id())),
+                    ("amount", SerializableMoveValue::U64(amount)),
+                ],
+            ),
+        }
+    }
+
+    fn ident(module: &str, function: &str) -> FunctionIdent {
+        FunctionIdent::new(&MoveAddress::from_str("0xb0b").unwrap(), module, function)
+    }
+
+    fn is_receipt(ty: &StructTag) -> bool {
+        ty.name.as_str() == "Receipt"
+    }
+
+    /// `borrow(pool)`, then `wrapper(receipt)` calling `repay(pool, receipt)` with the pool
+    /// holding `repaid` when `repay` returns, then `skim(pool)`.
+    fn flash_loan(repaid: u64) -> HotPotatoOracle {
+        let mut oracle = HotPotatoOracle::default();
+        oracle.shared.insert(ObjectID::from(pool_id()));
+
+        oracle.open_frame(ident("pool", "borrow"), &[pool(1000)], is_receipt);
+        oracle.close_frame(&[receipt(100)], is_receipt);
+        oracle.open_frame(ident("router", "wrapper"), &[receipt(100)], is_receipt);
+        oracle.open_frame(
+            ident("pool", "repay"),
+            &[pool(900), receipt(100)],
+            is_receipt,
+        );
+        oracle.close_frame(&[], is_receipt);
+        oracle.close_frame(&[], is_receipt);
+        oracle.open_frame(ident("pool", "skim"), &[pool(repaid)], is_receipt);
+        oracle.close_frame(&[], is_receipt);
+        oracle
+    }
+
+    #[test]
+    fn test_consumer_behind_wrapper() {
+        let oracle = flash_loan(1000);
+        assert_eq!(oracle.receipts.len(), 1);
+        let receipt = &oracle.receipts[0];
+        assert!(receipt.consumed);
+        assert_eq!(receipt.producer, ident("pool", "borrow"));
+        assert_eq!(
+            receipt.consumer.as_ref().map(|(f, _)| f),
+            Some(&ident("pool", "repay"))
+        );
+        // Drained after the repay, by a later command not tied to the receipt
+        assert!(oracle.unpaid(|_| Some(BTreeMap::new())).is_empty());
+    }
+
+    #[test]
+    fn test_unpaid_pool() {
+        let oracle = flash_loan(950);
+        let unpaid = oracle.unpaid(|_| None);
+        assert_eq!(unpaid.len(), 1);
+        assert_eq!(unpaid[0].1, ObjectID::from(pool_id()));
+        assert_eq!(unpaid[0].2[0]["before"], 1000);
+        assert_eq!(unpaid[0].2[0]["after"], 950);
+    }
+
+    #[test]
+    fn test_receipt_handed_back() {
+        let mut oracle = HotPotatoOracle::default();
+        oracle.shared.insert(ObjectID::from(pool_id()));
+        oracle.open_frame(ident("pool", "borrow"), &[pool(1000)], is_receipt);
+        oracle.close_frame(&[receipt(100)], is_receipt);
+        // Inspecting the receipt and returning it does not destroy it
+        oracle.open_frame(ident("router", "touch"), &[receipt(100)], is_receipt);
+        oracle.close_frame(&[receipt(100)], is_receipt);
+        assert_eq!(oracle.receipts.len(), 1);
+        assert!(!oracle.receipts[0].consumed);
+        assert!(oracle.receipts[0].consumer.is_none());
+
+        // Without a later borrow, the balances at the end of the transaction are used
+        oracle.open_frame(
+            ident("pool", "repay"),
+            &[pool(900), receipt(100)],
+            is_receipt,
+        );
+        oracle.close_frame(&[], is_receipt);
+        let current = BTreeMap::from([(".reserve".to_string(), 1010)]);
+        assert!(oracle.unpaid(|_| Some(current.clone())).is_empty());
+    }
+}
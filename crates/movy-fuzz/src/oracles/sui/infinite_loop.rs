@@ -2,7 +2,6 @@ use std::collections::BTreeMap;
 
 use move_binary_format::file_format::Bytecode;
 use move_trace_format::format::TraceEvent;
-use serde_json::json;
 
 use movy_replay::tracer::{
     concolic::{ConcolicState, SymbolValue},
@@ -18,7 +17,7 @@ use sui_types::effects::TransactionEffects;
 
 use crate::utils::hash_to_u64;
 
-use super::common::to_module_func;
+use super::common::{location_of, to_module_func};
 
 #[derive(Debug, Default, Clone)]
 pub struct InfiniteLoopOracle {
@@ -79,16 +78,10 @@ impl<T, S> SuiGeneralOracle<T, S> for InfiniteLoopOracle {
                                 } else {
                                     if count.1 >= 1000 {
                                         count.1 = 0;
-                                        let info = json!({
-                                            "oracle": "InfiniteLoopOracle",
-                                            "function": current_function.as_ref().map(|f| f.to_string()),
-                                            "pc": pc,
-                                        });
-                                        return Ok(vec![OracleFinding {
-                                            oracle: "InfiniteLoopOracle".to_string(),
-                                            severity: Severity::Major,
-                                            extra: info,
-                                        }]);
+                                        return Ok(vec![
+                                            OracleFinding::new("InfiniteLoopOracle", "infinite_loop", Severity::Major, "Potential infinite loop, branch condition repeated 1000 times")
+                                                .with_location(location_of(current_function, Some(*pc))),
+                                        ]);
                                     }
                                     count.1 += 1;
                                 }
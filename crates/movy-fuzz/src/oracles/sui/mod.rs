@@ -1,5 +1,11 @@
+mod abort_code;
 mod bool_judgement;
 mod common;
+mod config;
+mod dynamic_field_growth;
+mod event_invariant;
+mod gas_griefing;
+mod hot_potato;
 mod infinite_loop;
 mod overflow;
 mod precision_loss;
@@ -7,10 +13,18 @@ mod proceeds;
 mod type_conversion;
 mod typed_bug;
 
+pub use abort_code::{AbortClassification, AbortCodeOracle, AbortKind, classify_abort};
 pub use bool_judgement::BoolJudgementOracle;
+pub use config::{
+    CompareOp, EventInvariant, EventOperand, EventPredicate, ExpectedAbort, OracleConfig,
+};
+pub use dynamic_field_growth::DynamicFieldGrowthOracle;
+pub use event_invariant::EventInvariantOracle;
+pub use gas_griefing::GasGriefingOracle;
+pub use hot_potato::HotPotatoOracle;
 pub use infinite_loop::InfiniteLoopOracle;
 pub use overflow::OverflowOracle;
 pub use precision_loss::PrecisionLossOracle;
-pub use proceeds::ProceedsOracle;
+pub use proceeds::{ProceedsOracle, get_balance_changes_from_effect};
 pub use type_conversion::TypeConversionOracle;
 pub use typed_bug::TypedBugOracle;
@@ -1,7 +1,6 @@
 use move_binary_format::file_format::Bytecode;
 use move_core_types::u256::U256;
 use move_trace_format::format::{TraceEvent, TraceValue};
-use serde_json::json;
 
 use movy_replay::tracer::{
     concolic::{ConcolicState, value_bitwidth, value_to_u256},
@@ -15,6 +14,8 @@ use movy_types::{
 };
 use sui_types::effects::TransactionEffects;
 
+use super::common::location_of;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct OverflowOracle;
 
@@ -72,16 +73,15 @@ impl<T, S> SuiGeneralOracle<T, S> for OverflowOracle {
                 };
 
                 if overflow {
-                    let info = json!({
-                        "oracle": "OverflowOracle",
-                        "function": current_function.as_ref().map(|f| f.to_string()),
-                        "pc": pc,
-                    });
-                    return Ok(vec![OracleFinding {
-                        oracle: "OverflowOracle".to_string(),
-                        severity: Severity::Medium,
-                        extra: info,
-                    }]);
+                    return Ok(vec![
+                        OracleFinding::new(
+                            "OverflowOracle",
+                            "overflow",
+                            Severity::Medium,
+                            "Arithmetic overflow",
+                        )
+                        .with_location(location_of(current_function, Some(*pc))),
+                    ]);
                 }
                 Ok(vec![])
             }
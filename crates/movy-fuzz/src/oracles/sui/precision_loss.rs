@@ -1,5 +1,4 @@
 use move_trace_format::format::TraceEvent;
-use serde_json::json;
 
 use movy_replay::tracer::{
     concolic::{ConcolicState, SymbolValue},
@@ -10,6 +9,8 @@ use movy_types::{error::MovyError, input::MoveSequence, oracle::OracleFinding};
 use sui_types::effects::TransactionEffects;
 use z3::ast::Ast;
 
+use super::common::location_of;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct PrecisionLossOracle;
 
@@ -52,16 +53,15 @@ impl<T, S> SuiGeneralOracle<T, S> for PrecisionLossOracle {
                     _ => false,
                 };
                 if loss {
-                    let info = json!({
-                        "oracle": "PrecisionLossOracle",
-                        "function": current_function.as_ref().map(|f| f.to_string()),
-                        "pc": pc,
-                    });
-                    Ok(vec![OracleFinding {
-                        oracle: "PrecisionLossOracle".to_string(),
-                        severity: movy_types::oracle::Severity::Medium,
-                        extra: info,
-                    }])
+                    Ok(vec![
+                        OracleFinding::new(
+                            "PrecisionLossOracle",
+                            "precision_loss",
+                            movy_types::oracle::Severity::Medium,
+                            "Precision loss from multiplication after division",
+                        )
+                        .with_location(location_of(current_function, Some(*pc))),
+                    ])
                 } else {
                     Ok(vec![])
                 }
@@ -4,7 +4,7 @@ use std::{
 };
 
 use log::debug;
-use move_trace_format::{format::TraceEvent};
+use move_trace_format::format::TraceEvent;
 
 use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
 use movy_types::{
@@ -249,14 +249,15 @@ where
                 debug!("Balance change: {:?}", bc);
                 if bc.iter().all(|c| c.amount >= 0) && bc.iter().any(|c| c.amount > 0) {
                     debug!("Found proceeds: {:?}", bc);
-                    let finding = OracleFinding {
-                        oracle: "ProceedsOracle".to_string(),
-                        severity: movy_types::oracle::Severity::Critical,
-                        extra: json!({
-                            "message": "Positive proceeds detected",
-                            "balance_changes": bc,
-                        }),
-                    };
+                    let finding = OracleFinding::new(
+                        "ProceedsOracle",
+                        "proceeds",
+                        movy_types::oracle::Severity::Critical,
+                        "Positive proceeds detected",
+                    )
+                    .with_extra(json!({
+                        "balance_changes": bc,
+                    }));
                     return Ok(vec![finding]);
                 }
             }
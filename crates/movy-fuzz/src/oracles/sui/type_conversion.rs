@@ -2,13 +2,14 @@ use move_binary_format::file_format::Bytecode;
 use move_trace_format::format::TraceEvent;
 use movy_types::input::MoveSequence;
 use movy_types::oracle::OracleFinding;
-use serde_json::json;
 
 use movy_replay::tracer::concolic::value_bitwidth;
 use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
 use movy_types::error::MovyError;
 use sui_types::effects::TransactionEffects;
 
+use super::common::location_of;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct TypeConversionOracle;
 
@@ -49,16 +50,15 @@ impl<T, S> SuiGeneralOracle<T, S> for TypeConversionOracle {
                     _ => false,
                 };
                 if unnecessary {
-                    let info = json!({
-                        "oracle": "TypeConversionOracle",
-                        "function": current_function.as_ref().map(|f| f.to_string()),
-                        "pc": pc,
-                    });
-                    return Ok(vec![OracleFinding {
-                        oracle: "TypeConversionOracle".to_string(),
-                        severity: movy_types::oracle::Severity::Minor,
-                        extra: info,
-                    }]);
+                    return Ok(vec![
+                        OracleFinding::new(
+                            "TypeConversionOracle",
+                            "type_conversion",
+                            movy_types::oracle::Severity::Minor,
+                            "Unnecessary type conversion",
+                        )
+                        .with_location(location_of(current_function, Some(*pc))),
+                    ]);
                 }
                 Ok(vec![])
             }
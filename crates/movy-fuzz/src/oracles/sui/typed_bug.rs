@@ -1,5 +1,5 @@
 use log::{debug, trace};
-use move_trace_format::{format::TraceEvent};
+use move_trace_format::format::TraceEvent;
 
 use movy_replay::tracer::{concolic::ConcolicState, oracle::SuiGeneralOracle, trace::TraceState};
 use movy_types::{error::MovyError, input::MoveSequence, oracle::OracleFinding};
@@ -73,13 +73,17 @@ where
                     ..
                 } if *code == TYPED_BUG_ABORT_CODE => {
                     debug!("Typed bug abort detected: code {}", code);
-                    return Ok(vec![OracleFinding {
-                        oracle: "TypedBugOracle".to_string(),
-                        severity: movy_types::oracle::Severity::Critical,
-                        extra: json!({
+                    return Ok(vec![
+                        OracleFinding::new(
+                            "TypedBugOracle",
+                            "typed_bug",
+                            movy_types::oracle::Severity::Critical,
+                            "Typed bug abort",
+                        )
+                        .with_extra(json!({
                             "abort_code": code,
-                        }),
-                    }]);
+                        })),
+                    ]);
                 }
                 _ => return Ok(Vec::new()),
             }
@@ -93,13 +97,17 @@ where
         for event in &global_outcome.exec.events {
             if event.ty.module == "oracle" && event.ty.name == "Crash" {
                 debug!("Typed bug event detected: {:?}", event);
-                return Ok(vec![OracleFinding {
-                    oracle: "TypedBugOracle".to_string(),
-                    severity: movy_types::oracle::Severity::Critical,
-                    extra: json!({
+                return Ok(vec![
+                    OracleFinding::new(
+                        "TypedBugOracle",
+                        "typed_bug",
+                        movy_types::oracle::Severity::Critical,
+                        "Typed bug event emitted",
+                    )
+                    .with_extra(json!({
                         "event": event,
-                    }),
-                }]);
+                    })),
+                ]);
             }
         }
         Ok(vec![])
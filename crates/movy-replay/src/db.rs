@@ -11,7 +11,7 @@ use movy_sui::{
 use movy_types::{
     abi::MovePackageAbi,
     error::MovyError,
-    input::{MoveAddress, MoveTypeTag},
+    input::{InputArgument, MoveAddress, MoveSequence, MoveTypeTag, SuiObjectInputArgument},
     object::{MoveObjectInfo, MoveOwner},
 };
 use sui_types::{
@@ -94,6 +94,44 @@ pub trait ObjectStoreInfo {
     fn get_move_object_info(&self, object_id: MoveAddress) -> Result<MoveObjectInfo, MovyError>;
     fn get_package_info(&self, object_id: MoveAddress)
     -> Result<Option<MovePackageAbi>, MovyError>;
+
+    /// Refresh the references to owned objects of a sequence, which may come from an
+    /// earlier checkpoint, and check every object exists and is used as it is owned.
+    fn refresh_object_inputs(&self, sequence: &mut MoveSequence) -> Result<(), MovyError> {
+        for input in sequence.inputs.iter_mut() {
+            let InputArgument::Object(_, object) = input else {
+                continue;
+            };
+            if let SuiObjectInputArgument::Receiving(_) = object {
+                return Err(MovyError::Unsupported(format!(
+                    "receiving object {} in seeds",
+                    object.id()
+                )));
+            }
+            let info = self.get_move_object_info(object.id().into()).map_err(|e| {
+                MovyError::InvalidSeed(format!("object {} is missing: {}", object.id(), e))
+            })?;
+            let refreshed = match (&*object, &info.owner) {
+                (
+                    SuiObjectInputArgument::ImmOrOwnedObject(_),
+                    MoveOwner::AddressOwner(_) | MoveOwner::Immutable,
+                ) => SuiObjectInputArgument::ImmOrOwnedObject(info.sui_reference()),
+                (SuiObjectInputArgument::SharedObject { .. }, MoveOwner::Shared { .. }) => {
+                    continue;
+                }
+                (_, owner) => {
+                    return Err(MovyError::InvalidSeed(format!(
+                        "object {} is used as {} but owned by {:?}",
+                        object.id(),
+                        object,
+                        owner
+                    )));
+                }
+            };
+            *object = refreshed;
+        }
+        Ok(())
+    }
 }
 
 impl<T: ObjectStore> ObjectStoreInfo for T {
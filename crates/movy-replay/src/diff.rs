@@ -0,0 +1,402 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use itertools::Itertools;
+use move_core_types::{
+    annotated_value::MoveValue, identifier::Identifier, language_storage::StructTag,
+};
+use movy_types::{error::MovyError, input::MoveAddress};
+use serde::{Deserialize, Serialize};
+use sui_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    object::{Object, Owner},
+    storage::{ObjectStore, WriteKind},
+};
+
+use crate::layout::ObjectLayoutResolver;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectChangeKind {
+    Created,
+    Mutated,
+    Unwrapped,
+    Deleted,
+    Wrapped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDiff {
+    pub id: MoveAddress,
+    pub kind: ObjectChangeKind,
+    pub ty: Option<String>,
+    pub owner: Option<String>,
+    /// The parent object if this is a dynamic field
+    pub dynamic_field_of: Option<MoveAddress>,
+    /// Whether both versions are decoded, otherwise only the object is known to change
+    pub decoded: bool,
+    pub changes: Vec<FieldChange>,
+}
+
+impl Display for ObjectDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {} {}",
+            self.kind,
+            self.id,
+            self.ty.as_deref().unwrap_or("<package>")
+        )?;
+        if let Some(parent) = &self.dynamic_field_of {
+            write!(f, " (dynamic field of {})", parent)?;
+        } else if let Some(owner) = &self.owner {
+            write!(f, " ({})", owner)?;
+        }
+        if !self.decoded {
+            write!(f, "\n  <not decoded>")?;
+        }
+        for change in self.changes.iter() {
+            write!(
+                f,
+                "\n  {}: {} -> {}",
+                change.path,
+                change.before.as_deref().unwrap_or("-"),
+                change.after.as_deref().unwrap_or("-")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn value_string(value: &MoveValue) -> String {
+    match value {
+        MoveValue::Bool(v) => v.to_string(),
+        MoveValue::U8(v) => v.to_string(),
+        MoveValue::U16(v) => v.to_string(),
+        MoveValue::U32(v) => v.to_string(),
+        MoveValue::U64(v) => v.to_string(),
+        MoveValue::U128(v) => v.to_string(),
+        MoveValue::U256(v) => v.to_string(),
+        MoveValue::Address(v) | MoveValue::Signer(v) => v.to_canonical_string(true),
+        MoveValue::Vector(vs) => match bytes(vs) {
+            Some(bytes) => format!("0x{}", bytes.iter().map(|b| format!("{:02x}", b)).join("")),
+            None => format!("[{}]", vs.iter().map(value_string).join(", ")),
+        },
+        MoveValue::Struct(st) => format!(
+            "{} {{ {} }}",
+            st.type_.name,
+            st.fields
+                .iter()
+                .map(|(name, v)| format!("{}: {}", name, value_string(v)))
+                .join(", ")
+        ),
+        MoveValue::Variant(v) => format!(
+            "{}::{} {{ {} }}",
+            v.type_.name,
+            v.variant_name,
+            v.fields
+                .iter()
+                .map(|(name, v)| format!("{}: {}", name, value_string(v)))
+                .join(", ")
+        ),
+    }
+}
+
+fn bytes(values: &[MoveValue]) -> Option<Vec<u8>> {
+    if values.is_empty() {
+        return None;
+    }
+    values
+        .iter()
+        .map(|v| match v {
+            MoveValue::U8(b) => Some(*b),
+            _ => None,
+        })
+        .collect()
+}
+
+// Struct fields and enum variant fields, as long as the two sides are comparable
+fn fields(value: &MoveValue) -> Option<(String, &[(Identifier, MoveValue)])> {
+    match value {
+        MoveValue::Struct(st) => Some((st.type_.to_canonical_string(true), &st.fields)),
+        MoveValue::Variant(v) => Some((
+            format!("{}::{}", v.type_.to_canonical_string(true), v.variant_name),
+            &v.fields,
+        )),
+        _ => None,
+    }
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn diff_fields(
+    path: &str,
+    before: &[(Identifier, MoveValue)],
+    after: &[(Identifier, MoveValue)],
+    changes: &mut Vec<FieldChange>,
+) {
+    let names = before
+        .iter()
+        .chain(after.iter())
+        .map(|(name, _)| name)
+        .unique();
+    for name in names {
+        let b = before.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+        let a = after.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+        diff_value(&field_path(path, name.as_str()), b, a, changes);
+    }
+}
+
+/// Structurally diff two values into leaf changes, `None` stands for a missing value.
+pub fn diff_value(
+    path: &str,
+    before: Option<&MoveValue>,
+    after: Option<&MoveValue>,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (before, after) {
+        (Some(b), Some(a)) if b == a => {}
+        (Some(MoveValue::Vector(b)), Some(MoveValue::Vector(a)))
+            if bytes(b).is_none() && bytes(a).is_none() =>
+        {
+            for idx in 0..b.len().max(a.len()) {
+                diff_value(
+                    &format!("{}[{}]", path, idx),
+                    b.get(idx),
+                    a.get(idx),
+                    changes,
+                );
+            }
+        }
+        _ => {
+            let b = before.and_then(fields);
+            let a = after.and_then(fields);
+            match (b, a) {
+                (Some((bt, bf)), Some((at, af))) if bt == at => diff_fields(path, bf, af, changes),
+                (Some((_, bf)), None) if after.is_none() => diff_fields(path, bf, &[], changes),
+                (None, Some((_, af))) if before.is_none() => diff_fields(path, &[], af, changes),
+                _ => changes.push(FieldChange {
+                    path: path.to_string(),
+                    before: before.map(value_string),
+                    after: after.map(value_string),
+                }),
+            }
+        }
+    }
+}
+
+fn is_dynamic_field(ty: &StructTag) -> bool {
+    MoveAddress::from(ty.address) == MoveAddress::two()
+        && ty.module.as_str() == "dynamic_field"
+        && ty.name.as_str() == "Field"
+}
+
+/// Decode the objects changed by a transaction before and after it, and diff them field by
+/// field. The `db` holds the state before the transaction and `written` the objects after it.
+pub fn diff_transaction_objects<T: ObjectStore>(
+    db: &T,
+    effects: &TransactionEffects,
+    written: &BTreeMap<ObjectID, Object>,
+) -> Result<Vec<ObjectDiff>, MovyError> {
+    let versions: BTreeMap<_, _> = effects.modified_at_versions().into_iter().collect();
+    let mut changed = vec![];
+    for (oref, _, kind) in effects.all_changed_objects() {
+        let kind = match kind {
+            WriteKind::Create => ObjectChangeKind::Created,
+            WriteKind::Mutate => ObjectChangeKind::Mutated,
+            WriteKind::Unwrap => ObjectChangeKind::Unwrapped,
+        };
+        changed.push((oref.0, kind));
+    }
+    for oref in effects.deleted() {
+        changed.push((oref.0, ObjectChangeKind::Deleted));
+    }
+    for oref in effects.wrapped() {
+        changed.push((oref.0, ObjectChangeKind::Wrapped));
+    }
+
+    let mut resolver = ObjectLayoutResolver::new(db);
+    let mut diffs = vec![];
+    for (id, kind) in changed {
+        let before = versions
+            .get(&id)
+            .and_then(|version| db.get_object_by_key(&id, *version));
+        let after = written.get(&id);
+        let Some(object) = after.or(before.as_ref()) else {
+            log::warn!("object {} changed but found in neither versions", id);
+            continue;
+        };
+        let tag: Option<StructTag> = object.data.try_as_move().map(|o| o.type_().clone().into());
+        let dynamic_field_of = match object.owner() {
+            Owner::ObjectOwner(parent) if tag.as_ref().is_some_and(is_dynamic_field) => {
+                Some(MoveAddress::from(*parent))
+            }
+            _ => None,
+        };
+
+        let mut decoded = true;
+        let mut decode = |object: Option<&Object>| match object {
+            Some(object) => match resolver.decode_object(object) {
+                Ok(Some(st)) => Some(MoveValue::Struct(st)),
+                Ok(None) => {
+                    decoded = false;
+                    None
+                }
+                Err(e) => {
+                    log::warn!("fail to decode object {} due to {}", id, e);
+                    decoded = false;
+                    None
+                }
+            },
+            None => None,
+        };
+        let before_value = decode(before.as_ref());
+        let after_value = decode(after);
+        let mut changes = vec![];
+        if decoded {
+            diff_value(
+                "",
+                before_value.as_ref(),
+                after_value.as_ref(),
+                &mut changes,
+            );
+        }
+        diffs.push(ObjectDiff {
+            id: id.into(),
+            kind,
+            ty: tag.map(|t| t.to_canonical_string(true)),
+            owner: Some(object.owner().to_string()),
+            dynamic_field_of,
+            decoded,
+            changes,
+        });
+    }
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod test {
+    use move_core_types::{
+        account_address::AccountAddress,
+        annotated_value::{MoveStruct, MoveValue},
+        identifier::Identifier,
+        language_storage::StructTag,
+    };
+
+    use crate::diff::{FieldChange, diff_value};
+
+    fn object(name: &str, fields: Vec<(&str, MoveValue)>) -> MoveValue {
+        MoveValue::Struct(MoveStruct {
+            type_: StructTag {
+                address: AccountAddress::TWO,
+                module: Identifier::new("pool").unwrap(),
+                name: Identifier::new(name).unwrap(),
+                type_params: vec![],
+            },
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (Identifier::new(name).unwrap(), value))
+                .collect(),
+        })
+    }
+
+    fn pool(balance: u64, x: u64, name: Vec<u8>, items: Vec<u64>) -> MoveValue {
+        object(
+            "Pool",
+            vec![
+                ("balance", MoveValue::U64(balance)),
+                ("inner", object("Inner", vec![("x", MoveValue::U64(x))])),
+                (
+                    "name",
+                    MoveValue::Vector(name.into_iter().map(MoveValue::U8).collect()),
+                ),
+                (
+                    "items",
+                    MoveValue::Vector(items.into_iter().map(MoveValue::U64).collect()),
+                ),
+            ],
+        )
+    }
+
+    fn change(path: &str, before: Option<&str>, after: Option<&str>) -> FieldChange {
+        FieldChange {
+            path: path.to_string(),
+            before: before.map(|s| s.to_string()),
+            after: after.map(|s| s.to_string()),
+        }
+    }
+
+    fn diff(before: Option<&MoveValue>, after: Option<&MoveValue>) -> Vec<FieldChange> {
+        let mut changes = vec![];
+        diff_value("", before, after, &mut changes);
+        changes
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let before = pool(10, 1, vec![1, 2], vec![1]);
+        assert!(diff(Some(&before), Some(&before.clone())).is_empty());
+    }
+
+    #[test]
+    fn test_diff_fields() {
+        let before = pool(10, 1, vec![1, 2], vec![1]);
+        let after = pool(12, 2, vec![1, 3], vec![1, 5]);
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            vec![
+                change("balance", Some("10"), Some("12")),
+                change("inner.x", Some("1"), Some("2")),
+                change("name", Some("0x0102"), Some("0x0103")),
+                change("items[1]", None, Some("5")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_created_and_deleted() {
+        let value = pool(10, 1, vec![], vec![]);
+        let fields = [
+            ("balance", "10"),
+            ("inner.x", "1"),
+            ("name", "[]"),
+            ("items", "[]"),
+        ];
+        assert_eq!(
+            diff(None, Some(&value)),
+            fields
+                .iter()
+                .map(|(path, v)| change(path, None, Some(v)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            diff(Some(&value), None),
+            fields
+                .iter()
+                .map(|(path, v)| change(path, Some(v), None))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_diff_type_changed() {
+        let before = object("Inner", vec![("x", MoveValue::U64(1))]);
+        let after = object("Outer", vec![("x", MoveValue::U64(1))]);
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            vec![change("", Some("Inner { x: 1 }"), Some("Outer { x: 1 }"))]
+        );
+    }
+}
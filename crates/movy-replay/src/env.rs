@@ -7,14 +7,14 @@ use std::{
 use color_eyre::eyre::eyre;
 use itertools::Itertools;
 use movy_sui::{
-    compile::SuiCompiledPackage,
+    compile::{SuiCompiledPackage, SuiPackageSources},
     database::cache::ObjectSuiStoreCommit,
     rpc::graphql::{GraphQlClient, OwnerKind},
 };
 use movy_types::{
     abi::{MOVY_INIT, MovePackageAbi},
     error::MovyError,
-    input::{MoveAddress, MoveStructTag},
+    input::{InputArgument, MoveAddress, MoveSequence, MoveSequenceCall, MoveStructTag},
 };
 use sui_types::{
     Identifier,
@@ -117,9 +117,19 @@ impl<
         epoch: u64,
         epoch_ms: u64,
         gas: ObjectID,
-    ) -> Result<(MoveAddress, MovePackageAbi, MovePackageAbi, Vec<String>), MovyError> {
+    ) -> Result<
+        (
+            MoveAddress,
+            MovePackageAbi,
+            MovePackageAbi,
+            Vec<String>,
+            SuiPackageSources,
+        ),
+        MovyError,
+    > {
         log::info!("Compiling {} with non-test mode...", path.display());
-        let abi_result = SuiCompiledPackage::build_all_unpublished_from_folder(path, false)?;
+        let (abi_result, _, sources) =
+            SuiCompiledPackage::build_all_unpublished_with_dependencies_from_folder(path, false)?;
         let mut non_test_abi = abi_result.abi()?;
         log::info!("Compiling {} with test mode...", path.display());
         let compiled_result = SuiCompiledPackage::build_all_unpublished_from_folder(path, true)?;
@@ -188,7 +198,7 @@ impl<
 
         non_test_abi.published_at(address.into());
         abi.published_at(address.into());
-        Ok((address.into(), abi, non_test_abi, package_names))
+        Ok((address.into(), abi, non_test_abi, package_names, sources))
     }
 
     pub async fn export_abi(&self) -> Result<BTreeMap<MoveAddress, MovePackageAbi>, MovyError> {
@@ -257,6 +267,22 @@ impl<
         Ok(())
     }
 
+    /// Load the packages called and the objects used by a sequence, e.g. a seed, such that
+    /// the abis exported afterwards cover it.
+    pub async fn load_sequence(&self, sequence: &MoveSequence) -> Result<(), MovyError> {
+        for cmd in sequence.commands.iter() {
+            if let MoveSequenceCall::Call(call) = cmd {
+                self.deploy_address(call.module_id).await?;
+            }
+        }
+        for input in sequence.inputs.iter() {
+            if let InputArgument::Object(_, object) = input {
+                self.db.load_object(object.id().into()).await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn all_tys(&self) -> Result<BTreeSet<MoveStructTag>, MovyError> {
         let mut tags = BTreeSet::new();
         for obj in self.db.list_objects().await? {
@@ -1,4 +1,9 @@
-use std::{ops::Deref, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+    str::FromStr,
+    sync::Arc,
+};
 
 use color_eyre::eyre::eyre;
 use itertools::Itertools;
@@ -7,15 +12,19 @@ use move_core_types::account_address::AccountAddress;
 use move_trace_format::{format::MoveTraceBuilder, interface::Tracer};
 use movy_sui::{compile::SuiCompiledPackage, database::cache::ObjectSuiStoreCommit};
 use movy_types::{error::MovyError, object::MoveOwner};
+use serde::{Deserialize, Serialize};
 use sui_types::{
     TypeTag,
     base_types::{ObjectID, SuiAddress},
     committee::ProtocolVersion,
+    digests::{get_mainnet_chain_identifier, get_testnet_chain_identifier},
     effects::{TransactionEffects, TransactionEffectsAPI},
     gas::SuiGasStatus,
     inner_temporary_store::InnerTemporaryStore,
+    is_system_package,
     metrics::LimitsMetrics,
-    object::Owner,
+    move_package::{MovePackage, TypeOrigin},
+    object::{Data, Object, Owner},
     storage::{BackingStore, ObjectStore, WriteKind},
     supported_protocol_versions::{Chain, ProtocolConfig},
     transaction::{
@@ -30,6 +39,86 @@ use crate::{
     tracer::NopTracer,
 };
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuiChain {
+    #[default]
+    Mainnet,
+    Testnet,
+    Devnet,
+}
+
+impl SuiChain {
+    pub fn chain(&self) -> Chain {
+        match self {
+            Self::Mainnet => Chain::Mainnet,
+            Self::Testnet => Chain::Testnet,
+            Self::Devnet => Chain::Unknown,
+        }
+    }
+
+    /// The chain of a network from its identifier, the hex of the first four bytes of its
+    /// genesis checkpoint digest. Networks other than mainnet and testnet are devnets.
+    pub fn from_chain_identifier(identifier: &str) -> Self {
+        if identifier == get_mainnet_chain_identifier().to_string() {
+            Self::Mainnet
+        } else if identifier == get_testnet_chain_identifier().to_string() {
+            Self::Testnet
+        } else {
+            Self::Devnet
+        }
+    }
+}
+
+impl FromStr for SuiChain {
+    type Err = MovyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "devnet" => Ok(Self::Devnet),
+            _ => Err(eyre!("unknown chain {}, expect mainnet, testnet or devnet", s).into()),
+        }
+    }
+}
+
+/// The protocol version and chain whose rules and gas schedule transactions execute with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuiProtocol {
+    pub version: u64,
+    pub chain: SuiChain,
+}
+
+impl Default for SuiProtocol {
+    fn default() -> Self {
+        Self::latest()
+    }
+}
+
+impl SuiProtocol {
+    pub fn latest() -> Self {
+        Self {
+            version: ProtocolVersion::max().as_u64(),
+            chain: SuiChain::Mainnet,
+        }
+    }
+
+    pub fn config(&self) -> Result<ProtocolConfig, MovyError> {
+        ProtocolConfig::get_for_version_if_supported(
+            ProtocolVersion::new(self.version),
+            self.chain.chain(),
+        )
+        .ok_or_else(|| {
+            eyre!(
+                "protocol version {} is not supported, the max is {}",
+                self.version,
+                ProtocolVersion::max().as_u64()
+            )
+            .into()
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct SuiExecutor<T> {
     pub db: T,
@@ -62,8 +151,12 @@ where
     T: ObjectStore + BackingStore + ObjectSuiStoreCommit + ObjectStoreMintObject + ObjectStoreInfo,
 {
     pub fn new(db: T) -> Result<Self, MovyError> {
-        let protocol_config: ProtocolConfig =
-            ProtocolConfig::get_for_version(ProtocolVersion::max(), Chain::Mainnet);
+        Self::new_with_protocol(db, SuiProtocol::latest())
+    }
+
+    pub fn new_with_protocol(db: T, protocol: SuiProtocol) -> Result<Self, MovyError> {
+        log::debug!("Executing with {:?}", protocol);
+        let protocol_config = protocol.config()?;
         let registry = prometheus::Registry::new();
         let metrics = Arc::new(LimitsMetrics::new(&registry));
         let executor = sui_execution::executor(&protocol_config, false)?;
@@ -76,6 +169,15 @@ where
         })
     }
 
+    /// Switch to another protocol, e.g. the one of the next epoch when replaying across an
+    /// epoch change.
+    pub fn set_protocol(&mut self, protocol: SuiProtocol) -> Result<(), MovyError> {
+        log::debug!("Switching to {:?}", protocol);
+        self.protocol_config = protocol.config()?;
+        self.executor = sui_execution::executor(&self.protocol_config, false)?;
+        Ok(())
+    }
+
     pub fn run_tx_trace<R: Tracer>(
         &self,
         tx_data: TransactionData,
@@ -322,4 +424,113 @@ where
             Err(eyre!("fail to deploy").into())
         }
     }
+
+    /// Replace the code of an already published package in place, keeping its id, version
+    /// and linkage, such that transactions calling it run the patched code instead. Fails
+    /// when the patch depends on a package missing from the linkage table.
+    pub fn patch_package(
+        &self,
+        package_id: ObjectID,
+        patch: SuiCompiledPackage,
+    ) -> Result<(), MovyError> {
+        let object = self
+            .db
+            .get_object(&package_id)
+            .ok_or_else(|| eyre!("package {} not found", package_id))?;
+        let package = object
+            .data
+            .try_as_package()
+            .ok_or_else(|| eyre!("{} is not a package", package_id))?;
+        let original_id = package.original_package_id();
+
+        let mut type_origin_table = package.type_origin_table().clone();
+        let mut module_map = BTreeMap::new();
+        let (mut modules, _) = patch.into_deployment();
+
+        // The linkage table is kept, so the patch can only use the packages the on-chain
+        // one already links against
+        let linkage = package.linkage_table();
+        let unlinked = modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .module_handles()
+                    .iter()
+                    .map(move |handle| *module.address_identifier_at(handle.address))
+                    .filter(move |address| address != module.address())
+            })
+            .filter(|address| {
+                !is_system_package(*address) && !linkage.contains_key(&ObjectID::from(*address))
+            })
+            .collect::<BTreeSet<_>>();
+        if !unlinked.is_empty() {
+            return Err(eyre!(
+                "patch of {} depends on {}, which the on-chain package does not link against, patches keep the linkage table of the package they replace",
+                package_id,
+                unlinked
+                    .iter()
+                    .map(|address| address.to_canonical_string(true))
+                    .join(", ")
+            )
+            .into());
+        }
+
+        for module in modules.iter_mut() {
+            // rebase to the original id, which is what the types of the package refer to
+            let self_address = *module.address();
+            for ident in module.address_identifiers.iter_mut() {
+                if *ident == self_address {
+                    *ident = original_id.into();
+                }
+            }
+
+            let module_name = module.name().to_string();
+            let datatypes = module
+                .struct_defs()
+                .iter()
+                .map(|def| def.struct_handle)
+                .chain(module.enum_defs().iter().map(|def| def.enum_handle))
+                .map(|idx| {
+                    module
+                        .identifier_at(module.datatype_handle_at(idx).name)
+                        .to_string()
+                })
+                .collect_vec();
+            for datatype_name in datatypes {
+                if !type_origin_table
+                    .iter()
+                    .any(|t| t.module_name == module_name && t.datatype_name == datatype_name)
+                {
+                    debug!("New type {}::{} in patch", module_name, datatype_name);
+                    type_origin_table.push(TypeOrigin {
+                        module_name: module_name.clone(),
+                        datatype_name,
+                        package: original_id,
+                    });
+                }
+            }
+
+            let mut buf = vec![];
+            module.serialize_with_version(module.version, &mut buf)?;
+            module_map.insert(module_name, buf);
+        }
+
+        let patched = MovePackage::new(
+            package.id(),
+            package.version(),
+            module_map,
+            self.protocol_config.max_move_package_size(),
+            type_origin_table,
+            package.linkage_table().clone(),
+        )
+        .map_err(|e| eyre!("fail to patch {}: {}", package_id, e))?;
+        debug!(
+            "Patched package {} (original id {})",
+            package_id, original_id
+        );
+        self.db.commit_single_object(Object::new_package_from_data(
+            Data::Package(patched),
+            object.previous_transaction,
+        ))
+    }
 }
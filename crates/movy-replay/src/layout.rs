@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_core_types::{
+    annotated_value::{MoveDatatypeLayout, MoveStruct, MoveStructLayout, MoveTypeLayout},
+    language_storage::{StructTag, TypeTag},
+};
+use movy_types::{
+    abi::{MoveAbiSignatureToken, MoveModuleId, MovePackageAbi, MoveStructAbi},
+    error::MovyError,
+    input::{MoveAddress, MoveTypeTag},
+};
+use sui_json_rpc_types::type_and_fields_from_move_event_data;
+use sui_types::{event::Event, object::Object, storage::ObjectStore};
+
+use crate::db::ObjectStoreInfo;
+
+pub type StructDefs = BTreeMap<(MoveModuleId, String), MoveStructAbi>;
+
+/// Index the structs of the package at `address`. Types introduced by upgrades are tagged
+/// with the upgraded package while modules always carry the original one, so each struct is
+/// keyed under both.
+pub fn insert_package_structs(
+    structs: &mut StructDefs,
+    address: MoveAddress,
+    abi: &MovePackageAbi,
+) {
+    for md in abi.modules.iter() {
+        for st in md.structs.iter() {
+            let upgraded = MoveModuleId {
+                module_address: address,
+                module_name: md.module_id.module_name.clone(),
+            };
+            structs.insert((upgraded, st.struct_name.clone()), st.clone());
+            structs.insert((md.module_id.clone(), st.struct_name.clone()), st.clone());
+        }
+    }
+}
+
+/// The layout of a type, or None if the type or any of its type arguments is unknown, since a
+/// partial layout would misalign the fields.
+pub fn type_layout(structs: &StructDefs, ty: &MoveTypeTag) -> Option<MoveTypeLayout> {
+    let layout = MoveAbiSignatureToken::from_type_tag_lossy(ty).to_move_type_layout(&[], structs);
+    if layout.is_none() {
+        log::debug!("can not resolve the layout of {}", ty);
+    }
+    layout
+}
+
+fn struct_layout(structs: &StructDefs, tag: &StructTag) -> Option<Box<MoveStructLayout>> {
+    let ty = MoveTypeTag::from(TypeTag::Struct(Box::new(tag.clone())));
+    match type_layout(structs, &ty)? {
+        MoveTypeLayout::Struct(layout) => Some(layout),
+        _ => None,
+    }
+}
+
+/// Decode a move object, or None if it is a package or its layout is unknown.
+pub fn decode_object(
+    structs: &StructDefs,
+    object: &Object,
+) -> Result<Option<MoveStruct>, MovyError> {
+    let Some(move_object) = object.data.try_as_move() else {
+        return Ok(None);
+    };
+    let tag: StructTag = move_object.type_().clone().into();
+    let Some(layout) = struct_layout(structs, &tag) else {
+        return Ok(None);
+    };
+    Ok(Some(MoveStruct::simple_deserialize(
+        move_object.contents(),
+        &layout,
+    )?))
+}
+
+/// Decode an event into its type and fields, or None if its layout is unknown.
+pub fn decode_event(
+    structs: &StructDefs,
+    event: &Event,
+) -> Result<Option<(StructTag, serde_json::Value)>, MovyError> {
+    let Some(layout) = struct_layout(structs, &event.type_) else {
+        return Ok(None);
+    };
+    let value =
+        Event::move_event_to_move_value(&event.contents, MoveDatatypeLayout::Struct(layout))?;
+    Ok(Some(type_and_fields_from_move_event_data(value)?))
+}
+
+/// Resolves the layouts of objects by loading the packages defining them, and the packages
+/// their fields refer to, from the store on demand.
+pub struct ObjectLayoutResolver<'a, T> {
+    db: &'a T,
+    loaded: BTreeSet<MoveAddress>,
+    structs: StructDefs,
+}
+
+fn signature_addresses(ty: &MoveAbiSignatureToken, out: &mut Vec<MoveAddress>) {
+    match ty {
+        MoveAbiSignatureToken::Struct(st) => out.push(st.module_id.module_address),
+        MoveAbiSignatureToken::StructInstantiation(st, insts) => {
+            out.push(st.module_id.module_address);
+            for inst in insts.iter() {
+                signature_addresses(inst, out);
+            }
+        }
+        MoveAbiSignatureToken::Vector(v)
+        | MoveAbiSignatureToken::Reference(v)
+        | MoveAbiSignatureToken::MutableReference(v) => signature_addresses(v, out),
+        _ => {}
+    }
+}
+
+fn type_tag_addresses(ty: &MoveTypeTag, out: &mut Vec<MoveAddress>) {
+    match ty {
+        MoveTypeTag::Struct(st) => {
+            out.push(st.address);
+            for ty in st.tys.iter() {
+                type_tag_addresses(ty, out);
+            }
+        }
+        MoveTypeTag::Vector(v) => type_tag_addresses(v, out),
+        _ => {}
+    }
+}
+
+impl<'a, T: ObjectStore> ObjectLayoutResolver<'a, T> {
+    pub fn new(db: &'a T) -> Self {
+        Self {
+            db,
+            loaded: BTreeSet::new(),
+            structs: BTreeMap::new(),
+        }
+    }
+
+    fn load_packages(&mut self, mut pending: Vec<MoveAddress>) -> Result<(), MovyError> {
+        while let Some(address) = pending.pop() {
+            if !self.loaded.insert(address) {
+                continue;
+            }
+            let Some(abi) = self.db.get_package_info(address)? else {
+                log::debug!("package {} is not found for layouts", address);
+                continue;
+            };
+            for md in abi.modules.iter() {
+                for st in md.structs.iter() {
+                    for field in st.fields.iter() {
+                        signature_addresses(&field.ty, &mut pending);
+                    }
+                }
+            }
+            insert_package_structs(&mut self.structs, address, &abi);
+        }
+        Ok(())
+    }
+
+    fn load_type(&mut self, ty: &MoveTypeTag) -> Result<(), MovyError> {
+        let mut pending = vec![];
+        type_tag_addresses(ty, &mut pending);
+        self.load_packages(pending)
+    }
+
+    fn load_struct(&mut self, tag: &StructTag) -> Result<(), MovyError> {
+        self.load_type(&MoveTypeTag::from(TypeTag::Struct(Box::new(tag.clone()))))
+    }
+
+    pub fn type_layout(&mut self, ty: &MoveTypeTag) -> Result<Option<MoveTypeLayout>, MovyError> {
+        self.load_type(ty)?;
+        Ok(type_layout(&self.structs, ty))
+    }
+
+    /// Decode a move object, or None if it is a package or its layout is unknown.
+    pub fn decode_object(&mut self, object: &Object) -> Result<Option<MoveStruct>, MovyError> {
+        if let Some(move_object) = object.data.try_as_move() {
+            self.load_struct(&move_object.type_().clone().into())?;
+        }
+        decode_object(&self.structs, object)
+    }
+
+    /// Decode an event into its type and fields, or None if its layout is unknown.
+    pub fn decode_event(
+        &mut self,
+        event: &Event,
+    ) -> Result<Option<(StructTag, serde_json::Value)>, MovyError> {
+        self.load_struct(&event.type_)?;
+        decode_event(&self.structs, event)
+    }
+}
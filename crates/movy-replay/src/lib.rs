@@ -1,6 +1,8 @@
 pub mod db;
+pub mod diff;
 pub mod env;
 pub mod event;
 pub mod exec;
+pub mod layout;
 pub mod meta;
 pub mod tracer;
@@ -6,27 +6,24 @@ use std::{
 use crate::{
     db::{ObjectStoreCachedStore, ObjectStoreInfo},
     env::SuiTestingEnv,
+    layout,
 };
 use color_eyre::eyre::eyre;
-use move_core_types::{
-    annotated_value::{MoveDatatypeLayout, MoveStruct, MoveValue},
-    language_storage::StructTag,
-};
+use move_core_types::{annotated_value::MoveStruct, language_storage::StructTag};
 use movy_analysis::type_graph::MoveTypeGraph;
 use movy_sui::database::cache::ObjectSuiStoreCommit;
 use movy_types::{
     abi::{
-        MoveAbiSignatureToken, MoveAbility, MoveFunctionAbi, MoveModuleAbi, MoveModuleId,
-        MovePackageAbi, MoveStructAbi,
+        MoveAbility, MoveFunctionAbi, MoveModuleAbi, MoveModuleId, MovePackageAbi, MoveStructAbi,
     },
     error::MovyError,
     input::{FunctionIdent, MoveAddress, MoveStructTag, MoveTypeTag},
 };
 use serde::{Deserialize, Serialize};
 use serde_json_any_key::*;
-use sui_json_rpc_types::type_and_fields_from_move_event_data;
 use sui_types::{
     event::Event,
+    object::Object,
     storage::{BackingPackageStore, BackingStore, ObjectStore},
 };
 
@@ -158,38 +155,11 @@ impl Metadata {
         event: &Event,
     ) -> Result<Option<(StructTag, serde_json::Value)>, MovyError> {
         log::debug!("Decoding event {}", event.type_.to_canonical_string(true));
-        let id: MoveAddress = event.type_.address.into();
-        if let Some(st) =
-            self.get_struct(&id, event.type_.module.as_str(), event.type_.name.as_str())
-        {
-            let mut typs = vec![];
-            for ty in event.type_.type_params.iter() {
-                let ty = MoveTypeTag::from(ty.clone());
-                let abi_ty = MoveAbiSignatureToken::from_type_tag_lossy(&ty);
-                if let Some(typ) = abi_ty.to_move_type_layout(&[], &self.structs_mapping) {
-                    typs.push(typ);
-                } else {
-                    log::debug!("decode_event: abi_ty {} is mising", &abi_ty);
-                }
-            }
-            if let Some(layout) = st.to_move_struct_layout(&typs, &self.structs_mapping) {
-                let e = Event::move_event_to_move_value(
-                    &event.contents,
-                    MoveDatatypeLayout::Struct(Box::new(layout)),
-                )?;
-                return Ok(Some(type_and_fields_from_move_event_data(e)?));
-            } else {
-                log::debug!(
-                    "can not convert to move struct layout for {} and {:?}",
-                    st.struct_name,
-                    &typs
-                );
-            }
-        } else {
-            log::debug!("the event struct is not known");
-        }
+        layout::decode_event(&self.structs_mapping, event)
+    }
 
-        Ok(None)
+    pub fn decode_sui_object(&self, object: &Object) -> Result<Option<MoveStruct>, MovyError> {
+        layout::decode_object(&self.structs_mapping, object)
     }
 
     pub async fn from_env_filtered<T>(
@@ -300,12 +270,7 @@ impl Metadata {
 
         let mut structs_mapping = BTreeMap::new();
         for (pkg_id, pkg) in testing_abis.iter() {
-            for md in pkg.modules.iter() {
-                for st in md.structs.iter() {
-                    structs_mapping
-                        .insert((md.module_id.clone(), st.struct_name.clone()), st.clone());
-                }
-            }
+            layout::insert_package_structs(&mut structs_mapping, *pkg_id, pkg);
         }
 
         let meta = Metadata {
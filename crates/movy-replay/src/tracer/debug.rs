@@ -0,0 +1,487 @@
+//! An interactive step debugger on the top of the [`TraceState`] and the [`ConcolicState`].
+//!
+//! The debugger is driven by the trace events, so it blocks the execution within the tracer
+//! whenever a breakpoint is hit or a step is done, and reads commands from the stdin.
+
+use std::{
+    fmt::Display,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use move_trace_format::{
+    format::{Effect, TraceEvent, TraceIndex},
+    interface::Writer,
+};
+use movy_types::{error::MovyError, input::MoveAddress};
+
+use crate::{
+    event::{InstructionExtraInformation, TraceNotifier},
+    tracer::{
+        concolic::{ConcolicState, SymbolValue},
+        trace::TraceState,
+    },
+};
+
+const HELP: &str = "Commands:
+  s, step               Step to the next instruction
+  n, next               Step to the next instruction of this frame, over calls
+  f, finish             Run until the current frame returns
+  c, continue           Run until the next breakpoint
+  q, quit               Detach the debugger and run to the end
+  b, break [bp]         Add a breakpoint or list breakpoints, bp is one of
+                          [package::]module::function        function entry, or call of natives
+                          [package::]module::function:pc     instruction
+                          abort                              execution errors
+  d, delete <idx>       Delete a breakpoint
+  bt, backtrace         Show the call stack
+  stack                 Show the operand stack and symbolic values
+  locals                Show the locals of the current frame and symbolic values
+  globals               Show the loaded global values
+  h, help               Show this message";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionLocation {
+    pub package: Option<MoveAddress>,
+    pub module: String,
+    pub function: String,
+}
+
+impl FunctionLocation {
+    fn matches(&self, frame: &DebugFrame) -> bool {
+        self.package.is_none_or(|p| p == frame.package)
+            && self.module == frame.module
+            && self.function == frame.function
+    }
+}
+
+impl Display for FunctionLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(package) = &self.package {
+            write!(f, "{}::", package)?;
+        }
+        write!(f, "{}::{}", self.module, self.function)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    Entry(FunctionLocation),
+    Pc(FunctionLocation, u16),
+    Abort,
+}
+
+impl FromStr for Breakpoint {
+    type Err = MovyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "abort" {
+            return Ok(Self::Abort);
+        }
+        let (path, pc) = match s.rsplit_once(':') {
+            Some((path, pc)) if !path.ends_with(':') => {
+                let pc = pc
+                    .parse::<u16>()
+                    .map_err(|_| MovyError::InvalidIdentifier(format!("bad pc in {}", s)))?;
+                (path, Some(pc))
+            }
+            _ => (s, None),
+        };
+        let parts = path.split("::").collect::<Vec<_>>();
+        let (package, module, function) =
+            match parts.as_slice() {
+                [module, function] => (None, *module, *function),
+                [package, module, function] => (
+                    Some(MoveAddress::from_str(package).map_err(|_| {
+                        MovyError::InvalidIdentifier(format!("bad package in {}", s))
+                    })?),
+                    *module,
+                    *function,
+                ),
+                _ => {
+                    return Err(MovyError::InvalidIdentifier(format!(
+                        "{} is not a breakpoint, expect [package::]module::function[:pc] or abort",
+                        s
+                    )));
+                }
+            };
+        let location = FunctionLocation {
+            package,
+            module: module.to_string(),
+            function: function.to_string(),
+        };
+        Ok(match pc {
+            Some(pc) => Self::Pc(location, pc),
+            None => Self::Entry(location),
+        })
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Entry(location) => write!(f, "{}", location),
+            Self::Pc(location, pc) => write!(f, "{}:{}", location, pc),
+            Self::Abort => write!(f, "abort"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DebugFrame {
+    package: MoveAddress,
+    module: String,
+    function: String,
+    frame_id: TraceIndex,
+    is_native: bool,
+    pc: Option<u16>,
+    // No instruction executed yet
+    fresh: bool,
+}
+
+impl Display for DebugFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}::{}", self.package, self.module, self.function)?;
+        if let Some(pc) = self.pc {
+            write!(f, ":{}", pc)?;
+        }
+        if self.is_native {
+            write!(f, " (native)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebugMode {
+    Step,
+    // Stop at the next instruction with at most the depth
+    Next(usize),
+    // Stop at the next instruction with less than the depth
+    Finish(usize),
+    Continue,
+    Detached,
+}
+
+fn symbol_value(value: Option<&SymbolValue>) -> String {
+    match value {
+        Some(SymbolValue::Value(v)) => v.to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub struct DebugTracer {
+    trace_state: TraceState,
+    concolic: ConcolicState,
+    frames: Vec<DebugFrame>,
+    breakpoints: Vec<Breakpoint>,
+    mode: DebugMode,
+}
+
+impl DebugTracer {
+    /// Without breakpoints, the debugger stops at the first instruction.
+    pub fn new(breakpoints: Vec<Breakpoint>) -> Self {
+        let mode = if breakpoints.is_empty() {
+            DebugMode::Step
+        } else {
+            DebugMode::Continue
+        };
+        Self {
+            trace_state: TraceState::new(),
+            concolic: ConcolicState::new(),
+            frames: vec![],
+            breakpoints,
+            mode,
+        }
+    }
+
+    fn print_backtrace(&self) {
+        for (idx, frame) in self.frames.iter().rev().enumerate() {
+            println!("#{} {}", idx, frame);
+        }
+    }
+
+    fn print_stack(&self) {
+        let stack = &self.trace_state.operand_stack;
+        if stack.is_empty() {
+            println!("<empty>");
+        }
+        // The symbolic stack is reset per call, so align the two from the top
+        let sym = &self.concolic.stack;
+        for (idx, value) in stack.iter().enumerate().rev() {
+            let sym_value = (idx + sym.len())
+                .checked_sub(stack.len())
+                .and_then(|idx| sym.get(idx));
+            println!("[{}] {} | {}", idx, value, symbol_value(sym_value));
+        }
+    }
+
+    fn print_locals(&self) {
+        let Some(frame) = self.frames.last() else {
+            println!("<no frame>");
+            return;
+        };
+        let Some((locals, _)) = self.trace_state.call_stack.get(&frame.frame_id) else {
+            println!("<no locals>");
+            return;
+        };
+        let sym = self.concolic.locals.last();
+        for (idx, value) in locals.iter() {
+            let sym_value = sym.and_then(|v| v.get(*idx));
+            println!("loc{} = {} | {}", idx, value, symbol_value(sym_value));
+        }
+    }
+
+    fn print_globals(&self) {
+        if self.trace_state.loaded_state.is_empty() {
+            println!("<empty>");
+        }
+        for (idx, value) in self.trace_state.loaded_state.iter() {
+            println!("global{} = {}", idx, value);
+        }
+    }
+
+    fn print_breakpoints(&self) {
+        if self.breakpoints.is_empty() {
+            println!("<no breakpoints>");
+        }
+        for (idx, bp) in self.breakpoints.iter().enumerate() {
+            println!("#{} {}", idx, bp);
+        }
+    }
+
+    fn repl(&mut self) {
+        let stdin = std::io::stdin();
+        loop {
+            print!("(movy) ");
+            let _ = std::io::stdout().flush();
+            let mut line = String::new();
+            if !matches!(stdin.lock().read_line(&mut line), Ok(n) if n > 0) {
+                self.mode = DebugMode::Detached;
+                return;
+            }
+            let mut args = line.split_whitespace();
+            let Some(cmd) = args.next() else {
+                continue;
+            };
+            let depth = self.frames.len();
+            match cmd {
+                "s" | "step" => {
+                    self.mode = DebugMode::Step;
+                    return;
+                }
+                "n" | "next" => {
+                    self.mode = DebugMode::Next(depth);
+                    return;
+                }
+                "f" | "finish" => {
+                    self.mode = DebugMode::Finish(depth);
+                    return;
+                }
+                "c" | "continue" => {
+                    self.mode = DebugMode::Continue;
+                    return;
+                }
+                "q" | "quit" => {
+                    self.mode = DebugMode::Detached;
+                    return;
+                }
+                "b" | "break" => match args.next().map(Breakpoint::from_str) {
+                    Some(Ok(bp)) => {
+                        println!("Breakpoint #{} at {}", self.breakpoints.len(), bp);
+                        self.breakpoints.push(bp);
+                    }
+                    Some(Err(e)) => println!("{}", e),
+                    None => self.print_breakpoints(),
+                },
+                "d" | "delete" => match args.next().and_then(|v| v.parse::<usize>().ok()) {
+                    Some(idx) if idx < self.breakpoints.len() => {
+                        println!("Deleted breakpoint {}", self.breakpoints.remove(idx));
+                    }
+                    _ => println!("Expect a breakpoint index"),
+                },
+                "bt" | "backtrace" => self.print_backtrace(),
+                "stack" => self.print_stack(),
+                "locals" => self.print_locals(),
+                "globals" => self.print_globals(),
+                "h" | "help" => println!("{}", HELP),
+                _ => println!("Unknown command {}, try help", cmd),
+            }
+        }
+    }
+
+    fn stop_reason(&self, pc: u16, fresh: bool) -> Option<String> {
+        let depth = self.frames.len();
+        let frame = self.frames.last()?;
+        match self.mode {
+            DebugMode::Detached => return None,
+            DebugMode::Step => return Some("step".to_string()),
+            DebugMode::Next(d) if depth <= d => return Some("next".to_string()),
+            DebugMode::Finish(d) if depth < d => return Some("finish".to_string()),
+            _ => {}
+        }
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .find(|(_, bp)| match bp {
+                Breakpoint::Entry(location) => fresh && location.matches(frame),
+                Breakpoint::Pc(location, bp_pc) => *bp_pc == pc && location.matches(frame),
+                Breakpoint::Abort => false,
+            })
+            .map(|(idx, bp)| format!("breakpoint #{} {}", idx, bp))
+    }
+
+    /// Natives execute no instructions, so their entry breakpoints stop at the call.
+    fn native_stop_reason(&self) -> Option<String> {
+        let frame = self.frames.last()?;
+        if !frame.is_native || self.mode == DebugMode::Detached {
+            return None;
+        }
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .find(|(_, bp)| matches!(bp, Breakpoint::Entry(location) if location.matches(frame)))
+            .map(|(idx, bp)| format!("breakpoint #{} {}", idx, bp))
+    }
+}
+
+impl TraceNotifier for DebugTracer {
+    fn notify(&mut self, event: &TraceEvent, writer: &mut Writer<'_>) {
+        let _ = writer;
+        self.trace_state.notify_event(event);
+    }
+
+    fn notify_event(&mut self, event: &TraceEvent) -> Result<(), MovyError> {
+        if matches!(event, TraceEvent::Instruction { .. }) {
+            return Ok(());
+        }
+        let _ = self.concolic.notify_event(event, &self.trace_state);
+        match event {
+            TraceEvent::OpenFrame { frame, gas_left: _ } => {
+                self.frames.push(DebugFrame {
+                    package: (*frame.module.address()).into(),
+                    module: frame.module.name().to_string(),
+                    function: frame.function_name.clone(),
+                    frame_id: frame.frame_id,
+                    is_native: frame.is_native,
+                    pc: None,
+                    fresh: true,
+                });
+                if let Some(reason) = self.native_stop_reason() {
+                    if let Some(frame) = self.frames.last() {
+                        println!("Stopped by {} at the call of {}", reason, frame);
+                    }
+                    self.repl();
+                }
+            }
+            TraceEvent::CloseFrame { .. } => {
+                self.frames.pop();
+            }
+            TraceEvent::Effect(ef) => {
+                if let Effect::ExecutionError(e) = ef.as_ref()
+                    && self.mode != DebugMode::Detached
+                    && self.breakpoints.contains(&Breakpoint::Abort)
+                {
+                    match self.frames.last() {
+                        Some(frame) => println!("Stopped by abort in {}: {}", frame, e),
+                        None => println!("Stopped by abort: {}", e),
+                    }
+                    self.repl();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_before_instruction(
+        &mut self,
+        ctx: &TraceEvent,
+        extra: Option<&InstructionExtraInformation>,
+    ) -> Result<(), MovyError> {
+        let TraceEvent::Instruction {
+            pc, instruction, ..
+        } = ctx
+        else {
+            return Ok(());
+        };
+        let _ = self
+            .concolic
+            .handle_before_instruction(ctx, extra, &self.trace_state);
+
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(());
+        };
+        let fresh = std::mem::replace(&mut frame.fresh, false);
+        frame.pc = Some(*pc);
+        if let Some(reason) = self.stop_reason(*pc, fresh) {
+            if let Some(frame) = self.frames.last() {
+                println!("Stopped by {} at {}: {:?}", reason, frame, instruction);
+            }
+            self.repl();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use movy_types::{error::MovyError, input::MoveAddress};
+
+    use crate::tracer::debug::{Breakpoint, FunctionLocation};
+
+    fn location(package: Option<&str>, module: &str, function: &str) -> FunctionLocation {
+        FunctionLocation {
+            package: package.map(|p| MoveAddress::from_str(p).unwrap()),
+            module: module.to_string(),
+            function: function.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_breakpoint_from_str() {
+        assert_eq!(Breakpoint::from_str("abort").unwrap(), Breakpoint::Abort);
+        assert_eq!(
+            Breakpoint::from_str("pool::swap").unwrap(),
+            Breakpoint::Entry(location(None, "pool", "swap"))
+        );
+        assert_eq!(
+            Breakpoint::from_str("pool::swap:12").unwrap(),
+            Breakpoint::Pc(location(None, "pool", "swap"), 12)
+        );
+        assert_eq!(
+            Breakpoint::from_str("0x2::coin::split").unwrap(),
+            Breakpoint::Entry(location(Some("0x2"), "coin", "split"))
+        );
+        assert_eq!(
+            Breakpoint::from_str("0x2::coin::split:3").unwrap(),
+            Breakpoint::Pc(location(Some("0x2"), "coin", "split"), 3)
+        );
+
+        // Displayed back into the same breakpoint
+        for bp in ["abort", "pool::swap", "pool::swap:12", "0x2::coin::split:3"] {
+            let bp = Breakpoint::from_str(bp).unwrap();
+            assert_eq!(Breakpoint::from_str(&bp.to_string()).unwrap(), bp);
+        }
+
+        for bad in [
+            "swap",
+            "pool::swap:x",
+            "pool::swap:70000",
+            "0xzz::coin::split",
+            "a::b::c::d",
+        ] {
+            assert!(
+                matches!(
+                    Breakpoint::from_str(bad),
+                    Err(MovyError::InvalidIdentifier(_))
+                ),
+                "{} is parsed",
+                bad
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use move_trace_format::interface::Tracer;
 
 pub mod concolic;
+pub mod debug;
 pub mod fuzz;
 pub mod op;
 pub mod oracle;
@@ -1,17 +1,102 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
+use color_eyre::eyre::eyre;
 use itertools::Itertools;
 use log::warn;
+use move_binary_format::file_format::Bytecode;
 use move_trace_format::{
-    format::{Frame, TraceEvent, TraceValue},
+    format::{Effect, Frame, Location, TraceEvent, TraceValue, Write},
     interface::Tracer,
 };
+use movy_sui::compile::SuiPackageSources;
+use movy_types::{error::MovyError, input::MoveAddress};
+
+#[derive(Debug, Clone)]
+pub struct InstructionTraced {
+    pub pc: u16,
+    pub gas_left: u64,
+    pub instruction: Bytecode,
+    /// Locals written by the instruction
+    pub writes: Vec<(usize, TraceValue)>,
+    /// The index into `subcalls` if the instruction calls a function
+    pub subcall: Option<usize>,
+}
 
 #[derive(Debug, Clone)]
 pub struct FrameTraced {
     pub open: Box<Frame>,
     pub subcalls: Vec<FrameTraced>,
     pub close: Option<Vec<TraceValue>>,
+    pub instructions: Vec<InstructionTraced>,
+    pub open_gas_left: u64,
+    /// Aborted frames are never closed
+    pub close_gas_left: Option<u64>,
+}
+
+/// How the frames are weighted when exporting for profilers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceWeight {
+    Instructions,
+    #[default]
+    Gas,
+}
+
+impl FromStr for TraceWeight {
+    type Err = MovyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "instructions" => Ok(Self::Instructions),
+            "gas" => Ok(Self::Gas),
+            _ => Err(eyre!("unknown weight {}, expect instructions or gas", s).into()),
+        }
+    }
+}
+
+impl FrameTraced {
+    pub fn name(&self) -> String {
+        format!(
+            "{}::{}",
+            self.open.module.short_str_lossless(),
+            self.open.function_name
+        )
+    }
+
+    fn end_gas_left(&self) -> u64 {
+        self.close_gas_left.unwrap_or_else(|| {
+            self.instructions
+                .iter()
+                .map(|inst| inst.gas_left)
+                .chain(self.subcalls.iter().map(|call| call.end_gas_left()))
+                .min()
+                .unwrap_or(self.open_gas_left)
+        })
+    }
+
+    /// The weight of the frame including all its subcalls.
+    pub fn total_weight(&self, weight: TraceWeight) -> u64 {
+        match weight {
+            TraceWeight::Instructions => {
+                self.instructions.len() as u64
+                    + self
+                        .subcalls
+                        .iter()
+                        .map(|call| call.total_weight(weight))
+                        .sum::<u64>()
+            }
+            TraceWeight::Gas => self.open_gas_left.saturating_sub(self.end_gas_left()),
+        }
+    }
+
+    /// The weight of the frame excluding its subcalls.
+    pub fn self_weight(&self, weight: TraceWeight) -> u64 {
+        self.total_weight(weight).saturating_sub(
+            self.subcalls
+                .iter()
+                .map(|call| call.total_weight(weight))
+                .sum(),
+        )
+    }
 }
 
 impl Display for FrameTraced {
@@ -96,14 +181,189 @@ impl TreeTraceResult {
         tr
     }
 
-    pub fn pprint(&self) -> String {
-        let mut tr = self.pprint_call_tree();
+    fn pprint_source_child(
+        frame: &FrameTraced,
+        sources: &BTreeMap<MoveAddress, SuiPackageSources>,
+        instructions: bool,
+        tr: &mut ptree::TreeBuilder,
+    ) {
+        let module = frame.open.module.name().as_str();
+        let function = frame.open.function_name.as_str();
+        let package = sources.get(&MoveAddress::from(*frame.open.module.address()));
+        let location = package
+            .and_then(|p| p.locate(module, function, None))
+            .map(|span| format!(" at {}:{}", span.file, span.start_line))
+            .unwrap_or_default();
+        tr.begin_child(format!("{}{}", frame, location));
+
+        match package {
+            Some(package) if instructions => {
+                let mut last_line = None;
+                for inst in frame.instructions.iter() {
+                    let line = package
+                        .locate(module, function, Some(inst.pc))
+                        .map(|span| span.start_line);
+                    let writes = inst
+                        .writes
+                        .iter()
+                        .map(|(idx, value)| {
+                            let name = package
+                                .local_name(module, function, *idx)
+                                .map(|v| v.to_string())
+                                .unwrap_or_else(|| format!("loc{}", idx));
+                            format!("{} = {}", name, value)
+                        })
+                        .join(", ");
+                    let mut text = format!("{:>4}: {:?}", inst.pc, inst.instruction);
+                    if !writes.is_empty() {
+                        text = format!("{} [{}]", text, writes);
+                    }
+                    // Only repeat the source line when we move to another one
+                    if let Some(line) = line
+                        && last_line != Some(line)
+                    {
+                        last_line = Some(line);
+                        let src = package.line(module, line).unwrap_or_default().trim();
+                        text = format!("{} | {}: {}", text, line, src);
+                    }
+                    match inst.subcall.and_then(|idx| frame.subcalls.get(idx)) {
+                        Some(subcall) => {
+                            tr.begin_child(text);
+                            Self::pprint_source_child(subcall, sources, instructions, tr);
+                            tr.end_child();
+                        }
+                        None => {
+                            tr.add_empty_child(text);
+                        }
+                    }
+                }
+            }
+            _ => {
+                for child in frame.subcalls.iter() {
+                    Self::pprint_source_child(child, sources, instructions, tr);
+                }
+            }
+        }
+        tr.end_child();
+    }
+
+    /// Like [`Self::pprint`] but annotate the frames of the packages with sources by their
+    /// locations. With `instructions`, the executed instructions are listed along with their
+    /// source lines and the named locals they write.
+    pub fn pprint_with_sources(
+        &self,
+        sources: &BTreeMap<MoveAddress, SuiPackageSources>,
+        instructions: bool,
+    ) -> String {
+        let mut tr = ptree::TreeBuilder::new("calltree".to_string());
+        for call in self.calls.iter() {
+            Self::pprint_source_child(call, sources, instructions, &mut tr);
+        }
+        Self::write_tree(tr)
+    }
+
+    fn write_tree(mut tr: ptree::TreeBuilder) -> String {
         let mut buf = vec![];
         let out = std::io::Cursor::new(&mut buf);
         ptree::write_tree(&tr.build(), out).unwrap();
         String::from_utf8(buf).unwrap()
     }
 
+    pub fn pprint(&self) -> String {
+        Self::write_tree(self.pprint_call_tree())
+    }
+
+    fn fold_child(
+        frame: &FrameTraced,
+        weight: TraceWeight,
+        stack: &mut Vec<String>,
+        folded: &mut BTreeMap<String, u64>,
+    ) {
+        stack.push(frame.name());
+        let self_weight = frame.self_weight(weight);
+        if self_weight > 0 {
+            *folded.entry(stack.join(";")).or_default() += self_weight;
+        }
+        for call in frame.subcalls.iter() {
+            Self::fold_child(call, weight, stack, folded);
+        }
+        stack.pop();
+    }
+
+    /// The folded stacks consumed by flamegraph.pl, inferno and speedscope.
+    pub fn to_folded_stacks(&self, weight: TraceWeight) -> String {
+        let mut folded = BTreeMap::new();
+        for call in self.calls.iter() {
+            Self::fold_child(call, weight, &mut vec![], &mut folded);
+        }
+        folded
+            .into_iter()
+            .map(|(stack, weight)| format!("{} {}", stack, weight))
+            .join("\n")
+    }
+
+    fn chrome_child(
+        frame: &FrameTraced,
+        weight: TraceWeight,
+        base_gas: u64,
+        start: u64,
+        events: &mut Vec<serde_json::Value>,
+    ) -> u64 {
+        let start = match weight {
+            TraceWeight::Instructions => start,
+            TraceWeight::Gas => base_gas.saturating_sub(frame.open_gas_left),
+        };
+        match weight {
+            TraceWeight::Instructions => {
+                // Lay out the subcalls by the instructions executed before them
+                let mut cursor = start;
+                for inst in frame.instructions.iter() {
+                    cursor += 1;
+                    if let Some(call) = inst.subcall.and_then(|idx| frame.subcalls.get(idx)) {
+                        cursor = Self::chrome_child(call, weight, base_gas, cursor, events);
+                    }
+                }
+            }
+            TraceWeight::Gas => {
+                for call in frame.subcalls.iter() {
+                    Self::chrome_child(call, weight, base_gas, start, events);
+                }
+            }
+        }
+        let duration = frame.total_weight(weight);
+        events.push(serde_json::json!({
+            "name": frame.name(),
+            "cat": "move",
+            "ph": "X",
+            "ts": start,
+            "dur": duration,
+            "pid": 0,
+            "tid": 0,
+            "args": {
+                "self": frame.self_weight(weight),
+                "instructions": frame.instructions.len(),
+            },
+        }));
+        start + duration
+    }
+
+    /// The Chrome Trace Event JSON consumed by perfetto, chrome://tracing and speedscope. The
+    /// timestamps are the weights consumed before each frame.
+    pub fn to_chrome_trace(&self, weight: TraceWeight) -> serde_json::Value {
+        let base_gas = self
+            .calls
+            .iter()
+            .map(|call| call.open_gas_left)
+            .max()
+            .unwrap_or_default();
+        let mut events = vec![];
+        let mut cursor = 0;
+        for call in self.calls.iter() {
+            cursor = Self::chrome_child(call, weight, base_gas, cursor, &mut events);
+        }
+        serde_json::json!({ "traceEvents": events })
+    }
+
     pub fn into_raw(self) -> Vec<TraceEvent> {
         self.evs
     }
@@ -135,27 +395,66 @@ impl Tracer for TreeTracer {
         let inner = &mut self.inner;
         inner.evs.push(event.clone());
         match event {
-            TraceEvent::OpenFrame { frame, gas_left: _ } => {
+            TraceEvent::OpenFrame { frame, gas_left } => {
                 let current = inner.current_calls();
                 let idx_len = current.len();
                 current.push(FrameTraced {
                     open: frame.clone(),
                     subcalls: vec![],
                     close: None,
+                    instructions: vec![],
+                    open_gas_left: *gas_left,
+                    close_gas_left: None,
                 });
                 // drop(current);
+                if let Some(inst) = inner
+                    .current_frame()
+                    .and_then(|parent| parent.instructions.last_mut())
+                {
+                    inst.subcall = Some(idx_len);
+                }
                 inner.call_idxs.push(idx_len);
             }
+            TraceEvent::Instruction {
+                pc,
+                instruction,
+                gas_left,
+                ..
+            } => {
+                if let Some(current) = inner.current_frame() {
+                    current.instructions.push(InstructionTraced {
+                        pc: *pc,
+                        gas_left: *gas_left,
+                        instruction: instruction.clone(),
+                        writes: vec![],
+                        subcall: None,
+                    });
+                }
+            }
+            TraceEvent::Effect(ef) => {
+                if let Effect::Write(Write {
+                    location: Location::Local(frame_id, idx),
+                    root_value_after_write,
+                }) = &**ef
+                    && let Some(current) = inner.current_frame()
+                    && current.open.frame_id == *frame_id
+                    && let Some(inst) = current.instructions.last_mut()
+                {
+                    inst.writes.push((*idx, root_value_after_write.clone()));
+                }
+            }
             TraceEvent::CloseFrame {
                 frame_id: _,
                 return_,
-                gas_left: _,
+                gas_left,
             } => {
                 let current = inner.current_frame();
                 if current.is_none() {
                     warn!("current frame is none when trying to close frame!?");
                 } else {
-                    current.unwrap().close = Some(return_.clone());
+                    let current = current.unwrap();
+                    current.close = Some(return_.clone());
+                    current.close_gas_left = Some(*gas_left);
                 }
                 inner.call_idxs.pop();
             }
@@ -164,3 +463,156 @@ impl Tracer for TreeTracer {
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use move_binary_format::file_format::Bytecode;
+    use move_trace_format::format::Frame;
+
+    use crate::tracer::tree::{FrameTraced, InstructionTraced, TraceWeight, TreeTraceResult};
+
+    fn frame(
+        module: &str,
+        function: &str,
+        gas: (u64, Option<u64>),
+        instructions: Vec<(u64, Option<usize>)>,
+        subcalls: Vec<FrameTraced>,
+    ) -> FrameTraced {
+        let open: Frame = serde_json::from_value(serde_json::json!({
+            "frame_id": 0,
+            "function_name": function,
+            "module": {
+                "address": "0x0000000000000000000000000000000000000000000000000000000000000002",
+                "name": module,
+            },
+            "version_id": "0x0000000000000000000000000000000000000000000000000000000000000002",
+            "binary_member_index": 0,
+            "type_instantiation": [],
+            "parameters": [],
+            "return_types": [],
+            "locals_types": [],
+            "is_native": false,
+        }))
+        .unwrap();
+        FrameTraced {
+            open: Box::new(open),
+            subcalls,
+            close: gas.1.map(|_| vec![]),
+            instructions: instructions
+                .into_iter()
+                .enumerate()
+                .map(|(pc, (gas_left, subcall))| InstructionTraced {
+                    pc: pc as u16,
+                    gas_left,
+                    instruction: Bytecode::Nop,
+                    writes: vec![],
+                    subcall,
+                })
+                .collect(),
+            open_gas_left: gas.0,
+            close_gas_left: gas.1,
+        }
+    }
+
+    fn trace(child_close: Option<u64>) -> TreeTraceResult {
+        let mul = frame(
+            "math",
+            "mul",
+            (980, child_close),
+            vec![(970, None), (960, None)],
+            vec![],
+        );
+        let swap = frame(
+            "pool",
+            "swap",
+            (1000, Some(400)),
+            vec![(990, None), (980, Some(0)), (500, None)],
+            vec![mul],
+        );
+        TreeTraceResult {
+            calls: vec![swap],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trace_weight_from_str() {
+        assert_eq!(
+            "Instructions".parse::<TraceWeight>().unwrap(),
+            TraceWeight::Instructions
+        );
+        assert_eq!("gas".parse::<TraceWeight>().unwrap(), TraceWeight::Gas);
+        assert!("time".parse::<TraceWeight>().is_err());
+    }
+
+    #[test]
+    fn test_weights() {
+        let trace = trace(Some(700));
+        let swap = &trace.calls[0];
+        assert_eq!(swap.total_weight(TraceWeight::Instructions), 5);
+        assert_eq!(swap.self_weight(TraceWeight::Instructions), 3);
+        assert_eq!(swap.total_weight(TraceWeight::Gas), 600);
+        assert_eq!(swap.self_weight(TraceWeight::Gas), 320);
+
+        // Aborted frames end at the least gas left observed
+        let trace = self::trace(None);
+        assert_eq!(
+            trace.calls[0].subcalls[0].total_weight(TraceWeight::Gas),
+            20
+        );
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let trace = trace(Some(700));
+        assert_eq!(
+            trace.to_folded_stacks(TraceWeight::Gas),
+            "0x2::pool::swap 320\n0x2::pool::swap;0x2::math::mul 280"
+        );
+        assert_eq!(
+            trace.to_folded_stacks(TraceWeight::Instructions),
+            "0x2::pool::swap 3\n0x2::pool::swap;0x2::math::mul 2"
+        );
+        assert_eq!(
+            TreeTraceResult::default().to_folded_stacks(TraceWeight::Gas),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let trace = trace(Some(700));
+        let spans = |weight| {
+            trace.to_chrome_trace(weight)["traceEvents"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|ev| {
+                    assert_eq!(ev["ph"], "X");
+                    (
+                        ev["name"].as_str().unwrap().to_string(),
+                        ev["ts"].as_u64().unwrap(),
+                        ev["dur"].as_u64().unwrap(),
+                        ev["args"]["self"].as_u64().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Subcalls are emitted before their callers
+        assert_eq!(
+            spans(TraceWeight::Instructions),
+            vec![
+                ("0x2::math::mul".to_string(), 2, 2, 2),
+                ("0x2::pool::swap".to_string(), 0, 5, 3),
+            ]
+        );
+        assert_eq!(
+            spans(TraceWeight::Gas),
+            vec![
+                ("0x2::math::mul".to_string(), 20, 280, 280),
+                ("0x2::pool::swap".to_string(), 0, 600, 320),
+            ]
+        );
+    }
+}
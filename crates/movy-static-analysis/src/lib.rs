@@ -1,3 +1,5 @@
+pub mod sarif;
 pub mod sui;
+pub mod suppress;
 
 pub use sui::run_all as run_all_sui;
@@ -0,0 +1,440 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use movy_types::oracle::{FindingLocation, OracleFinding, Severity};
+use serde_json::{Map, Value, json};
+use url::Url;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Short descriptions of the rules of the static detectors and the upgrade check.
+const RULES: &[(&str, &str)] = &[
+    ("bool_judgement", "Boolean compared with a boolean literal"),
+    ("infinite_loop", "Loop condition is a constant"),
+    (
+        "precision_loss",
+        "Division or sqrt result is multiplied afterwards",
+    ),
+    (
+        "truncating_cast_before_multiply",
+        "Value is narrowed by a cast before a multiplication",
+    ),
+    (
+        "unwidened_mul_div",
+        "mul_div is computed without widening to u128",
+    ),
+    ("type_conversion", "Unnecessary type conversion"),
+    (
+        "unchecked_return",
+        "Return value is dropped without handling",
+    ),
+    ("unused_constant", "Constant is never referenced"),
+    ("unused_struct", "Struct is never used"),
+    ("unused_enum", "Enum is never used"),
+    (
+        "unused_private_function",
+        "Private function is never invoked",
+    ),
+    ("unused_friend_function", "Friend function is never invoked"),
+    (
+        "missing_access_control",
+        "Privileged operation without an authorization check",
+    ),
+    (
+        "public_random_function",
+        "Function taking Random is not a private entry function",
+    ),
+    (
+        "random_result_observable",
+        "Random outcome is returned to the caller",
+    ),
+    (
+        "random_test_and_abort",
+        "Caller can abort on an unfavorable random outcome",
+    ),
+    (
+        "tainted_transfer_recipient",
+        "Transfer recipient is controlled by the caller",
+    ),
+    (
+        "tainted_split_amount",
+        "Split amount is controlled by the caller",
+    ),
+    (
+        "tainted_withdraw_amount",
+        "Withdrawn amount is controlled by the caller",
+    ),
+    (
+        "tainted_dynamic_field_key",
+        "Dynamic field key is controlled by the caller",
+    ),
+    (
+        "weak_randomness_modulo",
+        "Predictable value is used as randomness in a modulo",
+    ),
+    (
+        "weak_randomness_branch",
+        "Predictable value decides a payout branch",
+    ),
+    (
+        "weak_randomness_index",
+        "Predictable value is used as randomness in an index",
+    ),
+    ("removed_module", "Module is removed by the upgrade"),
+    (
+        "removed_function",
+        "Public function is removed by the upgrade",
+    ),
+    (
+        "signature_change",
+        "Public function signature changes in the upgrade",
+    ),
+    (
+        "struct_layout_change",
+        "Struct layout changes in the upgrade",
+    ),
+    ("new_public_function", "Upgrade adds a public function"),
+    ("lost_assertion", "Upgrade drops an assertion of a function"),
+    (
+        "missing_version_check",
+        "Shared object is used without a version check",
+    ),
+];
+
+/// Stable rule id of a detector, e.g. `StaticBoolJudgement` -> `bool_judgement`.
+pub fn rule_id(oracle: &str) -> String {
+    let name = oracle.strip_prefix("Static").unwrap_or(oracle);
+    let mut out = String::new();
+    for (idx, ch) in name.chars().enumerate() {
+        if ch.is_ascii_uppercase() {
+            if idx != 0 {
+                out.push('_');
+            }
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+pub fn level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::Major => "error",
+        Severity::Medium | Severity::Minor => "warning",
+        Severity::Informational | Severity::Discussion => "note",
+    }
+}
+
+/// `UnusedConstant` for `unused_constant`.
+fn rule_name(id: &str) -> String {
+    id.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn file_url(path: &Path) -> Option<Url> {
+    Url::from_file_path(std::path::absolute(path).ok()?).ok()
+}
+
+fn directory_url(path: &Path) -> Option<Url> {
+    Url::from_directory_path(std::path::absolute(path).ok()?).ok()
+}
+
+/// Files of a package with a known root are relative to the `uriBaseId` named after the
+/// package, the others are `file://` URIs.
+fn artifact_location(
+    file: &str,
+    package_name: Option<&str>,
+    roots: &BTreeMap<String, PathBuf>,
+) -> Value {
+    let url = file_url(Path::new(file));
+    if let Some(name) = package_name
+        && let Some(base) = roots.get(name).and_then(|root| directory_url(root))
+        && let Some(url) = &url
+        && url.as_str().starts_with(base.as_str())
+        && let Some(relative) = base.make_relative(url)
+    {
+        return json!({ "uri": relative, "uriBaseId": name });
+    }
+    json!({ "uri": url.map(String::from).unwrap_or_else(|| file.to_string()) })
+}
+
+fn sarif_location(location: &FindingLocation, roots: &BTreeMap<String, PathBuf>) -> Value {
+    let module_name = match (&location.package, &location.module) {
+        (Some(package), Some(module)) => format!("{}::{}", package, module),
+        (None, Some(module)) => module.clone(),
+        (Some(package), None) => package.to_string(),
+        (None, None) => String::new(),
+    };
+    let logical = match &location.function {
+        Some(function) => json!({
+            "name": function,
+            "fullyQualifiedName": format!("{}::{}", module_name, function),
+            "kind": "function",
+        }),
+        None => json!({
+            "name": location.module.clone().unwrap_or_else(|| module_name.clone()),
+            "fullyQualifiedName": module_name,
+            "kind": "module",
+        }),
+    };
+    let mut out = json!({ "logicalLocations": [logical] });
+    if let Some(span) = &location.source {
+        out["physicalLocation"] = json!({
+            "artifactLocation": artifact_location(
+                &span.file,
+                location.package_name.as_deref(),
+                roots,
+            ),
+            "region": {
+                "startLine": span.start_line,
+                "startColumn": span.start_column,
+                "endLine": span.end_line,
+                "endColumn": span.end_column,
+            },
+        });
+    }
+    out
+}
+
+fn finding_rule(finding: &OracleFinding) -> String {
+    if finding.rule.is_empty() {
+        rule_id(&finding.oracle)
+    } else {
+        finding.rule.clone()
+    }
+}
+
+/// Render findings as a SARIF 2.1.0 log. Physical locations are only emitted for findings
+/// whose `location.source` was resolved, i.e. the ones in local packages, relative to the
+/// `roots` of the packages keyed by their names.
+pub fn to_sarif(findings: &[OracleFinding], roots: &BTreeMap<String, PathBuf>) -> Value {
+    let mut rules: BTreeMap<String, (usize, &OracleFinding)> = BTreeMap::new();
+    for finding in findings {
+        let id = finding_rule(finding);
+        let next = rules.len();
+        rules.entry(id).or_insert((next, finding));
+    }
+
+    let results = findings
+        .iter()
+        .map(|finding| {
+            let id = finding_rule(finding);
+            let message = if finding.message.is_empty() {
+                finding.oracle.clone()
+            } else {
+                finding.message.clone()
+            };
+            let locations = finding
+                .location
+                .iter()
+                .map(|location| sarif_location(location, roots))
+                .collect::<Vec<_>>();
+            json!({
+                "ruleId": id,
+                "ruleIndex": rules[&id].0,
+                "level": level(&finding.severity),
+                "message": { "text": message },
+                "locations": locations,
+                "partialFingerprints": { "movy/v3": finding.fingerprint },
+                "properties": {
+                    "severity": finding.severity.to_string(),
+                    "extra": finding.extra,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut rules = rules.into_iter().collect::<Vec<_>>();
+    rules.sort_by_key(|(_, (idx, _))| *idx);
+    let rules = rules
+        .into_iter()
+        .map(|(id, (_, finding))| {
+            let description = RULES
+                .iter()
+                .find(|(rule, _)| *rule == id)
+                .map(|(_, description)| description.to_string())
+                .unwrap_or_else(|| finding.message.clone());
+            json!({
+                "id": id,
+                "name": rule_name(&id),
+                "shortDescription": { "text": description },
+                "properties": { "oracle": finding.oracle },
+                "defaultConfiguration": { "level": level(&finding.severity) },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let base_ids = roots
+        .iter()
+        .filter_map(|(name, root)| {
+            directory_url(root).map(|url| (name.clone(), json!({ "uri": url.as_str() })))
+        })
+        .collect::<Map<_, _>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "movy",
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": base_ids,
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+    use movy_types::{
+        input::MoveAddress,
+        oracle::{FindingLocation, OracleFinding, Severity, SourceSpan},
+    };
+
+    use crate::sarif::{rule_id, to_sarif};
+
+    #[test]
+    fn test_rule_id() {
+        assert_eq!(rule_id("StaticBoolJudgement"), "bool_judgement");
+        assert_eq!(rule_id("AbortCodeOracle"), "abort_code_oracle");
+        assert_eq!(rule_id("taint"), "taint");
+    }
+
+    #[test]
+    fn test_to_sarif() {
+        let location = FindingLocation::module(MoveAddress::from_str("0x42").unwrap(), "pool")
+            .with_function("swap")
+            .with_offset(7);
+        let local = OracleFinding::new(
+            "StaticPrecisionLoss",
+            "precision_loss",
+            Severity::Major,
+            "Division before multiplication",
+        )
+        .with_location(FindingLocation {
+            package_name: Some("movy".to_string()),
+            source: Some(SourceSpan {
+                file: "/movy/sources/pool.move".to_string(),
+                start_line: 12,
+                start_column: 9,
+                end_line: 12,
+                end_column: 30,
+            }),
+            ..location.clone()
+        });
+        let onchain =
+            OracleFinding::new("StaticPrecisionLoss", "precision_loss", Severity::Major, "")
+                .with_location(location.with_function("quote"));
+        let module_wide = OracleFinding::new(
+            "StaticUnusedConstant",
+            "unused_constant",
+            Severity::Informational,
+            "Unused constant EUnused",
+        )
+        .with_location(FindingLocation::module(
+            MoveAddress::from_str("0x42").unwrap(),
+            "pool",
+        ));
+
+        let outside =
+            OracleFinding::new("StaticPrecisionLoss", "precision_loss", Severity::Major, "")
+                .with_location(FindingLocation {
+                    source: Some(SourceSpan {
+                        file: "/elsewhere/sources/my pool.move".to_string(),
+                        start_line: 1,
+                        start_column: 1,
+                        end_line: 1,
+                        end_column: 2,
+                    }),
+                    ..FindingLocation::module(MoveAddress::from_str("0x43").unwrap(), "pool")
+                });
+        let roots = BTreeMap::from([("movy".to_string(), PathBuf::from("/movy"))]);
+
+        let log = to_sarif(
+            &[local.clone(), onchain.clone(), module_wide, outside],
+            &roots,
+        );
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+
+        // One rule per rule id, in the order they are first seen
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "precision_loss");
+        assert_eq!(rules[0]["name"], "PrecisionLoss");
+        assert_eq!(
+            rules[0]["shortDescription"]["text"],
+            "Division or sqrt result is multiplied afterwards"
+        );
+        assert_eq!(rules[0]["defaultConfiguration"]["level"], "error");
+        assert_eq!(rules[1]["id"], "unused_constant");
+        assert_eq!(rules[1]["name"], "UnusedConstant");
+        assert_eq!(rules[1]["defaultConfiguration"]["level"], "note");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(run["originalUriBaseIds"]["movy"]["uri"], "file:///movy/");
+        assert_eq!(results[0]["ruleId"], "precision_loss");
+        assert_eq!(results[0]["ruleIndex"], 0);
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "Division before multiplication"
+        );
+        assert_eq!(
+            results[0]["partialFingerprints"]["movy/v3"],
+            local.fingerprint.as_str()
+        );
+        let logical = &results[0]["locations"][0]["logicalLocations"][0];
+        assert_eq!(logical["kind"], "function");
+        assert_eq!(logical["name"], "swap");
+        assert_eq!(
+            logical["fullyQualifiedName"],
+            format!("{}::pool::swap", MoveAddress::from_str("0x42").unwrap())
+        );
+        let physical = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(physical["artifactLocation"]["uri"], "sources/pool.move");
+        assert_eq!(physical["artifactLocation"]["uriBaseId"], "movy");
+        assert_eq!(physical["region"]["startLine"], 12);
+        assert_eq!(physical["region"]["endColumn"], 30);
+
+        // Without a source span only the logical location is known, and an empty message
+        // falls back to the oracle name
+        assert_eq!(results[1]["ruleIndex"], 0);
+        assert_eq!(results[1]["message"]["text"], "StaticPrecisionLoss");
+        assert!(results[1]["locations"][0].get("physicalLocation").is_none());
+        assert_ne!(
+            results[1]["partialFingerprints"]["movy/v3"],
+            results[0]["partialFingerprints"]["movy/v3"]
+        );
+
+        assert_eq!(results[2]["ruleIndex"], 1);
+        assert_eq!(results[2]["level"], "note");
+        let logical = &results[2]["locations"][0]["logicalLocations"][0];
+        assert_eq!(logical["kind"], "module");
+        assert_eq!(logical["name"], "pool");
+
+        // Files outside of any known package root are file URIs
+        let artifact = &results[3]["locations"][0]["physicalLocation"]["artifactLocation"];
+        assert_eq!(artifact["uri"], "file:///elsewhere/sources/my%20pool.move");
+        assert!(artifact.get("uriBaseId").is_none());
+    }
+}
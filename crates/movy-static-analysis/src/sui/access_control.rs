@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_model::{
+    model::{FunId, FunctionVisibility},
+    ty::Type,
+};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Operation};
+use serde_json::json;
+
+use super::{
+    common::{ModuleAnalysis, get_def_bytecode},
+    generate_bytecode::FunctionInfo,
+};
+use movy_types::oracle::{OracleFinding, Severity};
+
+// Bound on `$t1 := $t0` chains when tracing a temp back to a parameter
+const MAX_ASSIGN_DEPTH: usize = 8;
+
+pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
+    let mut reports = Vec::new();
+    let shared = shared_types(modules);
+    if shared.is_empty() {
+        return reports;
+    }
+
+    for module in modules {
+        let writes = written_params(module);
+        let guards = sender_guards(module);
+
+        for function in module.functions() {
+            if module.is_native(function)
+                || !(function.visibility == FunctionVisibility::Public || function.is_entry)
+            {
+                continue;
+            }
+            let Some(written) = writes.get(&function.fun_id) else {
+                continue;
+            };
+            let objects = written
+                .iter()
+                .filter_map(|(param, fields)| {
+                    let Type::Reference(true, inner) = function.local_types.get(*param)? else {
+                        return None;
+                    };
+                    let name = module.qualified_struct_name(inner)?;
+                    shared.contains(&name).then_some((*param, fields))
+                })
+                .collect::<Vec<_>>();
+            if objects.is_empty()
+                || has_capability(module, function)
+                || guards.contains(&function.fun_id)
+            {
+                continue;
+            }
+            for (param, fields) in objects {
+                let Some(struct_env) = module.struct_of(&function.local_types[param]) else {
+                    continue;
+                };
+                let symbols = module.global_env.symbol_pool();
+                let object = symbols.string(struct_env.get_name()).to_string();
+                let fields = fields
+                    .iter()
+                    .map(|offset| {
+                        symbols
+                            .string(struct_env.get_field_by_offset(*offset).get_name())
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+                reports.push(
+                    OracleFinding::new(
+                        "StaticAccessControl",
+                        "missing_access_control",
+                        Severity::Major,
+                        format!(
+                            "Shared object {} is mutated without a capability or sender check",
+                            object
+                        ),
+                    )
+                    .with_location(module.function_location(function))
+                    .with_extra(json!({
+                        "object": object,
+                        "parameter": param,
+                        "fields": fields,
+                    })),
+                );
+            }
+        }
+    }
+
+    reports
+}
+
+/// Structs passed to `transfer::share_object` or `public_share_object` by any of the
+/// modules, by qualified name as they may be defined in another module than the sharing one.
+fn shared_types(modules: &[ModuleAnalysis]) -> BTreeSet<String> {
+    let mut shared = BTreeSet::new();
+    for module in modules {
+        for function in module.functions() {
+            for instr in function.code.iter() {
+                let SLBytecode::Call(_, _, Operation::Function(mid, fid, tys), _, _) = instr else {
+                    continue;
+                };
+                if !module.is_framework_call(
+                    mid,
+                    fid,
+                    "transfer",
+                    &["share_object", "public_share_object"],
+                ) {
+                    continue;
+                }
+                if let Some(name) = tys.first().and_then(|ty| module.qualified_struct_name(ty)) {
+                    shared.insert(name);
+                }
+            }
+        }
+    }
+    shared
+}
+
+/// The parameter `temp` is a copy or move of, if any.
+fn source_param(function: &FunctionInfo, mut temp: usize, offset: usize) -> Option<usize> {
+    for _ in 0..MAX_ASSIGN_DEPTH {
+        if temp < function.param_count {
+            return Some(temp);
+        }
+        match get_def_bytecode(function, temp, offset)? {
+            SLBytecode::Assign(_, _, src, _) => temp = *src,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// For every function, the `&mut` parameters whose fields it borrows mutably, either
+/// directly or by passing them on to another function of the module that does.
+fn written_params(module: &ModuleAnalysis) -> BTreeMap<FunId, BTreeMap<usize, BTreeSet<usize>>> {
+    let mut writes: BTreeMap<FunId, BTreeMap<usize, BTreeSet<usize>>> = BTreeMap::new();
+    for function in module.functions() {
+        for (offset, instr) in function.code.iter().enumerate() {
+            let SLBytecode::Call(_, dsts, Operation::BorrowField(_, _, _, field), srcs, _) = instr
+            else {
+                continue;
+            };
+            let mutable = dsts
+                .first()
+                .and_then(|dst| function.local_types.get(*dst))
+                .is_some_and(|ty| matches!(ty, Type::Reference(true, _)));
+            if !mutable {
+                continue;
+            }
+            if let Some(param) = srcs
+                .first()
+                .and_then(|s| source_param(function, *s, offset))
+            {
+                writes
+                    .entry(function.fun_id)
+                    .or_default()
+                    .entry(param)
+                    .or_default()
+                    .insert(*field);
+            }
+        }
+    }
+
+    // Propagate through calls until a fixpoint, the module call graph is small
+    loop {
+        let mut changed = false;
+        for function in module.functions() {
+            for (offset, instr) in function.code.iter().enumerate() {
+                let SLBytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) = instr
+                else {
+                    continue;
+                };
+                if *mid != function.module_id {
+                    continue;
+                }
+                let Some(callee) = writes.get(fid).cloned() else {
+                    continue;
+                };
+                for (idx, src) in srcs.iter().enumerate() {
+                    let (Some(fields), Some(param)) =
+                        (callee.get(&idx), source_param(function, *src, offset))
+                    else {
+                        continue;
+                    };
+                    let entry = writes
+                        .entry(function.fun_id)
+                        .or_default()
+                        .entry(param)
+                        .or_default();
+                    for field in fields {
+                        changed |= entry.insert(*field);
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    writes
+}
+
+/// Whether some parameter looks like a capability: a `...Cap` / `...Capability` struct
+/// or an object carrying nothing but its `UID`, i.e. the usual `AdminCap` shape.
+fn has_capability(module: &ModuleAnalysis, function: &FunctionInfo) -> bool {
+    let symbols = module.global_env.symbol_pool();
+    function.local_types[..function.param_count]
+        .iter()
+        .filter_map(|ty| module.struct_of(ty))
+        .any(|s| {
+            let name = symbols.string(s.get_name());
+            name.ends_with("Cap")
+                || name.ends_with("Capability")
+                || (s.get_abilities().has_key() && s.get_field_count() == 1)
+        })
+}
+
+/// Functions comparing `tx_context::sender` against something, directly or through a
+/// function of the module they call.
+fn sender_guards(module: &ModuleAnalysis) -> BTreeSet<FunId> {
+    let mut guards = BTreeSet::new();
+    for function in module.functions() {
+        let mut senders = BTreeSet::new();
+        for instr in function.code.iter() {
+            match instr {
+                SLBytecode::Call(_, dsts, Operation::Function(mid, fid, _), _, _)
+                    if module.is_framework_call(mid, fid, "tx_context", &["sender"]) =>
+                {
+                    senders.extend(dsts.iter().copied());
+                }
+                SLBytecode::Assign(_, dst, src, _) if senders.contains(src) => {
+                    senders.insert(*dst);
+                }
+                SLBytecode::Call(_, _, Operation::Eq | Operation::Neq, srcs, _)
+                    if srcs.iter().any(|s| senders.contains(s)) =>
+                {
+                    guards.insert(function.fun_id);
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    loop {
+        let mut changed = false;
+        for function in module.functions() {
+            if guards.contains(&function.fun_id) {
+                continue;
+            }
+            let calls_guard = function.code.iter().any(|instr| {
+                matches!(
+                    instr,
+                    SLBytecode::Call(_, _, Operation::Function(mid, fid, _), _, _)
+                        if *mid == function.module_id && guards.contains(fid)
+                )
+            });
+            if calls_guard {
+                guards.insert(function.fun_id);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    guards
+}
+
+#[cfg(test)]
+mod test {
+    use super::analyze;
+    use crate::sui::common::test_modules;
+
+    fn flagged(modules: &[(&str, &str)]) -> Vec<String> {
+        analyze(&test_modules(modules))
+            .into_iter()
+            .filter_map(|f| {
+                let location = f.location?;
+                Some(format!("{}::{}", location.module?, location.function?))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_missing_access_control() {
+        let pool = r#"module test::pool;
+
+public struct Pool has key {
+    id: UID,
+    reserve: u64,
+    admin: address,
+}
+
+public struct AdminCap has key {
+    id: UID,
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(Pool { id: object::new(ctx), reserve: 0, admin: ctx.sender() });
+    transfer::transfer(AdminCap { id: object::new(ctx) }, ctx.sender());
+}
+
+public fun set_reserve(pool: &mut Pool, reserve: u64) {
+    pool.reserve = reserve;
+}
+
+public fun set_reserve_with_cap(_: &AdminCap, pool: &mut Pool, reserve: u64) {
+    pool.reserve = reserve;
+}
+
+public fun set_reserve_as_admin(pool: &mut Pool, reserve: u64, ctx: &TxContext) {
+    assert!(ctx.sender() == pool.admin);
+    pool.reserve = reserve;
+}
+"#;
+        assert_eq!(flagged(&[("pool", pool)]), vec!["pool::set_reserve"]);
+    }
+
+    #[test]
+    fn test_shared_by_another_module() {
+        let vault = r#"module test::vault;
+
+public struct Vault has key, store {
+    id: UID,
+    amount: u64,
+}
+
+public fun new(ctx: &mut TxContext): Vault {
+    Vault { id: object::new(ctx), amount: 0 }
+}
+
+public fun deposit(vault: &mut Vault, amount: u64) {
+    vault.amount = vault.amount + amount;
+}
+"#;
+        let registry = r#"module test::registry;
+
+use test::vault;
+
+fun init(ctx: &mut TxContext) {
+    transfer::public_share_object(vault::new(ctx));
+}
+"#;
+        assert_eq!(
+            flagged(&[("vault", vault), ("registry", registry)]),
+            vec!["vault::deposit"]
+        );
+    }
+
+    #[test]
+    fn test_share_outside_the_framework() {
+        // A `transfer::share_object` of another package does not share anything
+        let transfer = r#"module test::transfer;
+
+public fun share_object<T: key + store>(obj: T) {
+    sui::transfer::public_transfer(obj, @0x0);
+}
+"#;
+        let locker = r#"module test::locker;
+
+public struct Locker has key, store {
+    id: UID,
+    value: u64,
+}
+
+fun init(ctx: &mut TxContext) {
+    test::transfer::share_object(Locker { id: object::new(ctx), value: 0 });
+}
+
+public fun set(locker: &mut Locker, value: u64) {
+    locker.value = value;
+}
+"#;
+        assert!(flagged(&[("transfer", transfer), ("locker", locker)]).is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+use move_model::ty::{PrimitiveType, Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Constant, Operation};
+use serde_json::{Value, json};
+
+use super::{
+    common::{ModuleAnalysis, SinkCounter, get_def_offset},
+    generate_bytecode::FunctionInfo,
+};
+use movy_types::oracle::{OracleFinding, Severity};
+
+// Bound on the def-use chains followed backwards from an operand
+const MAX_CHAIN_LEN: usize = 16;
+
+/// One instruction of a def-use chain, `offset` being the stackless bytecode offset.
+#[derive(Debug, Clone)]
+pub(super) struct ChainStep {
+    pub(super) offset: usize,
+    pub(super) code: String,
+}
+
+#[derive(Debug, Clone)]
+struct ArithmeticIssue {
+    rule: &'static str,
+    severity: Severity,
+    message: &'static str,
+    chain: Vec<ChainStep>,
+}
+
+pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
+    let mut reports = Vec::new();
+
+    for module in modules {
+        for function in module.functions() {
+            if module.is_native(function) {
+                continue;
+            }
+            let mut sinks = SinkCounter::default();
+            for issue in detect_arithmetic_order(function) {
+                let sink = sinks.next(issue.rule, &function.name);
+                reports.push(
+                    OracleFinding::new(
+                        "StaticArithmeticOrder",
+                        issue.rule,
+                        issue.severity,
+                        issue.message,
+                    )
+                    .with_location(module.function_location(function).with_sink(sink))
+                    .with_extra(json!({ "def_use_chain": chain_json(&issue.chain) })),
+                );
+            }
+        }
+    }
+
+    reports
+}
+
+fn detect_arithmetic_order(function: &FunctionInfo) -> Vec<ArithmeticIssue> {
+    let mut issues = Vec::new();
+    for (offset, instr) in function.code.iter().enumerate() {
+        let SLBytecode::Call(_, _, op @ (Operation::Mul | Operation::Div), srcs, _) = instr else {
+            continue;
+        };
+        if srcs.len() != 2 {
+            continue;
+        }
+        let sink = ChainStep {
+            offset,
+            code: describe(instr),
+        };
+
+        if matches!(op, Operation::Mul) {
+            // Division feeding a multiplication is reported by `precision_loss`
+            for src in srcs.iter() {
+                if let Some(mut chain) = trace(function, *src, offset, |_, op, srcs| {
+                    is_truncating_cast(function, op, srcs)
+                }) {
+                    chain.push(sink.clone());
+                    issues.push(ArithmeticIssue {
+                        rule: "truncating_cast_before_multiply",
+                        severity: Severity::Medium,
+                        message: "Value is narrowed by a cast before being multiplied",
+                        chain,
+                    });
+                }
+            }
+        } else if let Some(mut chain) = trace(function, srcs[0], offset, |mul, op, mul_srcs| {
+            // `a * b / c` computed in the operand width instead of u128
+            matches!(op, Operation::Mul)
+                && mul_srcs.len() == 2
+                && mul_srcs.iter().all(|s| {
+                    is_narrow_integer(function.local_types.get(*s))
+                        && !is_constant(function, *s, mul)
+                })
+        }) {
+            chain.push(sink);
+            issues.push(ArithmeticIssue {
+                rule: "unwidened_mul_div",
+                severity: Severity::Minor,
+                message: "mul_div computed without widening to u128 may overflow",
+                chain,
+            });
+        }
+    }
+    issues
+}
+
+pub(super) fn chain_json(chain: &[ChainStep]) -> Value {
+    Value::Array(
+        chain
+            .iter()
+            .map(|s| json!({"offset": s.offset, "code": s.code}))
+            .collect(),
+    )
+}
+
+/// Follows `temp` back through assignments and widening casts to an operation accepted by
+/// `is_origin` (called with the operation's own offset), returning the chain from that
+/// operation to the last step before `offset`.
+pub(super) fn trace<F>(
+    function: &FunctionInfo,
+    temp: usize,
+    offset: usize,
+    is_origin: F,
+) -> Option<Vec<ChainStep>>
+where
+    F: Fn(usize, &Operation, &[usize]) -> bool,
+{
+    let mut chain = Vec::new();
+    let mut temp = temp;
+    let mut offset = offset;
+    for _ in 0..MAX_CHAIN_LEN {
+        let def = get_def_offset(function, temp, offset)?;
+        // A use before the definition means a loop carried value, stop there
+        if def >= offset {
+            return None;
+        }
+        let instr = function.code.get(def)?;
+        chain.push(ChainStep {
+            offset: def,
+            code: describe(instr),
+        });
+        match instr {
+            SLBytecode::Call(_, _, op, srcs, _) if is_origin(def, op, srcs) => {
+                chain.reverse();
+                return Some(chain);
+            }
+            SLBytecode::Assign(_, _, src, _) => temp = *src,
+            SLBytecode::Call(_, _, op, srcs, _) if is_widening_cast(function, op, srcs) => {
+                temp = srcs[0]
+            }
+            _ => return None,
+        }
+        offset = def;
+    }
+    None
+}
+
+fn integer_width(ty: Option<&Type>) -> Option<u16> {
+    match ty? {
+        Type::Primitive(PrimitiveType::U8) => Some(8),
+        Type::Primitive(PrimitiveType::U16) => Some(16),
+        Type::Primitive(PrimitiveType::U32) => Some(32),
+        Type::Primitive(PrimitiveType::U64) => Some(64),
+        Type::Primitive(PrimitiveType::U128) => Some(128),
+        Type::Primitive(PrimitiveType::U256) => Some(256),
+        _ => None,
+    }
+}
+
+fn cast_width(op: &Operation) -> Option<u16> {
+    match op {
+        Operation::CastU8 => Some(8),
+        Operation::CastU16 => Some(16),
+        Operation::CastU32 => Some(32),
+        Operation::CastU64 => Some(64),
+        Operation::CastU128 => Some(128),
+        Operation::CastU256 => Some(256),
+        _ => None,
+    }
+}
+
+fn is_truncating_cast(function: &FunctionInfo, op: &Operation, srcs: &[usize]) -> bool {
+    match (
+        cast_width(op),
+        srcs.first()
+            .and_then(|s| integer_width(function.local_types.get(*s))),
+    ) {
+        (Some(to), Some(from)) => to < from,
+        _ => false,
+    }
+}
+
+fn is_widening_cast(function: &FunctionInfo, op: &Operation, srcs: &[usize]) -> bool {
+    match (
+        cast_width(op),
+        srcs.first()
+            .and_then(|s| integer_width(function.local_types.get(*s))),
+    ) {
+        (Some(to), Some(from)) => to >= from,
+        _ => false,
+    }
+}
+
+fn is_narrow_integer(ty: Option<&Type>) -> bool {
+    integer_width(ty).is_some_and(|w| w <= 64)
+}
+
+fn is_constant(function: &FunctionInfo, temp: usize, offset: usize) -> bool {
+    get_def_offset(function, temp, offset)
+        .and_then(|def| function.code.get(def))
+        .is_some_and(|instr| matches!(instr, SLBytecode::Load(..)))
+}
+
+pub(super) fn describe(instr: &SLBytecode) -> String {
+    let temps = |ts: &[usize]| {
+        ts.iter()
+            .map(|t| format!("$t{}", t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match instr {
+        SLBytecode::Assign(_, dst, src, _) => format!("$t{} := $t{}", dst, src),
+        SLBytecode::Load(_, dst, constant) => match constant {
+            Constant::Bool(b) => format!("$t{} := {}", dst, b),
+            _ => format!("$t{} := <constant>", dst),
+        },
+        SLBytecode::Call(_, dsts, op, srcs, _) => {
+            let rhs = match (op, srcs.as_slice()) {
+                (Operation::Add, [a, b]) => format!("$t{} + $t{}", a, b),
+                (Operation::Sub, [a, b]) => format!("$t{} - $t{}", a, b),
+                (Operation::Mul, [a, b]) => format!("$t{} * $t{}", a, b),
+                (Operation::Div, [a, b]) => format!("$t{} / $t{}", a, b),
+                (Operation::Mod, [a, b]) => format!("$t{} % $t{}", a, b),
+                (op, [a]) if cast_width(op).is_some() => {
+                    format!("(u{}) $t{}", cast_width(op).unwrap_or_default(), a)
+                }
+                (_, srcs) => format!("call({})", temps(srcs)),
+            };
+            format!("{} := {}", temps(dsts), rhs)
+        }
+        _ => String::from("<instruction>"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use move_model::ty::{PrimitiveType, Type};
+    use move_stackless_bytecode::stackless_bytecode::{
+        AssignKind, AttrId, Bytecode, Constant, Operation,
+    };
+
+    use super::detect_arithmetic_order;
+    use crate::sui::generate_bytecode::test_function;
+
+    fn ty(p: PrimitiveType) -> Type {
+        Type::Primitive(p)
+    }
+
+    fn call(dst: usize, op: Operation, srcs: Vec<usize>) -> Bytecode {
+        Bytecode::Call(AttrId::new(0), vec![dst], op, srcs, None)
+    }
+
+    fn rules(code: Vec<Bytecode>, local_types: Vec<Type>) -> Vec<&'static str> {
+        let function = test_function("f", 2, local_types, vec![], code);
+        detect_arithmetic_order(&function)
+            .into_iter()
+            .map(|i| i.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_truncating_cast_before_multiply() {
+        let u64_ = ty(PrimitiveType::U64);
+        let u128_ = ty(PrimitiveType::U128);
+        let code = vec![
+            call(2, Operation::CastU64, vec![0]),
+            call(3, Operation::Mul, vec![2, 1]),
+        ];
+        assert_eq!(
+            rules(code, vec![u128_, u64_.clone(), u64_.clone(), u64_]),
+            vec!["truncating_cast_before_multiply"]
+        );
+    }
+
+    #[test]
+    fn test_unwidened_mul_div() {
+        let u64_ = ty(PrimitiveType::U64);
+        let code = vec![
+            call(2, Operation::Mul, vec![0, 1]),
+            call(3, Operation::Div, vec![2, 1]),
+        ];
+        assert_eq!(
+            rules(code, vec![u64_.clone(); 4]),
+            vec!["unwidened_mul_div"]
+        );
+
+        // Widened to u128 before multiplying
+        let u128_ = ty(PrimitiveType::U128);
+        let code = vec![
+            call(2, Operation::CastU128, vec![0]),
+            call(3, Operation::CastU128, vec![1]),
+            call(4, Operation::Mul, vec![2, 3]),
+            call(5, Operation::Div, vec![4, 3]),
+        ];
+        let types = vec![
+            u64_.clone(),
+            u64_,
+            u128_.clone(),
+            u128_.clone(),
+            u128_.clone(),
+            u128_,
+        ];
+        assert!(rules(code, types).is_empty());
+    }
+
+    #[test]
+    fn test_unwidened_mul_div_constant() {
+        // `$t1` is a constant at the multiplication and only redefined before the division
+        let u64_ = ty(PrimitiveType::U64);
+        let code = vec![
+            Bytecode::Load(AttrId::new(0), 1, Constant::U64(1000)),
+            call(2, Operation::Mul, vec![0, 1]),
+            Bytecode::Assign(AttrId::new(0), 1, 0, AssignKind::Copy),
+            call(3, Operation::Div, vec![2, 1]),
+        ];
+        assert!(rules(code, vec![u64_; 4]).is_empty());
+    }
+}
@@ -1,6 +1,5 @@
 use move_model::ty::{PrimitiveType, Type};
 use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Constant, Operation};
-use serde_json::json;
 
 use super::{
     common::{ModuleAnalysis, get_def_bytecode},
@@ -17,15 +16,15 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
                 continue;
             }
             if detect_bool_judgement(function) {
-                reports.push(OracleFinding {
-                    oracle: "StaticBoolJudgement".to_string(),
-                    severity: Severity::Minor,
-                    extra: json!({
-                        "module": module.qualified_module_name(),
-                        "function": function.name.clone(),
-                        "message": "Unnecessary bool judgement (boolean compared with boolean literal)"
-                    }),
-                });
+                reports.push(
+                    OracleFinding::new(
+                        "StaticBoolJudgement",
+                        "bool_judgement",
+                        Severity::Minor,
+                        "Unnecessary bool judgement (boolean compared with boolean literal)",
+                    )
+                    .with_location(module.function_location(function)),
+                );
             }
         }
     }
@@ -1,17 +1,20 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use itertools::Itertools;
 use move_binary_format::{CompiledModule, binary_config::BinaryConfig};
 use move_core_types::{
     account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
 };
-use move_model::model::{FunId, GlobalEnv};
+use move_model::{
+    model::{FunId, GlobalEnv, ModuleId as ModelModuleId, StructEnv},
+    ty::Type,
+};
 use move_stackless_bytecode::stackless_bytecode::Bytecode;
 use movy_replay::{
     db::{ObjectStoreCachedStore, ObjectStoreInfo},
     env::SuiTestingEnv,
 };
-use movy_types::{error::MovyError, input::MoveAddress};
+use movy_types::{error::MovyError, input::MoveAddress, oracle::FindingLocation};
 use sui_types::{base_types::ObjectID, storage::ObjectStore};
 
 use super::generate_bytecode::{
@@ -30,6 +33,12 @@ impl ModuleAnalysis {
         format!("{}::{}", module_id.address(), module_id.name())
     }
 
+    pub fn function_location(&self, function: &FunctionInfo) -> FindingLocation {
+        let module_id = self.compiled.self_id();
+        FindingLocation::module((*module_id.address()).into(), module_id.name().as_str())
+            .with_function(&function.name)
+    }
+
     pub fn functions(&self) -> &[FunctionInfo] {
         &self.stackless.functions
     }
@@ -56,6 +65,91 @@ impl ModuleAnalysis {
             .string(fun_id.symbol())
             .to_string()
     }
+
+    /// `(module, function)` names of a callee, e.g. `("tx_context", "sender")`.
+    pub fn callee_name(&self, module_id: &ModelModuleId, fun_id: &FunId) -> (String, String) {
+        let module_env = self.global_env.get_module(*module_id);
+        let module_name = self
+            .global_env
+            .symbol_pool()
+            .string(module_env.get_name().name())
+            .to_string();
+        (module_name, self.get_function_name(fun_id))
+    }
+
+    /// The address of a module of the environment.
+    pub fn module_address(&self, module_id: &ModelModuleId) -> AccountAddress {
+        *self
+            .global_env
+            .get_module(*module_id)
+            .get_verified_module()
+            .address()
+    }
+
+    /// `<address>::<module>::<function>` of a function, comparable across the analyses of
+    /// different modules.
+    pub fn qualified_function_name(&self, module_id: &ModelModuleId, fun_id: &FunId) -> String {
+        format!(
+            "{}::{}",
+            self.global_env
+                .get_module(*module_id)
+                .get_verified_module()
+                .self_id(),
+            self.get_function_name(fun_id)
+        )
+    }
+
+    /// Whether a callee is one of `functions` of the Sui framework module `module`, e.g.
+    /// `0x2::transfer::share_object`.
+    pub fn is_framework_call(
+        &self,
+        module_id: &ModelModuleId,
+        fun_id: &FunId,
+        module: &str,
+        functions: &[&str],
+    ) -> bool {
+        if self.module_address(module_id) != AccountAddress::TWO {
+            return false;
+        }
+        let (callee_module, callee) = self.callee_name(module_id, fun_id);
+        callee_module == module && functions.contains(&callee.as_str())
+    }
+
+    /// `<address>::<module>::<struct>` of the struct behind `ty`, comparable across the
+    /// analyses of different modules.
+    pub fn qualified_struct_name(&self, ty: &Type) -> Option<String> {
+        let struct_env = self.struct_of(ty)?;
+        Some(format!(
+            "{}::{}",
+            struct_env.module_env.get_verified_module().self_id(),
+            self.global_env.symbol_pool().string(struct_env.get_name())
+        ))
+    }
+
+    /// The struct behind `ty`, looking through references.
+    pub fn struct_of(&self, ty: &Type) -> Option<StructEnv<'_>> {
+        match ty {
+            Type::Reference(_, inner) => self.struct_of(inner),
+            Type::Datatype(mid, sid, _) => self
+                .global_env
+                .get_module(*mid)
+                .into_structs()
+                .find(|s| s.get_id() == *sid),
+            _ => None,
+        }
+    }
+
+    /// `(module, struct)` names behind `ty`, e.g. `("random", "Random")` for `&Random`.
+    pub fn struct_name(&self, ty: &Type) -> Option<(String, String)> {
+        let struct_env = self.struct_of(ty)?;
+        let symbols = self.global_env.symbol_pool();
+        Some((
+            symbols
+                .string(struct_env.module_env.get_name().name())
+                .to_string(),
+            symbols.string(struct_env.get_name()).to_string(),
+        ))
+    }
 }
 
 fn fetch_compiled_module<T>(
@@ -93,14 +187,14 @@ where
     Ok(module.ok())
 }
 
-fn collect_modules_rec<T>(
-    env: &SuiTestingEnv<T>,
+fn collect_modules_rec<F>(
+    fetch: &F,
     module: CompiledModule,
     visited: &mut BTreeSet<(String, String)>,
     ordered: &mut Vec<CompiledModule>,
 ) -> Result<(), MovyError>
 where
-    T: ObjectStore + ObjectStoreInfo,
+    F: Fn(&ModuleId) -> Result<Option<CompiledModule>, MovyError>,
 {
     let module_id = module.self_id();
     let key = (
@@ -116,8 +210,8 @@ where
         if visited.contains(&dep_key) {
             continue;
         }
-        if let Some(dep_module) = fetch_compiled_module(env, &dep)? {
-            collect_modules_rec(env, dep_module, visited, ordered)?;
+        if let Some(dep_module) = fetch(&dep)? {
+            collect_modules_rec(fetch, dep_module, visited, ordered)?;
         }
     }
 
@@ -125,28 +219,25 @@ where
     Ok(())
 }
 
-fn collect_modules<T>(
-    env: &SuiTestingEnv<T>,
-    root: CompiledModule,
-) -> Result<Vec<CompiledModule>, MovyError>
+fn collect_modules<F>(fetch: &F, root: CompiledModule) -> Result<Vec<CompiledModule>, MovyError>
 where
-    T: ObjectStore + ObjectStoreInfo,
+    F: Fn(&ModuleId) -> Result<Option<CompiledModule>, MovyError>,
 {
     let mut visited = BTreeSet::new();
     let mut ordered = Vec::new();
-    collect_modules_rec(env, root, &mut visited, &mut ordered)?;
+    collect_modules_rec(fetch, root, &mut visited, &mut ordered)?;
     Ok(ordered)
 }
 
-fn analyze_module<T>(
-    env: &SuiTestingEnv<T>,
+fn analyze_module<F>(
+    fetch: &F,
     compiled: CompiledModule,
     std_dependency: Option<CompiledModule>,
 ) -> Result<Option<ModuleAnalysis>, MovyError>
 where
-    T: ObjectStore + ObjectStoreInfo,
+    F: Fn(&ModuleId) -> Result<Option<CompiledModule>, MovyError>,
 {
-    let mut modules = collect_modules(env, compiled)?;
+    let mut modules = collect_modules(fetch, compiled)?;
     if modules.is_empty() {
         return Ok(None);
     }
@@ -173,6 +264,41 @@ where
     }))
 }
 
+fn std_vector_id() -> ModuleId {
+    ModuleId::new(AccountAddress::ONE, Identifier::new("vector").unwrap())
+}
+
+/// Modules of an on-chain package, in the order of its metadata.
+pub fn package_modules<T>(
+    env: &SuiTestingEnv<T>,
+    package_id: MoveAddress,
+) -> Result<Vec<CompiledModule>, MovyError>
+where
+    T: ObjectStore + ObjectStoreInfo + ObjectStoreCachedStore,
+{
+    let Some(package_meta) = env.inner().get_package_info(package_id)? else {
+        return Ok(vec![]);
+    };
+    let Some(object) = env.inner().get_object(&ObjectID::from(package_id)) else {
+        return Ok(vec![]);
+    };
+    let Some(package) = object.data.try_as_package() else {
+        return Ok(vec![]);
+    };
+    Ok(package_meta
+        .modules
+        .iter()
+        .filter_map(|m| {
+            package
+                .deserialize_module_by_str(
+                    &m.module_id.module_name,
+                    &BinaryConfig::new_unpublishable(),
+                )
+                .ok()
+        })
+        .collect())
+}
+
 pub async fn load_target_modules<T>(
     env: &SuiTestingEnv<T>,
     target_packages: &Vec<MoveAddress>,
@@ -183,10 +309,8 @@ where
     let mut seen = BTreeSet::new();
     let mut analyses = Vec::new();
 
-    let std_dependency = fetch_compiled_module(
-        env,
-        &ModuleId::new(AccountAddress::ONE, Identifier::new("vector").unwrap()),
-    )?;
+    let fetch = |module_id: &ModuleId| fetch_compiled_module(env, module_id);
+    let std_dependency = fetch(&std_vector_id())?;
 
     for package_id in target_packages {
         let Some(package_meta) = env.inner().get_package_info(*package_id)? else {
@@ -214,7 +338,7 @@ where
                 continue;
             };
 
-            if let Some(analysis) = analyze_module(env, compiled, std_dependency.clone())? {
+            if let Some(analysis) = analyze_module(&fetch, compiled, std_dependency.clone())? {
                 analyses.push(analysis);
             }
         }
@@ -223,11 +347,66 @@ where
     Ok(analyses)
 }
 
+/// Same as [`load_target_modules`], but resolving dependencies from the given modules only,
+/// i.e. without any chain state.
+pub fn load_local_modules(
+    targets: &[CompiledModule],
+    dependencies: &[CompiledModule],
+) -> Result<Vec<ModuleAnalysis>, MovyError> {
+    let available = targets
+        .iter()
+        .chain(dependencies.iter())
+        .map(|m| (m.self_id(), m))
+        .collect::<BTreeMap<_, _>>();
+    let fetch = |module_id: &ModuleId| -> Result<Option<CompiledModule>, MovyError> {
+        Ok(available.get(module_id).map(|m| (*m).clone()))
+    };
+    let std_dependency = fetch(&std_vector_id())?;
+
+    let mut analyses = Vec::new();
+    for compiled in targets.iter().sorted_by_key(|m| m.self_id()) {
+        if let Some(analysis) = analyze_module(&fetch, compiled.clone(), std_dependency.clone())? {
+            analyses.push(analysis);
+        }
+    }
+
+    Ok(analyses)
+}
+
+/// Numbers the sinks of each kind in a function, in code order, such that findings on
+/// several of them get their own fingerprints. Stackless offsets are not bytecode offsets
+/// and shift on any change of the function, so they are not used for that.
+#[derive(Debug, Default)]
+pub struct SinkCounter {
+    seen: BTreeMap<(String, String), usize>,
+}
+
+impl SinkCounter {
+    /// `<kind>:<function>#<n>` for the n-th sink of `kind` seen in `function`.
+    pub fn next(&mut self, kind: &str, function: &str) -> String {
+        let count = self
+            .seen
+            .entry((kind.to_string(), function.to_string()))
+            .or_default();
+        let sink = format!("{}:{}#{}", kind, function, count);
+        *count += 1;
+        sink
+    }
+}
+
 pub fn get_def_bytecode(
     function: &FunctionInfo,
     temp: usize,
     code_offset: usize,
 ) -> Option<&Bytecode> {
+    function
+        .code
+        .get(get_def_offset(function, temp, code_offset)?)
+}
+
+/// Offset of the definition of `temp` reaching `code_offset`, the closest preceding one
+/// when there are several.
+pub fn get_def_offset(function: &FunctionInfo, temp: usize, code_offset: usize) -> Option<usize> {
     if temp >= function.def_attrid.len() {
         return None;
     }
@@ -236,7 +415,7 @@ pub fn get_def_bytecode(
         return None;
     }
     if defs.len() == 1 {
-        return function.code.get(defs[0]);
+        return Some(defs[0]);
     }
     let mut candidates = defs
         .iter()
@@ -245,7 +424,62 @@ pub fn get_def_bytecode(
         .collect::<Vec<_>>();
     candidates.sort();
     if let Some(idx) = candidates.last() {
-        return function.code.get(*idx);
+        return Some(*idx);
+    }
+    Some(defs[0])
+}
+
+/// Analyses of the modules of a package `test` built from `(module, source)`.
+#[cfg(test)]
+pub(crate) fn test_modules(modules: &[(&str, &str)]) -> Vec<ModuleAnalysis> {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use movy_sui::compile::{SuiCompiledPackage, build_package_resolved};
+
+    fn visit(
+        module_id: &ModuleId,
+        all: &BTreeMap<ModuleId, CompiledModule>,
+        visited: &mut BTreeSet<ModuleId>,
+        ordered: &mut Vec<CompiledModule>,
+    ) {
+        let Some(module) = all.get(module_id) else {
+            return;
+        };
+        if !visited.insert(module_id.clone()) {
+            return;
+        }
+        for dep in module.immediate_dependencies() {
+            visit(&dep, all, visited, ordered);
+        }
+        ordered.push(module.clone());
     }
-    function.code.get(defs[0])
+
+    let dir = SuiCompiledPackage::quick_package("test", modules).unwrap();
+    let (artifacts, _) = build_package_resolved(dir.path(), false).unwrap();
+    let all = artifacts
+        .package
+        .all_compiled_units()
+        .map(|unit| (unit.module.self_id(), unit.module.clone()))
+        .collect::<BTreeMap<_, _>>();
+    // Dependencies first, as the model builder expects
+    let mut ordered = Vec::new();
+    let mut visited = BTreeSet::new();
+    for module_id in all.keys() {
+        visit(module_id, &all, &mut visited, &mut ordered);
+    }
+
+    ordered
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| *m.address() == AccountAddress::ZERO)
+        .map(|(idx, compiled)| {
+            let (stackless, global_env) =
+                generate_stackless_bytecode_for_module(&ordered[..idx], compiled).unwrap();
+            ModuleAnalysis {
+                compiled: compiled.clone(),
+                stackless,
+                global_env,
+            }
+        })
+        .collect()
 }
@@ -211,3 +211,40 @@ fn record(slots: &mut [Vec<usize>], idx: usize, offset: usize) {
         list.push(offset);
     }
 }
+
+/// Builds a public function from hand written stackless bytecode, for detector tests.
+#[cfg(test)]
+pub(crate) fn test_function(
+    name: &str,
+    param_count: usize,
+    local_types: Vec<Type>,
+    return_types: Vec<Type>,
+    code: Vec<Bytecode>,
+) -> FunctionInfo {
+    let args_count = local_types.len();
+    let (def_attrid, use_attrid) = compute_def_use(args_count, &code);
+    let cfg = if code.is_empty() {
+        None
+    } else {
+        Some(StacklessControlFlowGraph::new_forward(&code))
+    };
+    FunctionInfo {
+        module_id: ModuleId::new(0),
+        fun_id: FunId::new(SymbolPool::new().make(name)),
+        idx: 0,
+        name: name.to_string(),
+        visibility: FunctionVisibility::Public,
+        is_entry: false,
+        param_count,
+        args_count,
+        code,
+        local_types,
+        return_types,
+        acquires: Vec::new(),
+        location_table: BTreeMap::new(),
+        loop_invariants: BTreeSet::new(),
+        cfg,
+        def_attrid,
+        use_attrid,
+    }
+}
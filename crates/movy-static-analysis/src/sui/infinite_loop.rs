@@ -1,5 +1,4 @@
 use move_stackless_bytecode::stackless_bytecode::Bytecode as SLBytecode;
-use serde_json::json;
 
 use super::{
     common::{ModuleAnalysis, get_def_bytecode},
@@ -16,15 +15,15 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
                 continue;
             }
             if detect_infinite_loop(function) {
-                reports.push(OracleFinding {
-                    oracle: "StaticInfiniteLoop".to_string(),
-                    severity: Severity::Major,
-                    extra: json!({
-                        "module": module.qualified_module_name(),
-                        "function": function.name.clone(),
-                        "message": "Potential infinite loop detected from constant branch condition"
-                    }),
-                });
+                reports.push(
+                    OracleFinding::new(
+                        "StaticInfiniteLoop",
+                        "infinite_loop",
+                        Severity::Major,
+                        "Potential infinite loop detected from constant branch condition",
+                    )
+                    .with_location(module.function_location(function)),
+                );
             }
         }
     }
@@ -1,14 +1,23 @@
+mod access_control;
+mod arithmetic_order;
 mod bool_judgement;
 mod common;
 mod generate_bytecode;
 mod infinite_loop;
 mod precision_loss;
+mod random_usage;
+mod taint;
 mod type_conversion;
 mod unchecked_return;
 mod unused_const;
 mod unused_private_fun;
 mod unused_struct;
+mod upgrade;
+mod weak_randomness;
 
+use std::collections::BTreeMap;
+
+use move_binary_format::CompiledModule;
 use movy_replay::{
     db::{ObjectStoreCachedStore, ObjectStoreInfo},
     env::SuiTestingEnv,
@@ -18,6 +27,11 @@ use sui_types::storage::ObjectStore;
 
 pub use common::ModuleAnalysis;
 pub use generate_bytecode::FunctionInfo;
+pub use taint::{
+    CallSite, FunctionSummary, SinkHit, SinkSpec, SinkTarget, TaintAnalysis, TaintConfig,
+    TaintSource,
+};
+pub use upgrade::check_upgrade;
 
 /// Run all static analyses that were originally implemented as once-per-world oracles.
 /// Returns structured findings; the caller can decide how to surface them.
@@ -29,16 +43,51 @@ where
     T: ObjectStore + ObjectStoreInfo + ObjectStoreCachedStore,
 {
     let modules = common::load_target_modules(env, target_packages).await?;
+    let mut packages = BTreeMap::new();
+    for package_id in target_packages {
+        packages.insert(*package_id, common::package_modules(env, *package_id)?);
+    }
+
+    run_detectors(&modules, &packages)
+}
+
+/// Same as [`run_all`], but on compiled modules directly, e.g. a local build or a folder of
+/// `.mv` files. Dependencies are only resolved from `dependencies`, so no chain state is
+/// needed.
+pub fn run_local(
+    targets: &[CompiledModule],
+    dependencies: &[CompiledModule],
+) -> Result<Vec<OracleFinding>, MovyError> {
+    let modules = common::load_local_modules(targets, dependencies)?;
+    let mut packages: BTreeMap<MoveAddress, Vec<CompiledModule>> = BTreeMap::new();
+    for module in targets {
+        packages
+            .entry((*module.address()).into())
+            .or_default()
+            .push(module.clone());
+    }
+
+    run_detectors(&modules, &packages)
+}
 
+fn run_detectors(
+    modules: &[ModuleAnalysis],
+    packages: &BTreeMap<MoveAddress, Vec<CompiledModule>>,
+) -> Result<Vec<OracleFinding>, MovyError> {
     let mut reports = Vec::new();
-    reports.extend(bool_judgement::analyze(&modules));
-    reports.extend(infinite_loop::analyze(&modules));
-    reports.extend(precision_loss::analyze(&modules));
-    reports.extend(type_conversion::analyze(&modules));
-    reports.extend(unchecked_return::analyze(&modules));
-    reports.extend(unused_const::analyze(env, target_packages).await?);
-    reports.extend(unused_private_fun::analyze(env, target_packages).await?);
-    reports.extend(unused_struct::analyze(env, target_packages).await?);
+    reports.extend(access_control::analyze(modules));
+    reports.extend(arithmetic_order::analyze(modules));
+    reports.extend(bool_judgement::analyze(modules));
+    reports.extend(infinite_loop::analyze(modules));
+    reports.extend(precision_loss::analyze(modules));
+    reports.extend(random_usage::analyze(modules));
+    reports.extend(taint::analyze(modules));
+    reports.extend(type_conversion::analyze(modules));
+    reports.extend(unchecked_return::analyze(modules));
+    reports.extend(weak_randomness::analyze(modules));
+    reports.extend(unused_const::analyze(packages)?);
+    reports.extend(unused_private_fun::analyze(packages)?);
+    reports.extend(unused_struct::analyze(packages)?);
 
     Ok(reports)
 }
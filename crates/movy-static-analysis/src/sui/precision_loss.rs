@@ -2,7 +2,11 @@ use move_model::symbol::SymbolPool;
 use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Operation};
 use serde_json::json;
 
-use super::{common::ModuleAnalysis, generate_bytecode::FunctionInfo};
+use super::{
+    arithmetic_order::{ChainStep, chain_json, describe, trace},
+    common::ModuleAnalysis,
+    generate_bytecode::FunctionInfo,
+};
 use movy_types::oracle::{OracleFinding, Severity};
 
 pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
@@ -13,16 +17,17 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
             if module.is_native(function) {
                 continue;
             }
-            if detect_precision_loss(function, module.global_env.symbol_pool()) {
-                reports.push(OracleFinding {
-                    oracle: "StaticPrecisionLoss".to_string(),
-                    severity: Severity::Medium,
-                    extra: json!({
-                        "module": module.qualified_module_name(),
-                        "function": function.name.clone(),
-                        "message": "Potential precision loss from multiplication involving division/sqrt"
-                    }),
-                });
+            if let Some(chain) = detect_precision_loss(function, module.global_env.symbol_pool()) {
+                reports.push(
+                    OracleFinding::new(
+                        "StaticPrecisionLoss",
+                        "precision_loss",
+                        Severity::Medium,
+                        "Potential precision loss from multiplication involving division/sqrt",
+                    )
+                    .with_location(module.function_location(function))
+                    .with_extra(json!({ "def_use_chain": chain_json(&chain) })),
+                );
             }
         }
     }
@@ -30,33 +35,80 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
     reports
 }
 
-fn detect_precision_loss(function: &FunctionInfo, symbol_pool: &SymbolPool) -> bool {
+/// Returns the def-use chain from the first division or `sqrt` whose result reaches a
+/// multiplication, following assignments and widening casts.
+fn detect_precision_loss(
+    function: &FunctionInfo,
+    symbol_pool: &SymbolPool,
+) -> Option<Vec<ChainStep>> {
     for (offset, instr) in function.code.iter().enumerate() {
-        if let SLBytecode::Call(_, _, Operation::Mul, srcs, _) = instr {
-            if srcs.len() != 2 {
-                continue;
-            }
-            let op1 = match super::common::get_def_bytecode(function, srcs[0], offset) {
-                Some(code) => code,
-                None => continue,
-            };
-            let op2 = match super::common::get_def_bytecode(function, srcs[1], offset) {
-                Some(code) => code,
-                None => continue,
-            };
-            if is_div(op1) || is_div(op2) || is_sqrt(op1, symbol_pool) || is_sqrt(op2, symbol_pool)
-            {
-                return true;
+        let SLBytecode::Call(_, _, Operation::Mul, srcs, _) = instr else {
+            continue;
+        };
+        if srcs.len() != 2 {
+            continue;
+        }
+        for src in srcs.iter() {
+            if let Some(mut chain) = trace(function, *src, offset, |_, op, _| {
+                matches!(op, Operation::Div) || is_sqrt(op, symbol_pool)
+            }) {
+                chain.push(ChainStep {
+                    offset,
+                    code: describe(instr),
+                });
+                return Some(chain);
             }
         }
     }
-    false
+    None
 }
 
-fn is_div(bytecode: &SLBytecode) -> bool {
-    matches!(bytecode, SLBytecode::Call(_, _, Operation::Div, _, _))
+fn is_sqrt(op: &Operation, symbol_pool: &SymbolPool) -> bool {
+    matches!(op, Operation::Function(_, fid, _) if symbol_pool.string(fid.symbol()).as_str() == "sqrt")
 }
 
-fn is_sqrt(bytecode: &SLBytecode, symbol_pool: &SymbolPool) -> bool {
-    matches!(bytecode, SLBytecode::Call(_, _, Operation::Function(_, fid, _), _, _) if symbol_pool.string(fid.symbol()).as_str() == "sqrt")
+#[cfg(test)]
+mod test {
+    use move_model::{
+        symbol::SymbolPool,
+        ty::{PrimitiveType, Type},
+    };
+    use move_stackless_bytecode::stackless_bytecode::{AssignKind, AttrId, Bytecode, Operation};
+
+    use super::detect_precision_loss;
+    use crate::sui::generate_bytecode::test_function;
+
+    fn call(dst: usize, op: Operation, srcs: Vec<usize>) -> Bytecode {
+        Bytecode::Call(AttrId::new(0), vec![dst], op, srcs, None)
+    }
+
+    #[test]
+    fn test_divide_before_multiply() {
+        let code = vec![
+            call(2, Operation::Div, vec![0, 1]),
+            Bytecode::Assign(AttrId::new(0), 3, 2, AssignKind::Copy),
+            call(4, Operation::CastU128, vec![3]),
+            call(5, Operation::CastU128, vec![1]),
+            call(6, Operation::Mul, vec![4, 5]),
+        ];
+        let mut types = vec![Type::Primitive(PrimitiveType::U64); 4];
+        types.extend(vec![Type::Primitive(PrimitiveType::U128); 3]);
+        let function = test_function("f", 2, types, vec![], code);
+        let chain = detect_precision_loss(&function, &SymbolPool::new()).unwrap();
+        assert_eq!(
+            chain.iter().map(|s| s.offset).collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+    }
+
+    #[test]
+    fn test_multiply_before_divide() {
+        let code = vec![
+            call(2, Operation::Mul, vec![0, 1]),
+            call(3, Operation::Div, vec![2, 1]),
+        ];
+        let types = vec![Type::Primitive(PrimitiveType::U64); 4];
+        let function = test_function("f", 2, types, vec![], code);
+        assert!(detect_precision_loss(&function, &SymbolPool::new()).is_none());
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::BTreeSet;
+
+use move_model::model::FunctionVisibility;
+use serde_json::json;
+
+use super::{
+    common::{ModuleAnalysis, SinkCounter},
+    generate_bytecode::FunctionInfo,
+    taint::{CallSite, SinkSpec, SinkTarget, TaintAnalysis, TaintConfig, TaintSource},
+};
+use movy_types::oracle::{OracleFinding, Severity};
+
+const RANDOM_OUTPUTS: TaintConfig = TaintConfig {
+    sources: &[
+        CallSite::new("random", "generate_bytes"),
+        CallSite::new("random", "generate_bool"),
+        CallSite::new("random", "generate_u8"),
+        CallSite::new("random", "generate_u16"),
+        CallSite::new("random", "generate_u32"),
+        CallSite::new("random", "generate_u64"),
+        CallSite::new("random", "generate_u128"),
+        CallSite::new("random", "generate_u256"),
+        CallSite::new("random", "generate_u8_in_range"),
+        CallSite::new("random", "generate_u16_in_range"),
+        CallSite::new("random", "generate_u32_in_range"),
+        CallSite::new("random", "generate_u64_in_range"),
+        CallSite::new("random", "generate_u128_in_range"),
+        CallSite::new("random", "shuffle"),
+    ],
+    sinks: &[SinkSpec {
+        kind: "branch",
+        target: SinkTarget::Branch,
+        ignore: &[],
+    }],
+    sanitize_on_assert: false,
+    implicit_flows: true,
+};
+
+/// Which of `Random` / `RandomGenerator` the function takes.
+fn random_params(module: &ModuleAnalysis, function: &FunctionInfo) -> (bool, bool) {
+    let mut random = false;
+    let mut generator = false;
+    for ty in function.local_types[..function.param_count].iter() {
+        match module.struct_name(ty) {
+            Some((m, s)) if m == "random" && s == "Random" => random = true,
+            Some((m, s)) if m == "random" && s == "RandomGenerator" => generator = true,
+            _ => {}
+        }
+    }
+    (random, generator)
+}
+
+pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
+    let mut reports = Vec::new();
+
+    let analysis = TaintAnalysis::run(modules, &RANDOM_OUTPUTS);
+    for module in modules {
+        for function in module.functions() {
+            if module.is_native(function) {
+                continue;
+            }
+            let (random, generator) = random_params(module, function);
+            if !random && !generator {
+                continue;
+            }
+            let public = function.visibility == FunctionVisibility::Public;
+            let exposed = public || function.is_entry;
+
+            if random && public {
+                reports.push(
+                    OracleFinding::new(
+                        "StaticRandomUsage",
+                        "public_random_function",
+                        Severity::Major,
+                        "Function taking Random is public, it must be a private entry function",
+                    )
+                    .with_location(module.function_location(function)),
+                );
+            }
+
+            let Some(summary) = analysis.summary(module, function) else {
+                continue;
+            };
+            let from_random = |labels: &BTreeSet<TaintSource>| {
+                labels.iter().any(|l| matches!(l, TaintSource::Call(_)))
+            };
+            let random_branches = summary
+                .sinks
+                .iter()
+                .filter(|hit| from_random(&hit.sources))
+                .map(|hit| (hit.function.as_str(), hit.offset))
+                .collect::<BTreeSet<_>>();
+            // Implicit flows make a result picked under a random branch random as well,
+            // while a constant returned after both arms join is not
+            let returns_random = summary.returns.iter().any(from_random);
+
+            if exposed && returns_random {
+                reports.push(
+                    OracleFinding::new(
+                        "StaticRandomUsage",
+                        "random_result_observable",
+                        Severity::Major,
+                        "Random dependent outcome is returned to the caller",
+                    )
+                    .with_location(module.function_location(function))
+                    .with_extra(json!({
+                        "random_branches": random_branches
+                            .iter()
+                            .map(|(function, offset)| json!({"function": function, "offset": offset}))
+                            .collect::<Vec<_>>(),
+                    })),
+                );
+            }
+
+            // A public function can be called from Move code that checks the outcome and
+            // aborts when it does not like it, retrying until it wins. Every branch is its
+            // own finding such that accepting one does not hide the ones added later.
+            if !public {
+                continue;
+            }
+            let mut sinks = SinkCounter::default();
+            for (branch_function, offset) in random_branches {
+                reports.push(
+                    OracleFinding::new(
+                        "StaticRandomUsage",
+                        "random_test_and_abort",
+                        Severity::Major,
+                        "Caller can observe the random outcome and abort the transaction",
+                    )
+                    .with_location(
+                        module
+                            .function_location(function)
+                            .with_sink(sinks.next("branch", branch_function)),
+                    )
+                    .with_extra(json!({
+                        "random_branch": {"function": branch_function, "offset": offset},
+                        "takes_generator": generator,
+                    })),
+                );
+            }
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::analyze;
+    use crate::sui::common::test_modules;
+
+    const GAME: &str = r#"module test::game;
+
+use sui::random::{Self, Random, RandomGenerator};
+
+public struct Game has key {
+    id: UID,
+    wins: u64,
+}
+
+entry fun play(game: &mut Game, r: &Random, ctx: &mut TxContext) {
+    let mut generator = random::new_generator(r, ctx);
+    if (generator.generate_bool()) {
+        game.wins = game.wins + 1;
+    }
+}
+
+public fun flip(r: &Random, ctx: &mut TxContext): bool {
+    let mut generator = random::new_generator(r, ctx);
+    generator.generate_bool()
+}
+
+public fun roll(generator: &mut RandomGenerator): u64 {
+    if (generator.generate_u8() < 128) {
+        1
+    } else {
+        2
+    }
+}
+
+public fun count_win(game: &mut Game, generator: &mut RandomGenerator): u64 {
+    if (generator.generate_bool()) {
+        game.wins = game.wins + 1;
+    };
+    7
+}
+"#;
+
+    fn flagged() -> BTreeSet<(String, String)> {
+        analyze(&test_modules(&[("game", GAME)]))
+            .into_iter()
+            .filter_map(|f| Some((f.rule, f.location?.function?)))
+            .collect()
+    }
+
+    fn rules_of(flagged: &BTreeSet<(String, String)>, function: &str) -> BTreeSet<String> {
+        flagged
+            .iter()
+            .filter(|(_, f)| f == function)
+            .map(|(rule, _)| rule.clone())
+            .collect()
+    }
+
+    fn set(rules: &[&str]) -> BTreeSet<String> {
+        rules.iter().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn test_private_entry() {
+        assert!(rules_of(&flagged(), "play").is_empty());
+    }
+
+    #[test]
+    fn test_public_random_result() {
+        assert_eq!(
+            rules_of(&flagged(), "flip"),
+            set(&["public_random_function", "random_result_observable"])
+        );
+    }
+
+    #[test]
+    fn test_result_picked_by_random_branch() {
+        assert_eq!(
+            rules_of(&flagged(), "roll"),
+            set(&["random_result_observable", "random_test_and_abort"])
+        );
+    }
+
+    #[test]
+    fn test_result_independent_of_random_branch() {
+        assert_eq!(
+            rules_of(&flagged(), "count_win"),
+            set(&["random_test_and_abort"])
+        );
+    }
+}
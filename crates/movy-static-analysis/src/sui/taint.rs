@@ -0,0 +1,717 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_core_types::account_address::AccountAddress;
+use move_model::{model::FunctionVisibility, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Label, Operation};
+use serde_json::json;
+
+use super::{
+    common::{ModuleAnalysis, SinkCounter, get_def_bytecode, get_def_offset},
+    generate_bytecode::FunctionInfo,
+};
+use movy_types::oracle::{OracleFinding, Severity};
+
+// Passes over a function body before giving up on a fixpoint, loops rarely need more
+const MAX_FUNCTION_PASSES: usize = 8;
+// Rounds of summary propagation over the call graph of the analyzed modules
+const MAX_SUMMARY_ROUNDS: usize = 16;
+// Copies followed back from a compared value to the variable it was read from
+const MAX_ASSIGN_DEPTH: usize = 8;
+
+/// A framework callee by its `module::function` name, e.g. `tx_context::sender`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallSite {
+    pub module: &'static str,
+    pub function: &'static str,
+}
+
+impl CallSite {
+    pub const fn new(module: &'static str, function: &'static str) -> Self {
+        Self { module, function }
+    }
+}
+
+impl std::fmt::Display for CallSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.module, self.function)
+    }
+}
+
+/// Where a tainted value comes from, relative to the function it is tracked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaintSource {
+    Param(usize),
+    Call(CallSite),
+}
+
+/// What must not be tainted.
+#[derive(Debug, Clone, Copy)]
+pub enum SinkTarget {
+    /// An argument of a callee, by index.
+    Call(CallSite, usize),
+    /// Either operand of `%`.
+    Modulo,
+    /// The condition of a branch.
+    Branch,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SinkSpec {
+    pub kind: &'static str,
+    pub target: SinkTarget,
+    /// Sources that are expected at this sink, e.g. the sender as transfer recipient.
+    pub ignore: &'static [CallSite],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaintConfig {
+    /// Callees whose results are tainted.
+    pub sources: &'static [CallSite],
+    pub sinks: &'static [SinkSpec],
+    /// Whether comparing a value in a branch condition, i.e. an `assert!`, sanitizes it.
+    pub sanitize_on_assert: bool,
+    /// Whether values defined under a branch carry the taint of its condition, e.g. a
+    /// result picked by `if (random) { 1 } else { 2 }`.
+    pub implicit_flows: bool,
+}
+
+/// A tainted argument reaching a sink, `module`, `function` and `offset` being where the
+/// sink call is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkHit {
+    pub kind: &'static str,
+    pub module: String,
+    pub function: String,
+    pub offset: usize,
+    pub sources: BTreeSet<TaintSource>,
+}
+
+/// What a caller needs to know about a function: the parameters and sources flowing into
+/// its return values, and the ones reaching sinks in it or its callees.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionSummary {
+    pub returns: Vec<BTreeSet<TaintSource>>,
+    pub sinks: Vec<SinkHit>,
+}
+
+/// Intra-procedural taint propagation over stackless bytecode, made inter-procedural
+/// across the analyzed modules through function summaries keyed by qualified name. With
+/// `sanitize_on_assert`, a value compared in a branch condition, i.e. an `assert!`, is
+/// sanitized where every path to its use went through the comparison.
+pub struct TaintAnalysis<'a> {
+    config: &'a TaintConfig,
+    summaries: BTreeMap<String, FunctionSummary>,
+}
+
+impl<'a> TaintAnalysis<'a> {
+    pub fn run(modules: &[ModuleAnalysis], config: &'a TaintConfig) -> Self {
+        let mut analysis = Self {
+            config,
+            summaries: BTreeMap::new(),
+        };
+        for _ in 0..MAX_SUMMARY_ROUNDS {
+            let mut changed = false;
+            for module in modules {
+                for function in module.functions() {
+                    if module.is_native(function) {
+                        continue;
+                    }
+                    let summary = analysis.analyze_function(module, function);
+                    let name =
+                        module.qualified_function_name(&function.module_id, &function.fun_id);
+                    if analysis.summaries.get(&name) != Some(&summary) {
+                        analysis.summaries.insert(name, summary);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        analysis
+    }
+
+    pub fn summary(
+        &self,
+        module: &ModuleAnalysis,
+        function: &FunctionInfo,
+    ) -> Option<&FunctionSummary> {
+        self.summaries
+            .get(&module.qualified_function_name(&function.module_id, &function.fun_id))
+    }
+
+    fn callee(&self, module: &ModuleAnalysis, instr: &SLBytecode) -> Option<CallSite> {
+        let SLBytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) = instr else {
+            return None;
+        };
+        let address = module.module_address(mid);
+        if address != AccountAddress::ONE && address != AccountAddress::TWO {
+            return None;
+        }
+        let (module, function) = module.callee_name(mid, fid);
+        let matches = |site: &&CallSite| site.module == module && site.function == function;
+        self.config
+            .sources
+            .iter()
+            .chain(self.config.sinks.iter().filter_map(|s| match &s.target {
+                SinkTarget::Call(site, _) => Some(site),
+                _ => None,
+            }))
+            .find(matches)
+            .copied()
+    }
+
+    fn record(
+        summary: &mut FunctionSummary,
+        spec: &SinkSpec,
+        module: &ModuleAnalysis,
+        function: &FunctionInfo,
+        offset: usize,
+        labels: &BTreeSet<TaintSource>,
+    ) {
+        let sources = labels
+            .iter()
+            .filter(|s| !matches!(s, TaintSource::Call(c) if spec.ignore.contains(c)))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        if !sources.is_empty() {
+            summary.sinks.push(SinkHit {
+                kind: spec.kind,
+                module: module.compiled.self_id().name().to_string(),
+                function: function.name.clone(),
+                offset,
+                sources,
+            });
+        }
+    }
+
+    fn analyze_function(
+        &self,
+        module: &ModuleAnalysis,
+        function: &FunctionInfo,
+    ) -> FunctionSummary {
+        let mut taint = vec![BTreeSet::new(); function.local_types.len()];
+        for (param, labels) in taint.iter_mut().enumerate().take(function.param_count) {
+            labels.insert(TaintSource::Param(param));
+        }
+        let mut summary = FunctionSummary {
+            returns: vec![BTreeSet::new(); function.return_types.len()],
+            sinks: vec![],
+        };
+        let sanitized = if self.config.sanitize_on_assert {
+            sanitized_temps(function)
+        } else {
+            vec![BTreeSet::new(); function.code.len()]
+        };
+        let control = if self.config.implicit_flows {
+            controlling_conditions(function)
+        } else {
+            vec![vec![]; function.code.len()]
+        };
+
+        for _ in 0..MAX_FUNCTION_PASSES {
+            let mut changed = false;
+            summary.sinks.clear();
+            for (offset, instr) in function.code.iter().enumerate() {
+                let read = |temp: usize| {
+                    if sanitized[offset].contains(&temp) {
+                        BTreeSet::new()
+                    } else {
+                        taint[temp].clone()
+                    }
+                };
+                let implicit = control[offset]
+                    .iter()
+                    .flat_map(|cond| read(*cond))
+                    .collect::<BTreeSet<_>>();
+                let (dsts, flowing) = match instr {
+                    SLBytecode::Assign(_, dst, src, _) => (vec![*dst], read(*src)),
+                    SLBytecode::Load(_, dst, _) => (vec![*dst], BTreeSet::new()),
+                    SLBytecode::Ret(_, srcs) => {
+                        for (ret, src) in summary.returns.iter_mut().zip(srcs.iter()) {
+                            ret.extend(read(*src));
+                            ret.extend(implicit.iter().copied());
+                        }
+                        continue;
+                    }
+                    SLBytecode::Call(_, dsts, Operation::Function(mid, fid, _), srcs, _) => {
+                        let args = srcs.iter().map(|s| read(*s)).collect::<Vec<_>>();
+                        let site = self.callee(module, instr);
+                        for spec in self.config.sinks.iter() {
+                            if let SinkTarget::Call(callee, argument) = spec.target
+                                && Some(callee) == site
+                                && let Some(arg) = args.get(argument)
+                            {
+                                Self::record(&mut summary, spec, module, function, offset, arg);
+                            }
+                        }
+
+                        if let Some(site) = site
+                            && self.config.sources.contains(&site)
+                        {
+                            for dst in dsts {
+                                changed |= taint[*dst].insert(TaintSource::Call(site));
+                            }
+                            continue;
+                        }
+                        if let Some(callee) = self
+                            .summaries
+                            .get(&module.qualified_function_name(mid, fid))
+                        {
+                            let substitute = |labels: &BTreeSet<TaintSource>| {
+                                let mut out = BTreeSet::new();
+                                for label in labels {
+                                    match label {
+                                        TaintSource::Param(idx) => {
+                                            out.extend(args.get(*idx).into_iter().flatten())
+                                        }
+                                        TaintSource::Call(_) => {
+                                            out.insert(*label);
+                                        }
+                                    }
+                                }
+                                out
+                            };
+                            for hit in callee.sinks.iter() {
+                                let sources = substitute(&hit.sources);
+                                if !sources.is_empty() {
+                                    summary.sinks.push(SinkHit {
+                                        sources,
+                                        ..hit.clone()
+                                    });
+                                }
+                            }
+                            for (dst, ret) in dsts.iter().zip(callee.returns.iter()) {
+                                for label in substitute(ret).into_iter().chain(implicit.clone()) {
+                                    changed |= taint[*dst].insert(label);
+                                }
+                            }
+                            continue;
+                        }
+                        // Unknown callees propagate every argument to every result
+                        (dsts.clone(), args.into_iter().flatten().collect())
+                    }
+                    SLBytecode::Call(_, dsts, op, srcs, _) => {
+                        let flowing = srcs.iter().flat_map(|s| read(*s)).collect::<BTreeSet<_>>();
+                        if matches!(op, Operation::Mod) {
+                            for spec in self.config.sinks.iter() {
+                                if matches!(spec.target, SinkTarget::Modulo) {
+                                    Self::record(
+                                        &mut summary,
+                                        spec,
+                                        module,
+                                        function,
+                                        offset,
+                                        &flowing,
+                                    );
+                                }
+                            }
+                        }
+                        (dsts.clone(), flowing)
+                    }
+                    SLBytecode::Branch(_, _, _, cond) => {
+                        let labels = read(*cond);
+                        for spec in self.config.sinks.iter() {
+                            if matches!(spec.target, SinkTarget::Branch) {
+                                Self::record(&mut summary, spec, module, function, offset, &labels);
+                            }
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                for dst in dsts {
+                    for label in flowing.iter().chain(implicit.iter()) {
+                        changed |= taint[dst].insert(*label);
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        summary
+    }
+}
+
+fn is_comparison(op: &Operation) -> bool {
+    matches!(
+        op,
+        Operation::Eq
+            | Operation::Neq
+            | Operation::Lt
+            | Operation::Le
+            | Operation::Gt
+            | Operation::Ge
+    )
+}
+
+fn successors(
+    code: &[SLBytecode],
+    label_offsets: &BTreeMap<Label, u16>,
+    offset: usize,
+) -> Vec<usize> {
+    let target = |label| label_offsets.get(label).map(|o| *o as usize);
+    match &code[offset] {
+        SLBytecode::Jump(_, label) => target(label).into_iter().collect(),
+        SLBytecode::Branch(_, then_label, else_label, _) => {
+            [target(then_label), target(else_label)]
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+        SLBytecode::Ret(..) | SLBytecode::Abort(..) => vec![],
+        _ if offset + 1 < code.len() => vec![offset + 1],
+        _ => vec![],
+    }
+}
+
+/// For every offset, the conditions of the branches deciding whether it runs: the offsets
+/// reached from one arm only. An arm that cannot return, e.g. the abort of an `assert!`,
+/// decides nothing a caller sees.
+fn controlling_conditions(function: &FunctionInfo) -> Vec<Vec<usize>> {
+    let code = &function.code;
+    let label_offsets = SLBytecode::label_offsets(code);
+    let reachable = |start: usize| {
+        let mut seen = BTreeSet::new();
+        let mut worklist = vec![start];
+        while let Some(offset) = worklist.pop() {
+            if seen.insert(offset) {
+                worklist.extend(successors(code, &label_offsets, offset));
+            }
+        }
+        seen
+    };
+    let returns = |arm: &BTreeSet<usize>| {
+        arm.iter()
+            .any(|offset| matches!(code[*offset], SLBytecode::Ret(..)))
+    };
+    let mut control = vec![vec![]; code.len()];
+    for instr in code {
+        let SLBytecode::Branch(_, then_label, else_label, cond) = instr else {
+            continue;
+        };
+        let (Some(then_start), Some(else_start)) =
+            (label_offsets.get(then_label), label_offsets.get(else_label))
+        else {
+            continue;
+        };
+        let then_arm = reachable(*then_start as usize);
+        let else_arm = reachable(*else_start as usize);
+        if !returns(&then_arm) || !returns(&else_arm) {
+            continue;
+        }
+        for controlled in then_arm.symmetric_difference(&else_arm) {
+            control[*controlled].push(*cond);
+        }
+    }
+    control
+}
+
+/// Temps defined by `instr`.
+fn defined(instr: &SLBytecode) -> &[usize] {
+    match instr {
+        SLBytecode::Assign(_, dst, _, _) | SLBytecode::Load(_, dst, _) => std::slice::from_ref(dst),
+        SLBytecode::Call(_, dsts, _, _, _) => dsts,
+        _ => &[],
+    }
+}
+
+/// Temps compared by the condition `cond` of the branch at `offset`, together with the
+/// temps they were copied from.
+fn checked_temps(function: &FunctionInfo, cond: usize, offset: usize) -> BTreeSet<usize> {
+    let mut checked = BTreeSet::new();
+    let Some(SLBytecode::Call(_, _, op, srcs, _)) = get_def_bytecode(function, cond, offset) else {
+        return checked;
+    };
+    if !is_comparison(op) {
+        return checked;
+    }
+    let def_offset = get_def_offset(function, cond, offset).unwrap_or(offset);
+    for src in srcs {
+        let (mut temp, mut at) = (*src, def_offset);
+        checked.insert(temp);
+        for _ in 0..MAX_ASSIGN_DEPTH {
+            let Some(def) = get_def_offset(function, temp, at) else {
+                break;
+            };
+            let Some(SLBytecode::Assign(_, _, from, _)) = function.code.get(def) else {
+                break;
+            };
+            (temp, at) = (*from, def);
+            checked.insert(temp);
+        }
+    }
+    checked
+}
+
+/// For every offset, the temps checked by a comparison on all paths reaching it and not
+/// redefined since. A check on one arm of an `if` does not sanitize the code after it.
+fn sanitized_temps(function: &FunctionInfo) -> Vec<BTreeSet<usize>> {
+    let code = &function.code;
+    let label_offsets = SLBytecode::label_offsets(code);
+    let mut state: Vec<Option<BTreeSet<usize>>> = vec![None; code.len()];
+    if code.is_empty() {
+        return vec![];
+    }
+    state[0] = Some(BTreeSet::new());
+    let mut worklist = vec![0];
+    while let Some(offset) = worklist.pop() {
+        let Some(mut out) = state[offset].clone() else {
+            continue;
+        };
+        for temp in defined(&code[offset]) {
+            out.remove(temp);
+        }
+        if let SLBytecode::Branch(_, _, _, cond) = &code[offset] {
+            out.extend(checked_temps(function, *cond, offset));
+        }
+        for next in successors(code, &label_offsets, offset) {
+            let joined = match &state[next] {
+                Some(known) => known & &out,
+                None => out.clone(),
+            };
+            if state[next].as_ref() != Some(&joined) {
+                state[next] = Some(joined);
+                worklist.push(next);
+            }
+        }
+    }
+    state.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// Values an attacker picks freely when calling an entry function, as opposed to objects.
+fn is_attacker_value(ty: &Type) -> bool {
+    match ty {
+        Type::Primitive(_) => true,
+        Type::Vector(inner) => is_attacker_value(inner),
+        _ => false,
+    }
+}
+
+const SENDER: CallSite = CallSite::new("tx_context", "sender");
+
+const ENTRY_TAINT: TaintConfig = TaintConfig {
+    sources: &[SENDER],
+    sinks: &[
+        SinkSpec {
+            kind: "transfer_recipient",
+            target: SinkTarget::Call(CallSite::new("transfer", "public_transfer"), 1),
+            ignore: &[SENDER],
+        },
+        SinkSpec {
+            kind: "split_amount",
+            target: SinkTarget::Call(CallSite::new("coin", "split"), 1),
+            ignore: &[],
+        },
+        // `balance::split` is how the framework withdraws from a `Balance`
+        SinkSpec {
+            kind: "withdraw_amount",
+            target: SinkTarget::Call(CallSite::new("balance", "split"), 1),
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "dynamic_field_key",
+            target: SinkTarget::Call(CallSite::new("dynamic_field", "add"), 1),
+            ignore: &[SENDER],
+        },
+    ],
+    sanitize_on_assert: true,
+    implicit_flows: false,
+};
+
+pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
+    let mut reports = Vec::new();
+
+    let analysis = TaintAnalysis::run(modules, &ENTRY_TAINT);
+    for module in modules {
+        let module_name = module.compiled.self_id().name().to_string();
+        for function in module.functions() {
+            if module.is_native(function)
+                || !(function.visibility == FunctionVisibility::Public || function.is_entry)
+            {
+                continue;
+            }
+            let Some(summary) = analysis.summary(module, function) else {
+                continue;
+            };
+            let mut hits: BTreeMap<(&str, String, usize), BTreeSet<TaintSource>> = BTreeMap::new();
+            for hit in summary.sinks.iter() {
+                let sources = hit.sources.iter().filter(|s| match s {
+                    TaintSource::Param(idx) => function
+                        .local_types
+                        .get(*idx)
+                        .is_some_and(is_attacker_value),
+                    TaintSource::Call(_) => true,
+                });
+                // Sinks reached in another module are named after it
+                let sink_function = if hit.module == module_name {
+                    hit.function.clone()
+                } else {
+                    format!("{}::{}", hit.module, hit.function)
+                };
+                hits.entry((hit.kind, sink_function, hit.offset))
+                    .or_default()
+                    .extend(sources);
+            }
+            let mut sinks = SinkCounter::default();
+            for ((kind, sink_function, offset), sources) in hits {
+                let sink = sinks.next(kind, &sink_function);
+                if sources.is_empty() {
+                    continue;
+                }
+                let sources = sources
+                    .iter()
+                    .map(|s| match s {
+                        TaintSource::Param(idx) => format!("parameter {}", idx),
+                        TaintSource::Call(site) => site.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                let severity = if kind == "transfer_recipient" {
+                    Severity::Major
+                } else {
+                    Severity::Medium
+                };
+                reports.push(
+                    OracleFinding::new(
+                        "StaticTaint",
+                        &format!("tainted_{}", kind),
+                        severity,
+                        format!(
+                            "Unchecked {} reaches the {} of a sensitive call",
+                            sources.join(", "),
+                            kind.replace('_', " ")
+                        ),
+                    )
+                    .with_location(module.function_location(function).with_sink(sink))
+                    .with_extra(json!({
+                        "sink": kind,
+                        "sink_function": sink_function,
+                        "sink_offset": offset,
+                        "sources": sources,
+                    })),
+                );
+            }
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use super::analyze;
+    use crate::sui::common::test_modules;
+
+    const BANK: &str = r#"module test::bank;
+
+use sui::balance::Balance;
+use sui::coin::Coin;
+use sui::sui::SUI;
+use test::vault;
+
+const MAX: u64 = 1000;
+
+public struct Bank has key {
+    id: UID,
+    funds: Balance<SUI>,
+}
+
+public fun withdraw(bank: &mut Bank, amount: u64): Balance<SUI> {
+    bank.funds.split(amount)
+}
+
+public fun withdraw_doubled(bank: &mut Bank, amount: u64): Balance<SUI> {
+    bank.funds.split(double(amount))
+}
+
+public fun withdraw_checked(bank: &mut Bank, amount: u64): Balance<SUI> {
+    assert!(amount <= MAX);
+    bank.funds.split(amount)
+}
+
+public fun withdraw_checked_on_one_path(bank: &mut Bank, amount: u64, small: bool): Balance<SUI> {
+    if (small) {
+        assert!(amount <= MAX);
+    };
+    bank.funds.split(amount)
+}
+
+public fun withdraw_through_vault(bank: &mut Bank, amount: u64): Balance<SUI> {
+    vault::take(&mut bank.funds, amount)
+}
+
+public fun pay(coin: Coin<SUI>, recipient: address) {
+    transfer::public_transfer(coin, recipient);
+}
+
+public fun pay_sender(coin: Coin<SUI>, ctx: &TxContext) {
+    transfer::public_transfer(coin, ctx.sender());
+}
+
+fun double(value: u64): u64 {
+    value * 2
+}
+"#;
+
+    const VAULT: &str = r#"module test::vault;
+
+use sui::balance::Balance;
+use sui::sui::SUI;
+
+public(package) fun take(funds: &mut Balance<SUI>, amount: u64): Balance<SUI> {
+    funds.split(amount)
+}
+"#;
+
+    fn flagged() -> Vec<(String, String, String)> {
+        analyze(&test_modules(&[("bank", BANK), ("vault", VAULT)]))
+            .into_iter()
+            .filter_map(|f| {
+                let sink_function = f.extra["sink_function"].as_str()?.to_string();
+                Some((f.rule, f.location?.function?, sink_function))
+            })
+            .collect()
+    }
+
+    fn has(flagged: &[(String, String, String)], rule: &str, function: &str) -> bool {
+        flagged.iter().any(|(r, f, _)| r == rule && f == function)
+    }
+
+    #[test]
+    fn test_propagation() {
+        let flagged = flagged();
+        assert!(has(&flagged, "tainted_withdraw_amount", "withdraw"));
+        assert!(has(&flagged, "tainted_withdraw_amount", "withdraw_doubled"));
+        assert!(has(&flagged, "tainted_transfer_recipient", "pay"));
+        assert!(!has(&flagged, "tainted_transfer_recipient", "pay_sender"));
+    }
+
+    #[test]
+    fn test_sanitizer_on_every_path() {
+        let flagged = flagged();
+        assert!(!has(
+            &flagged,
+            "tainted_withdraw_amount",
+            "withdraw_checked"
+        ));
+        assert!(has(
+            &flagged,
+            "tainted_withdraw_amount",
+            "withdraw_checked_on_one_path"
+        ));
+    }
+
+    #[test]
+    fn test_summary_across_modules() {
+        let flagged = flagged();
+        assert!(flagged.contains(&(
+            "tainted_withdraw_amount".to_string(),
+            "withdraw_through_vault".to_string(),
+            "vault::take".to_string(),
+        )));
+        // `take` is not callable from outside the package
+        assert!(!flagged.iter().any(|(_, f, _)| f == "take"));
+    }
+}
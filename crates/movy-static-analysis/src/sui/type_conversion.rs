@@ -1,6 +1,5 @@
 use move_model::ty::{PrimitiveType, Type};
 use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Operation};
-use serde_json::json;
 
 use super::{common::ModuleAnalysis, generate_bytecode::FunctionInfo};
 use movy_types::oracle::{OracleFinding, Severity};
@@ -14,15 +13,15 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
                 continue;
             }
             if detect_unnecessary_type_conversion(function) {
-                reports.push(OracleFinding {
-                    oracle: "StaticTypeConversion".to_string(),
-                    severity: Severity::Minor,
-                    extra: json!({
-                        "module": module.qualified_module_name(),
-                        "function": function.name.clone(),
-                        "message": "Unnecessary type conversion"
-                    }),
-                });
+                reports.push(
+                    OracleFinding::new(
+                        "StaticTypeConversion",
+                        "type_conversion",
+                        Severity::Minor,
+                        "Unnecessary type conversion",
+                    )
+                    .with_location(module.function_location(function)),
+                );
             }
         }
     }
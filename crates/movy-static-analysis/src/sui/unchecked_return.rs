@@ -14,16 +14,18 @@ pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
                 continue;
             }
             for fid in detect_unchecked_return(function) {
-                reports.push(OracleFinding {
-                    oracle: "StaticUncheckedReturn".to_string(),
-                    severity: Severity::Minor,
-                    extra: json!({
-                        "module": module.qualified_module_name(),
-                        "function": function.name.clone(),
+                reports.push(
+                    OracleFinding::new(
+                        "StaticUncheckedReturn",
+                        "unchecked_return",
+                        Severity::Minor,
+                        "Return value dropped without handling",
+                    )
+                    .with_location(module.function_location(function))
+                    .with_extra(json!({
                         "callee": module.get_function_name(&fid),
-                        "message": "Return value dropped without handling"
-                    }),
-                });
+                    })),
+                );
             }
         }
     }
@@ -1,53 +1,21 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use move_binary_format::{
-    binary_config::BinaryConfig,
+    CompiledModule,
     file_format::{Bytecode, Visibility},
 };
-use movy_replay::{
-    db::{ObjectStoreCachedStore, ObjectStoreInfo},
-    env::SuiTestingEnv,
-};
-use movy_types::oracle::{OracleFinding, Severity};
+use movy_types::oracle::{FindingLocation, OracleFinding, Severity};
 use movy_types::{error::MovyError, input::MoveAddress};
-use serde_json::json;
-use sui_types::base_types::ObjectID;
-use sui_types::storage::ObjectStore;
 
-pub async fn analyze<T>(
-    env: &SuiTestingEnv<T>,
-    target_packages: &Vec<MoveAddress>,
-) -> Result<Vec<OracleFinding>, MovyError>
-where
-    T: ObjectStore + ObjectStoreInfo + ObjectStoreCachedStore,
-{
+pub fn analyze(
+    packages: &BTreeMap<MoveAddress, Vec<CompiledModule>>,
+) -> Result<Vec<OracleFinding>, MovyError> {
     let mut reports = Vec::new();
 
-    for pkg in target_packages {
-        let Some(pkg_obj) = env.inner().get_object(&ObjectID::from(*pkg)) else {
-            continue;
-        };
-        let Some(package_meta) = env.inner().get_package_info(*pkg)? else {
-            continue;
-        };
-        let Some(pkg_data) = pkg_obj.data.try_as_package() else {
-            continue;
-        };
-
-        let modules = package_meta
-            .modules
-            .iter()
-            .map(|m| m.module_id.module_name.clone())
-            .collect::<Vec<_>>();
-
+    for (pkg, modules) in packages {
         let mut unused_friend_functions = modules
             .iter()
-            .flat_map(|m| {
-                let Ok(module_data) =
-                    pkg_data.deserialize_module_by_str(m, &BinaryConfig::new_unpublishable())
-                else {
-                    return BTreeSet::new();
-                };
+            .flat_map(|module_data| {
                 module_data
                     .function_defs()
                     .iter()
@@ -64,12 +32,7 @@ where
             })
             .collect::<BTreeSet<_>>();
 
-        for module in modules {
-            let Ok(module_data) =
-                pkg_data.deserialize_module_by_str(&module, &BinaryConfig::new_unpublishable())
-            else {
-                continue;
-            };
+        for module_data in modules {
             let mut unused_private_functions = module_data
                 .function_defs()
                 .iter()
@@ -121,37 +84,33 @@ where
                     }
                 }
             }
-            if !unused_private_functions.is_empty() {
-                let unused = unused_private_functions
-                    .iter()
-                    .map(|(module_id, func)| format!("{module_id}::{func}"))
-                    .collect::<Vec<_>>();
-                reports.push(OracleFinding {
-                    oracle: "StaticUnusedPrivateFunction".to_string(),
-                    severity: Severity::Informational,
-                    extra: json!({
-                        "package": pkg.to_string(),
-                        "module": module.to_string(),
-                        "functions": unused,
-                        "message": "Private functions are never invoked"
-                    }),
-                });
+            for (module_id, func) in unused_private_functions.iter() {
+                reports.push(
+                    OracleFinding::new(
+                        "StaticUnusedPrivateFunction",
+                        "unused_private_function",
+                        Severity::Informational,
+                        "Private function is never invoked",
+                    )
+                    .with_location(
+                        FindingLocation::module(*pkg, module_id.name().as_str())
+                            .with_function(func),
+                    ),
+                );
             }
         }
-        if !unused_friend_functions.is_empty() {
-            let unused = unused_friend_functions
-                .iter()
-                .map(|(module_id, func)| format!("{module_id}::{func}"))
-                .collect::<Vec<_>>();
-            reports.push(OracleFinding {
-                oracle: "StaticUnusedFriendFunction".to_string(),
-                severity: Severity::Informational,
-                extra: json!({
-                    "package": pkg.to_string(),
-                    "functions": unused,
-                    "message": "Friend functions are never invoked"
-                }),
-            });
+        for (module_id, func) in unused_friend_functions.iter() {
+            reports.push(
+                OracleFinding::new(
+                    "StaticUnusedFriendFunction",
+                    "unused_friend_function",
+                    Severity::Informational,
+                    "Friend function is never invoked",
+                )
+                .with_location(
+                    FindingLocation::module(*pkg, module_id.name().as_str()).with_function(func),
+                ),
+            );
         }
     }
 
@@ -1,44 +1,21 @@
+use std::collections::BTreeMap;
+
 use move_binary_format::{
-    binary_config::BinaryConfig,
+    CompiledModule,
     file_format::{Bytecode, SignatureToken},
     internals::ModuleIndex,
 };
-use movy_replay::{
-    db::{ObjectStoreCachedStore, ObjectStoreInfo},
-    env::SuiTestingEnv,
-};
-use movy_types::oracle::{OracleFinding, Severity};
+use movy_types::oracle::{FindingLocation, OracleFinding, Severity};
 use movy_types::{error::MovyError, input::MoveAddress};
 use serde_json::json;
-use sui_types::base_types::ObjectID;
-use sui_types::storage::ObjectStore;
 
-pub async fn analyze<T>(
-    env: &SuiTestingEnv<T>,
-    target_packages: &Vec<MoveAddress>,
-) -> Result<Vec<OracleFinding>, MovyError>
-where
-    T: ObjectStore + ObjectStoreInfo + ObjectStoreCachedStore,
-{
+pub fn analyze(
+    packages: &BTreeMap<MoveAddress, Vec<CompiledModule>>,
+) -> Result<Vec<OracleFinding>, MovyError> {
     let mut reports = Vec::new();
 
-    for pkg in target_packages {
-        let Some(pkg_obj) = env.inner().get_object(&ObjectID::from(*pkg)) else {
-            continue;
-        };
-        let Some(package_meta) = env.inner().get_package_info(*pkg)? else {
-            continue;
-        };
-        let Some(pkg_data) = pkg_obj.data.try_as_package() else {
-            continue;
-        };
-        for module in package_meta.modules.iter() {
-            let Ok(module_data) = pkg_data.deserialize_module_by_str(
-                &module.module_id.module_name,
-                &BinaryConfig::new_unpublishable(),
-            ) else {
-                continue;
-            };
+    for (pkg, modules) in packages {
+        for module_data in modules {
             let struct_pool = module_data.struct_defs();
             let enum_pool = module_data.enum_defs();
             let mut struct_is_visited = vec![false; struct_pool.len()];
@@ -94,28 +71,32 @@ where
                 .filter_map(|(id, visited)| if !visited { Some(id) } else { None })
                 .collect::<Vec<_>>();
             if !unused_struct.is_empty() {
-                reports.push(OracleFinding {
-                    oracle: "StaticUnusedStruct".to_string(),
-                    severity: Severity::Informational,
-                    extra: json!({
-                        "package": pkg.to_string(),
-                        "module": module.module_id.module_name.to_string(),
+                reports.push(
+                    OracleFinding::new(
+                        "StaticUnusedStruct",
+                        "unused_struct",
+                        Severity::Informational,
+                        "Structs are defined but never used",
+                    )
+                    .with_location(FindingLocation::module(*pkg, module_data.name().as_str()))
+                    .with_extra(json!({
                         "struct_indices": unused_struct,
-                        "message": "Structs are defined but never used"
-                    }),
-                });
+                    })),
+                );
             }
             if !unused_enum.is_empty() {
-                reports.push(OracleFinding {
-                    oracle: "StaticUnusedEnum".to_string(),
-                    severity: Severity::Informational,
-                    extra: json!({
-                        "package": pkg.to_string(),
-                        "module": module.module_id.module_name.to_string(),
+                reports.push(
+                    OracleFinding::new(
+                        "StaticUnusedEnum",
+                        "unused_enum",
+                        Severity::Informational,
+                        "Enums are defined but never used",
+                    )
+                    .with_location(FindingLocation::module(*pkg, module_data.name().as_str()))
+                    .with_extra(json!({
                         "enum_indices": unused_enum,
-                        "message": "Enums are defined but never used"
-                    }),
-                });
+                    })),
+                );
             }
         }
     }
@@ -0,0 +1,1027 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    CompiledModule,
+    file_format::{
+        Bytecode, CodeUnit, FieldHandleIndex, FunctionDefinition, SignatureToken, Visibility,
+    },
+};
+use movy_types::{
+    abi::{MoveFunctionAbi, MoveFunctionVisibility, MoveModuleAbi, MoveStructAbi},
+    error::MovyError,
+    input::MoveAddress,
+    oracle::{FindingLocation, OracleFinding, Severity},
+};
+use serde_json::json;
+use sui_types::move_package::MovePackage;
+
+const VERSION_FIELD: &str = "version";
+// Set in abort codes made by the compiler for `#[error]` constants and bare `assert!`s
+const CLEVER_ERROR_TAG: u64 = 1 << 63;
+// Identifier index of a clever error without a named constant
+const CLEVER_ERROR_NO_IDENTIFIER: u64 = 0xffff;
+
+/// `(module, function)` of a function definition in the package.
+type FunctionKey = (String, String);
+
+/// Abort sites of every function of a package by reason.
+type AbortReasons = BTreeMap<FunctionKey, BTreeMap<String, usize>>;
+
+struct UpgradeContext {
+    package: MoveAddress,
+    findings: Vec<OracleFinding>,
+}
+
+impl UpgradeContext {
+    fn report(
+        &mut self,
+        rule: &str,
+        severity: Severity,
+        module: &str,
+        function: Option<&str>,
+        message: String,
+        extra: serde_json::Value,
+    ) {
+        let mut location = FindingLocation::module(self.package, module);
+        if let Some(function) = function {
+            location = location.with_function(function);
+        }
+        self.findings.push(
+            OracleFinding::new("UpgradeCheck", rule, severity, message)
+                .with_location(location)
+                .with_extra(extra),
+        );
+    }
+}
+
+fn is_exposed(def: &FunctionDefinition) -> bool {
+    def.visibility == Visibility::Public || def.is_entry
+}
+
+fn function_def<'a>(module: &'a CompiledModule, name: &str) -> Option<&'a FunctionDefinition> {
+    module.function_defs().iter().find(|def| {
+        module
+            .identifier_at(module.function_handle_at(def.function).name)
+            .as_str()
+            == name
+    })
+}
+
+fn function_key(module: &CompiledModule, def: &FunctionDefinition) -> FunctionKey {
+    (
+        module.name().to_string(),
+        module
+            .identifier_at(module.function_handle_at(def.function).name)
+            .to_string(),
+    )
+}
+
+/// A function of the same package called by `instr`.
+fn package_callee(module: &CompiledModule, instr: &Bytecode) -> Option<FunctionKey> {
+    let callee = match instr {
+        Bytecode::Call(idx) => *idx,
+        Bytecode::CallGeneric(idx) => module.function_instantiation_at(*idx).handle,
+        _ => return None,
+    };
+    let handle = module.function_handle_at(callee);
+    let owner = module.module_handle_at(handle.module);
+    (module.address_identifier_at(owner.address) == module.address()).then(|| {
+        (
+            module.identifier_at(owner.name).to_string(),
+            module.identifier_at(handle.name).to_string(),
+        )
+    })
+}
+
+/// Why the `Abort` at `offset` aborts, i.e. its code. Clever errors are named after their
+/// constant, their line number moving with unrelated edits.
+fn abort_reason(module: &CompiledModule, code: &[Bytecode], offset: usize) -> String {
+    let value = match offset.checked_sub(1).and_then(|o| code.get(o)) {
+        Some(Bytecode::LdU64(value)) => Some(*value),
+        Some(Bytecode::LdConst(idx)) => {
+            let constant = module.constant_at(*idx);
+            (constant.type_ == SignatureToken::U64)
+                .then(|| <[u8; 8]>::try_from(constant.data.as_slice()).ok())
+                .flatten()
+                .map(u64::from_le_bytes)
+        }
+        _ => None,
+    };
+    let Some(value) = value else {
+        return "computed".to_string();
+    };
+    if value & CLEVER_ERROR_TAG == 0 {
+        return format!("code {}", value);
+    }
+    let identifier = (value >> 16) & 0xffff;
+    if identifier == CLEVER_ERROR_NO_IDENTIFIER {
+        return "assert".to_string();
+    }
+    match module.identifiers().get(identifier as usize) {
+        Some(name) => format!("error {}", name),
+        None => "assert".to_string(),
+    }
+}
+
+/// Abort sites of every function by reason, counting the ones of the package functions it
+/// calls such that moving an assertion into a helper does not lose it.
+fn abort_reasons(modules: &[CompiledModule]) -> AbortReasons {
+    let mut own: BTreeMap<FunctionKey, (BTreeMap<String, usize>, BTreeSet<FunctionKey>)> =
+        BTreeMap::new();
+    for module in modules {
+        for def in module.function_defs() {
+            let Some(CodeUnit { code, .. }) = &def.code else {
+                continue;
+            };
+            let (reasons, callees) = own.entry(function_key(module, def)).or_default();
+            for (offset, instr) in code.iter().enumerate() {
+                if matches!(instr, Bytecode::Abort) {
+                    *reasons
+                        .entry(abort_reason(module, code, offset))
+                        .or_default() += 1;
+                }
+                callees.extend(package_callee(module, instr));
+            }
+        }
+    }
+
+    fn total(
+        key: &FunctionKey,
+        own: &BTreeMap<FunctionKey, (BTreeMap<String, usize>, BTreeSet<FunctionKey>)>,
+        stack: &mut BTreeSet<FunctionKey>,
+    ) -> BTreeMap<String, usize> {
+        let Some((reasons, callees)) = own.get(key) else {
+            return BTreeMap::new();
+        };
+        // Recursion adds nothing the outer call does not count already
+        if !stack.insert(key.clone()) {
+            return BTreeMap::new();
+        }
+        let mut out = reasons.clone();
+        for callee in callees {
+            for (reason, count) in total(callee, own, stack) {
+                *out.entry(reason).or_default() += count;
+            }
+        }
+        stack.remove(key);
+        out
+    }
+
+    own.keys()
+        .map(|key| (key.clone(), total(key, &own, &mut BTreeSet::new())))
+        .collect()
+}
+
+/// Rewrite the new package self address to the old one so that tokens compare equal.
+fn normalize_function(
+    mut function: MoveFunctionAbi,
+    from: MoveAddress,
+    to: MoveAddress,
+) -> MoveFunctionAbi {
+    for ty in function
+        .parameters
+        .iter_mut()
+        .chain(function.return_paramters.iter_mut())
+    {
+        ty.published_at(from, to);
+    }
+    function
+}
+
+fn normalize_struct(mut st: MoveStructAbi, from: MoveAddress, to: MoveAddress) -> MoveStructAbi {
+    if st.handle.module_id.module_address == from {
+        st.handle.module_id.module_address = to;
+    }
+    for field in st.fields.iter_mut() {
+        field.ty.published_at(from, to);
+    }
+    st
+}
+
+fn compare_functions(
+    ctx: &mut UpgradeContext,
+    module: &str,
+    old: &MoveModuleAbi,
+    new: &MoveModuleAbi,
+    old_compiled: &CompiledModule,
+    new_compiled: &CompiledModule,
+    (old_aborts, new_aborts): (&AbortReasons, &AbortReasons),
+) {
+    let new_functions = new
+        .functions
+        .iter()
+        .map(|f| (f.name.as_str(), f))
+        .collect::<BTreeMap<_, _>>();
+    for old_function in old.functions.iter() {
+        let public = old_function.visibility == MoveFunctionVisibility::Public;
+        let Some(new_function) = new_functions.get(old_function.name.as_str()) else {
+            if public {
+                ctx.report(
+                    "removed_function",
+                    Severity::Major,
+                    module,
+                    Some(&old_function.name),
+                    format!("Public function {} is removed", old_function.name),
+                    json!({ "old": old_function.to_string() }),
+                );
+            }
+            continue;
+        };
+        if public
+            && (old_function.parameters != new_function.parameters
+                || old_function.return_paramters != new_function.return_paramters
+                || old_function.type_parameters != new_function.type_parameters
+                || new_function.visibility != old_function.visibility)
+        {
+            ctx.report(
+                "signature_change",
+                Severity::Major,
+                module,
+                Some(&old_function.name),
+                format!("Signature of public function {} changed", old_function.name),
+                json!({
+                    "old": old_function.to_string(),
+                    "new": new_function.to_string(),
+                }),
+            );
+        }
+
+        let (Some(old_def), Some(new_def)) = (
+            function_def(old_compiled, &old_function.name),
+            function_def(new_compiled, &old_function.name),
+        ) else {
+            continue;
+        };
+        let key = (module.to_string(), old_function.name.clone());
+        let empty = BTreeMap::new();
+        let new_reasons = new_aborts.get(&key).unwrap_or(&empty);
+        let lost = old_aborts
+            .get(&key)
+            .unwrap_or(&empty)
+            .iter()
+            .filter_map(|(reason, old_count)| {
+                let new_count = new_reasons.get(reason).copied().unwrap_or_default();
+                (new_count < *old_count)
+                    .then(|| json!({ "reason": reason, "old": old_count, "new": new_count }))
+            })
+            .collect::<Vec<_>>();
+        if !lost.is_empty() {
+            ctx.report(
+                "lost_assertion",
+                Severity::Major,
+                module,
+                Some(&old_function.name),
+                format!(
+                    "Function {} lost abort sites in the upgrade, directly or in the helpers it calls",
+                    old_function.name
+                ),
+                json!({ "lost": lost }),
+            );
+        }
+        if !is_exposed(old_def) && is_exposed(new_def) {
+            ctx.report(
+                "new_public_function",
+                Severity::Informational,
+                module,
+                Some(&old_function.name),
+                format!("Function {} became callable from PTBs", old_function.name),
+                json!({ "new": new_function.to_string(), "entry": new_def.is_entry }),
+            );
+        }
+    }
+
+    let old_names = old
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<BTreeSet<_>>();
+    for new_function in new.functions.iter() {
+        if old_names.contains(new_function.name.as_str()) {
+            continue;
+        }
+        let Some(def) = function_def(new_compiled, &new_function.name) else {
+            continue;
+        };
+        if is_exposed(def) {
+            ctx.report(
+                "new_public_function",
+                Severity::Informational,
+                module,
+                Some(&new_function.name),
+                format!("New function {} is callable from PTBs", new_function.name),
+                json!({ "new": new_function.to_string(), "entry": def.is_entry }),
+            );
+        }
+    }
+}
+
+fn compare_structs(
+    ctx: &mut UpgradeContext,
+    module: &str,
+    old: &MoveModuleAbi,
+    new: &MoveModuleAbi,
+) {
+    let new_structs = new
+        .structs
+        .iter()
+        .map(|s| (s.struct_name.as_str(), s))
+        .collect::<BTreeMap<_, _>>();
+    for old_struct in old.structs.iter() {
+        match new_structs.get(old_struct.struct_name.as_str()) {
+            None => ctx.report(
+                "struct_layout_change",
+                Severity::Major,
+                module,
+                None,
+                format!("Struct {} is removed", old_struct.struct_name),
+                json!({ "struct": old_struct.struct_name }),
+            ),
+            Some(new_struct)
+                if new_struct.fields != old_struct.fields
+                    || new_struct.abilities != old_struct.abilities
+                    || new_struct.type_parameters != old_struct.type_parameters =>
+            {
+                let fields = |st: &MoveStructAbi| {
+                    st.fields
+                        .iter()
+                        .map(|f| format!("{}: {}", f.name, f.ty))
+                        .collect::<Vec<_>>()
+                };
+                ctx.report(
+                    "struct_layout_change",
+                    Severity::Major,
+                    module,
+                    None,
+                    format!(
+                        "Layout or abilities of struct {} changed",
+                        old_struct.struct_name
+                    ),
+                    json!({
+                        "struct": old_struct.struct_name,
+                        "old_fields": fields(old_struct),
+                        "new_fields": fields(new_struct),
+                    }),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Structs of the package with `key` and a `version` field, with the field index.
+fn versioned_structs(modules: &[CompiledModule]) -> BTreeMap<(String, String), usize> {
+    let mut out = BTreeMap::new();
+    for module in modules {
+        for def in module.struct_defs() {
+            let handle = module.datatype_handle_at(def.struct_handle);
+            if !handle.abilities.has_key() {
+                continue;
+            }
+            let Some(idx) = def
+                .fields()
+                .into_iter()
+                .flatten()
+                .position(|f| module.identifier_at(f.name).as_str() == VERSION_FIELD)
+            else {
+                continue;
+            };
+            out.insert(
+                (
+                    module.name().to_string(),
+                    module.identifier_at(handle.name).to_string(),
+                ),
+                idx,
+            );
+        }
+    }
+    out
+}
+
+/// Versioned struct behind a `&mut T` parameter.
+fn versioned_param(
+    module: &CompiledModule,
+    token: &SignatureToken,
+    versioned: &BTreeMap<(String, String), usize>,
+) -> Option<(String, String)> {
+    let SignatureToken::MutableReference(inner) = token else {
+        return None;
+    };
+    let handle_idx = match inner.as_ref() {
+        SignatureToken::Datatype(idx) => *idx,
+        SignatureToken::DatatypeInstantiation(inst) => inst.0,
+        _ => return None,
+    };
+    let handle = module.datatype_handle_at(handle_idx);
+    let owner = module.module_handle_at(handle.module);
+    if module.address_identifier_at(owner.address) != module.address() {
+        return None;
+    }
+    let key = (
+        module.identifier_at(owner.name).to_string(),
+        module.identifier_at(handle.name).to_string(),
+    );
+    versioned.contains_key(&key).then_some(key)
+}
+
+/// What a stack slot or local holds as far as the version check is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Other,
+    /// The version field, a reference to it or a value computed from it.
+    Version,
+    /// The outcome of comparing a version.
+    Check,
+}
+
+fn is_version_field(
+    module: &CompiledModule,
+    field_handle: FieldHandleIndex,
+    versioned: &BTreeMap<(String, String), usize>,
+) -> bool {
+    let field = module.field_handle_at(field_handle);
+    let owner = module.struct_def_at(field.owner);
+    let name = module
+        .identifier_at(module.datatype_handle_at(owner.struct_handle).name)
+        .to_string();
+    versioned.get(&(module.name().to_string(), name)) == Some(&(field.field as usize))
+}
+
+/// Whether the function branches on a comparison of a version field, i.e. asserts it, and
+/// whether it returns the version, following the stack of every instruction. `getters`
+/// and `checkers` are what is known about the package functions it calls.
+fn version_flow(
+    module: &CompiledModule,
+    code: &[Bytecode],
+    versioned: &BTreeMap<(String, String), usize>,
+    getters: &BTreeSet<FunctionKey>,
+    checkers: &BTreeSet<FunctionKey>,
+) -> (bool, bool) {
+    let (mut checks, mut returns_version) = (false, false);
+    let mut locals: BTreeMap<u8, Slot> = BTreeMap::new();
+    let mut stack: Vec<Slot> = vec![];
+    let pop = |stack: &mut Vec<Slot>| stack.pop().unwrap_or(Slot::Other);
+    for instr in code {
+        let callee_handle = match instr {
+            Bytecode::Call(idx) => Some(*idx),
+            Bytecode::CallGeneric(idx) => Some(module.function_instantiation_at(*idx).handle),
+            _ => None,
+        };
+        if let Some(handle) = callee_handle.map(|idx| module.function_handle_at(idx)) {
+            for _ in 0..module.signature_at(handle.parameters).len() {
+                pop(&mut stack);
+            }
+            let callee = package_callee(module, instr);
+            checks |= callee.as_ref().is_some_and(|c| checkers.contains(c));
+            let result = if callee.as_ref().is_some_and(|c| getters.contains(c)) {
+                Slot::Version
+            } else {
+                Slot::Other
+            };
+            for _ in 0..module.signature_at(handle.return_).len() {
+                stack.push(result);
+            }
+            continue;
+        }
+        match instr {
+            Bytecode::LdU8(_)
+            | Bytecode::LdU16(_)
+            | Bytecode::LdU32(_)
+            | Bytecode::LdU64(_)
+            | Bytecode::LdU128(_)
+            | Bytecode::LdU256(_)
+            | Bytecode::LdConst(_)
+            | Bytecode::LdTrue
+            | Bytecode::LdFalse => stack.push(Slot::Other),
+            Bytecode::CopyLoc(idx)
+            | Bytecode::MoveLoc(idx)
+            | Bytecode::ImmBorrowLoc(idx)
+            | Bytecode::MutBorrowLoc(idx) => {
+                stack.push(locals.get(idx).copied().unwrap_or(Slot::Other))
+            }
+            Bytecode::StLoc(idx) => {
+                let slot = pop(&mut stack);
+                locals.insert(*idx, slot);
+            }
+            Bytecode::Pop | Bytecode::Abort => {
+                pop(&mut stack);
+            }
+            Bytecode::BrTrue(_) | Bytecode::BrFalse(_) => {
+                checks |= pop(&mut stack) == Slot::Check;
+            }
+            Bytecode::WriteRef => {
+                pop(&mut stack);
+                pop(&mut stack);
+            }
+            Bytecode::ImmBorrowField(idx) | Bytecode::MutBorrowField(idx) => {
+                pop(&mut stack);
+                stack.push(if is_version_field(module, *idx, versioned) {
+                    Slot::Version
+                } else {
+                    Slot::Other
+                });
+            }
+            Bytecode::ImmBorrowFieldGeneric(idx) | Bytecode::MutBorrowFieldGeneric(idx) => {
+                pop(&mut stack);
+                let handle = module.field_instantiation_at(*idx).handle;
+                stack.push(if is_version_field(module, handle, versioned) {
+                    Slot::Version
+                } else {
+                    Slot::Other
+                });
+            }
+            Bytecode::ReadRef
+            | Bytecode::FreezeRef
+            | Bytecode::Not
+            | Bytecode::CastU8
+            | Bytecode::CastU16
+            | Bytecode::CastU32
+            | Bytecode::CastU64
+            | Bytecode::CastU128
+            | Bytecode::CastU256 => {
+                let slot = pop(&mut stack);
+                stack.push(slot);
+            }
+            Bytecode::Eq
+            | Bytecode::Neq
+            | Bytecode::Lt
+            | Bytecode::Gt
+            | Bytecode::Le
+            | Bytecode::Ge => {
+                let (a, b) = (pop(&mut stack), pop(&mut stack));
+                stack.push(if a == Slot::Version || b == Slot::Version {
+                    Slot::Check
+                } else {
+                    Slot::Other
+                });
+            }
+            Bytecode::Add
+            | Bytecode::Sub
+            | Bytecode::Mul
+            | Bytecode::Div
+            | Bytecode::Mod
+            | Bytecode::BitOr
+            | Bytecode::BitAnd
+            | Bytecode::Xor
+            | Bytecode::Shl
+            | Bytecode::Shr
+            | Bytecode::And
+            | Bytecode::Or => {
+                let (a, b) = (pop(&mut stack), pop(&mut stack));
+                stack.push(if a == Slot::Version || b == Slot::Version {
+                    Slot::Version
+                } else if a == Slot::Check || b == Slot::Check {
+                    Slot::Check
+                } else {
+                    Slot::Other
+                });
+            }
+            Bytecode::Ret => {
+                returns_version |= stack.contains(&Slot::Version);
+                stack.clear();
+            }
+            Bytecode::Branch(_) | Bytecode::Nop => {}
+            // Anything else does not move a version around, forget what is on the stack
+            // rather than getting its arity wrong
+            _ => stack.clear(),
+        }
+    }
+    (checks, returns_version)
+}
+
+/// Functions asserting the `version` field of a versioned object, directly or through
+/// package functions they call, including ones comparing what a getter returns.
+fn version_checkers(
+    modules: &[CompiledModule],
+    versioned: &BTreeMap<(String, String), usize>,
+) -> BTreeSet<FunctionKey> {
+    let mut checkers = BTreeSet::new();
+    let mut getters = BTreeSet::new();
+    loop {
+        let before = (checkers.len(), getters.len());
+        for module in modules {
+            for def in module.function_defs() {
+                let Some(CodeUnit { code, .. }) = &def.code else {
+                    continue;
+                };
+                let (checks, returns_version) =
+                    version_flow(module, code, versioned, &getters, &checkers);
+                if checks {
+                    checkers.insert(function_key(module, def));
+                }
+                if returns_version {
+                    getters.insert(function_key(module, def));
+                }
+            }
+        }
+        if (checkers.len(), getters.len()) == before {
+            break;
+        }
+    }
+    checkers
+}
+
+fn check_versioning(ctx: &mut UpgradeContext, new: &[CompiledModule]) {
+    let versioned = versioned_structs(new);
+    if versioned.is_empty() {
+        return;
+    }
+    let checkers = version_checkers(new, &versioned);
+    let mut missing = vec![];
+    for module in new {
+        for def in module.function_defs() {
+            if !is_exposed(def) {
+                continue;
+            }
+            let handle = module.function_handle_at(def.function);
+            let name = module.identifier_at(handle.name).to_string();
+            if checkers.contains(&(module.name().to_string(), name.clone())) {
+                continue;
+            }
+            let objects = module
+                .signature_at(handle.parameters)
+                .0
+                .iter()
+                .filter_map(|token| versioned_param(module, token, &versioned))
+                .map(|(m, s)| format!("{}::{}", m, s))
+                .collect::<BTreeSet<_>>();
+            if !objects.is_empty() {
+                missing.push((module.name().to_string(), name, objects));
+            }
+        }
+    }
+    for (module, function, objects) in missing {
+        ctx.report(
+            "missing_version_check",
+            Severity::Medium,
+            &module,
+            Some(&function),
+            format!(
+                "Entry point {} mutates versioned objects without checking their version",
+                function
+            ),
+            json!({ "objects": objects }),
+        );
+    }
+}
+
+/// Compare the on-chain `old` package with the locally built `new` modules, reporting
+/// changes the upgrade compatibility check would reject and logic changes that deserve a
+/// second look.
+pub fn check_upgrade(
+    old: &MovePackage,
+    new: &[CompiledModule],
+) -> Result<Vec<OracleFinding>, MovyError> {
+    let old_modules = old
+        .serialized_module_map()
+        .values()
+        .map(|bytes| CompiledModule::deserialize_with_defaults(bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(compare_packages(old.id().into(), &old_modules, new))
+}
+
+fn compare_packages(
+    package: MoveAddress,
+    old_modules: &[CompiledModule],
+    new: &[CompiledModule],
+) -> Vec<OracleFinding> {
+    let Some(old_self) = old_modules.first().map(|m| MoveAddress::from(*m.address())) else {
+        return vec![];
+    };
+    let new_self = new
+        .first()
+        .map(|m| MoveAddress::from(*m.address()))
+        .unwrap_or(old_self);
+
+    let mut ctx = UpgradeContext {
+        package,
+        findings: vec![],
+    };
+    let aborts = (&abort_reasons(old_modules), &abort_reasons(new));
+
+    let new_by_name = new
+        .iter()
+        .map(|m| (m.name().to_string(), m))
+        .collect::<BTreeMap<_, _>>();
+    for old_compiled in old_modules.iter() {
+        let module = old_compiled.name().to_string();
+        let Some(new_compiled) = new_by_name.get(&module) else {
+            ctx.report(
+                "removed_module",
+                Severity::Major,
+                &module,
+                None,
+                format!("Module {} is removed", module),
+                serde_json::Value::Null,
+            );
+            continue;
+        };
+        let old_abi = MoveModuleAbi::from_sui_module(old_compiled);
+        let mut new_abi = MoveModuleAbi::from_sui_module(new_compiled);
+        new_abi.functions = new_abi
+            .functions
+            .into_iter()
+            .map(|f| normalize_function(f, new_self, old_self))
+            .collect();
+        new_abi.structs = new_abi
+            .structs
+            .into_iter()
+            .map(|s| normalize_struct(s, new_self, old_self))
+            .collect();
+        compare_structs(&mut ctx, &module, &old_abi, &new_abi);
+        compare_functions(
+            &mut ctx,
+            &module,
+            &old_abi,
+            &new_abi,
+            old_compiled,
+            new_compiled,
+            aborts,
+        );
+    }
+
+    let old_names = old_modules
+        .iter()
+        .map(|m| m.name().to_string())
+        .collect::<BTreeSet<_>>();
+    for new_compiled in new {
+        if old_names.contains(new_compiled.name().as_str()) {
+            continue;
+        }
+        let abi = MoveModuleAbi::from_sui_module(new_compiled);
+        for function in abi.functions.iter() {
+            if function_def(new_compiled, &function.name).is_some_and(is_exposed) {
+                ctx.report(
+                    "new_public_function",
+                    Severity::Informational,
+                    new_compiled.name().as_str(),
+                    Some(&function.name),
+                    format!("New function {} is callable from PTBs", function.name),
+                    json!({ "new": function.to_string() }),
+                );
+            }
+        }
+    }
+
+    check_versioning(&mut ctx, new);
+    ctx.findings
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use move_core_types::account_address::AccountAddress;
+
+    use super::compare_packages;
+    use crate::sui::common::test_modules;
+
+    const V1: &str = r#"module test::pool;
+
+const VERSION: u64 = 1;
+
+#[error]
+const EWrongVersion: vector<u8> = b"Wrong version";
+#[error]
+const ETooLarge: vector<u8> = b"Amount too large";
+
+public struct Pool has key {
+    id: UID,
+    version: u64,
+    reserve: u64,
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(Pool { id: object::new(ctx), version: VERSION, reserve: 0 });
+}
+
+public fun deposit(pool: &mut Pool, amount: u64) {
+    assert!(pool.version == VERSION, EWrongVersion);
+    assert!(amount <= 1000, ETooLarge);
+    pool.reserve = pool.reserve + amount;
+}
+
+public fun withdraw(pool: &mut Pool, amount: u64) {
+    check_version(pool);
+    assert!(amount <= pool.reserve, ETooLarge);
+    pool.reserve = pool.reserve - amount;
+}
+
+public fun version(pool: &Pool): u64 {
+    pool.version
+}
+
+public fun migrate(pool: &mut Pool) {
+    assert!(pool.version < VERSION, EWrongVersion);
+    pool.version = VERSION;
+}
+
+fun check_version(pool: &Pool) {
+    assert!(version(pool) == VERSION, EWrongVersion);
+}
+"#;
+
+    /// Assertion of `deposit` moved into a helper, lines shifted and a getter added.
+    const V2_COMPATIBLE: &str = r#"module test::pool;
+
+const VERSION: u64 = 1;
+
+#[error]
+const EWrongVersion: vector<u8> = b"Wrong version";
+#[error]
+const ETooLarge: vector<u8> = b"Amount too large";
+
+/// The pool, shared at publication.
+public struct Pool has key {
+    id: UID,
+    version: u64,
+    reserve: u64,
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(Pool { id: object::new(ctx), version: VERSION, reserve: 0 });
+}
+
+public fun deposit(pool: &mut Pool, amount: u64) {
+    assert!(pool.version == VERSION, EWrongVersion);
+    check_amount(amount);
+    pool.reserve = pool.reserve + amount;
+}
+
+public fun withdraw(pool: &mut Pool, amount: u64) {
+    check_version(pool);
+    assert!(amount <= pool.reserve, ETooLarge);
+    pool.reserve = pool.reserve - amount;
+}
+
+public fun version(pool: &Pool): u64 {
+    pool.version
+}
+
+public fun reserve(pool: &Pool): u64 {
+    pool.reserve
+}
+
+public fun migrate(pool: &mut Pool) {
+    assert!(pool.version < VERSION, EWrongVersion);
+    pool.version = VERSION;
+}
+
+fun check_version(pool: &Pool) {
+    assert!(version(pool) == VERSION, EWrongVersion);
+}
+
+fun check_amount(amount: u64) {
+    assert!(amount <= 1000, ETooLarge);
+}
+"#;
+
+    /// A field added, a signature changed and a public function removed.
+    const V2_INCOMPATIBLE: &str = r#"module test::pool;
+
+const VERSION: u64 = 1;
+
+#[error]
+const EWrongVersion: vector<u8> = b"Wrong version";
+#[error]
+const ETooLarge: vector<u8> = b"Amount too large";
+
+public struct Pool has key {
+    id: UID,
+    version: u64,
+    reserve: u64,
+    fee: u64,
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(Pool { id: object::new(ctx), version: VERSION, reserve: 0, fee: 0 });
+}
+
+public fun deposit(pool: &mut Pool, amount: u64, fee: u64) {
+    assert!(pool.version == VERSION, EWrongVersion);
+    assert!(amount <= 1000, ETooLarge);
+    pool.reserve = pool.reserve + amount;
+    pool.fee = pool.fee + fee;
+}
+
+public fun withdraw(pool: &mut Pool, amount: u64) {
+    check_version(pool);
+    assert!(amount <= pool.reserve, ETooLarge);
+    pool.reserve = pool.reserve - amount;
+}
+
+public fun migrate(pool: &mut Pool) {
+    assert!(pool.version < VERSION, EWrongVersion);
+    pool.version = VERSION;
+}
+
+fun check_version(pool: &Pool) {
+    assert!(pool.version == VERSION, EWrongVersion);
+}
+"#;
+
+    /// `deposit` no longer checks the version and `touch` reads it without checking it.
+    const V2_DROPPED_VERSION_CHECK: &str = r#"module test::pool;
+
+const VERSION: u64 = 1;
+
+#[error]
+const EWrongVersion: vector<u8> = b"Wrong version";
+#[error]
+const ETooLarge: vector<u8> = b"Amount too large";
+
+public struct Pool has key {
+    id: UID,
+    version: u64,
+    reserve: u64,
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(Pool { id: object::new(ctx), version: VERSION, reserve: 0 });
+}
+
+public fun deposit(pool: &mut Pool, amount: u64) {
+    assert!(amount <= 1000, ETooLarge);
+    pool.reserve = pool.reserve + amount;
+}
+
+public fun withdraw(pool: &mut Pool, amount: u64) {
+    check_version(pool);
+    assert!(amount <= pool.reserve, ETooLarge);
+    pool.reserve = pool.reserve - amount;
+}
+
+public fun version(pool: &Pool): u64 {
+    pool.version
+}
+
+public fun touch(pool: &mut Pool) {
+    let version = pool.version;
+    pool.reserve = version;
+}
+
+public fun migrate(pool: &mut Pool) {
+    assert!(pool.version < VERSION, EWrongVersion);
+    pool.version = VERSION;
+}
+
+fun check_version(pool: &Pool) {
+    assert!(version(pool) == VERSION, EWrongVersion);
+}
+"#;
+
+    fn findings(old: &str, new: &str) -> BTreeSet<(String, String)> {
+        let compile = |source| {
+            test_modules(&[("pool", source)])
+                .into_iter()
+                .map(|m| m.compiled)
+                .collect::<Vec<_>>()
+        };
+        compare_packages(AccountAddress::ZERO.into(), &compile(old), &compile(new))
+            .into_iter()
+            .map(|f| {
+                let function = f.location.and_then(|l| l.function).unwrap_or_default();
+                (f.rule, function)
+            })
+            .collect()
+    }
+
+    fn set(findings: &[(&str, &str)]) -> BTreeSet<(String, String)> {
+        findings
+            .iter()
+            .map(|(rule, function)| (rule.to_string(), function.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compatible_upgrade() {
+        assert_eq!(
+            findings(V1, V2_COMPATIBLE),
+            set(&[("new_public_function", "reserve")])
+        );
+    }
+
+    #[test]
+    fn test_incompatible_upgrade() {
+        assert_eq!(
+            findings(V1, V2_INCOMPATIBLE),
+            set(&[
+                ("struct_layout_change", ""),
+                ("signature_change", "deposit"),
+                ("removed_function", "version"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_dropped_version_check() {
+        assert_eq!(
+            findings(V1, V2_DROPPED_VERSION_CHECK),
+            set(&[
+                ("lost_assertion", "deposit"),
+                ("missing_version_check", "deposit"),
+                ("missing_version_check", "touch"),
+                ("new_public_function", "touch"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_model::model::FunId;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode as SLBytecode, Operation};
+use serde_json::json;
+
+use super::{
+    common::{ModuleAnalysis, SinkCounter},
+    generate_bytecode::FunctionInfo,
+    taint::{CallSite, SinkSpec, SinkTarget, TaintAnalysis, TaintConfig, TaintSource},
+};
+use movy_types::oracle::{OracleFinding, Severity};
+
+const RECOMMENDATION: &str =
+    "Use 0x2::random::Random with a RandomGenerator in a private entry function instead";
+
+const WEAK_RANDOMNESS: TaintConfig = TaintConfig {
+    sources: &[
+        CallSite::new("clock", "timestamp_ms"),
+        CallSite::new("tx_context", "epoch"),
+        CallSite::new("tx_context", "epoch_timestamp_ms"),
+        CallSite::new("tx_context", "digest"),
+        CallSite::new("tx_context", "fresh_object_address"),
+        CallSite::new("object", "id_address"),
+        CallSite::new("object", "uid_to_address"),
+        CallSite::new("object", "id_to_address"),
+    ],
+    sinks: &[
+        SinkSpec {
+            kind: "modulo",
+            target: SinkTarget::Modulo,
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "branch",
+            target: SinkTarget::Branch,
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "index",
+            target: SinkTarget::Call(CallSite::new("vector", "borrow"), 1),
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "index",
+            target: SinkTarget::Call(CallSite::new("vector", "borrow_mut"), 1),
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "index",
+            target: SinkTarget::Call(CallSite::new("vector", "remove"), 1),
+            ignore: &[],
+        },
+        SinkSpec {
+            kind: "index",
+            target: SinkTarget::Call(CallSite::new("vector", "swap_remove"), 1),
+            ignore: &[],
+        },
+    ],
+    sanitize_on_assert: false,
+    implicit_flows: false,
+};
+
+/// Calls moving value out, i.e. what a branch on weak randomness must not decide.
+const PAYOUTS: &[(&str, &str)] = &[
+    ("transfer", "public_transfer"),
+    ("transfer", "transfer"),
+    ("coin", "split"),
+    ("coin", "take"),
+    ("coin", "from_balance"),
+    ("coin", "mint"),
+    ("balance", "split"),
+    ("balance", "withdraw_all"),
+    ("balance", "increase_supply"),
+];
+
+/// Functions of the module doing a payout, directly or through the module functions they
+/// call.
+fn payout_functions(module: &ModuleAnalysis) -> BTreeSet<FunId> {
+    let mut payouts = BTreeSet::new();
+    loop {
+        let before = payouts.len();
+        for function in module.functions() {
+            if payouts.contains(&function.fun_id) {
+                continue;
+            }
+            if function
+                .code
+                .iter()
+                .any(|instr| is_payout_call(module, function, instr, &payouts))
+            {
+                payouts.insert(function.fun_id);
+            }
+        }
+        if payouts.len() == before {
+            break;
+        }
+    }
+    payouts
+}
+
+fn is_payout_call(
+    module: &ModuleAnalysis,
+    function: &FunctionInfo,
+    instr: &SLBytecode,
+    payouts: &BTreeSet<FunId>,
+) -> bool {
+    let SLBytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) = instr else {
+        return false;
+    };
+    if *mid == function.module_id && payouts.contains(fid) {
+        return true;
+    }
+    let (callee_module, callee) = module.callee_name(mid, fid);
+    PAYOUTS
+        .iter()
+        .any(|(m, f)| *m == callee_module && *f == callee)
+}
+
+/// Whether one side of the branch at `offset` reaches a payout before leaving its block.
+fn selects_payout(
+    module: &ModuleAnalysis,
+    function: &FunctionInfo,
+    offset: usize,
+    payouts: &BTreeSet<FunId>,
+) -> bool {
+    let Some(SLBytecode::Branch(_, then_label, else_label, _)) = function.code.get(offset) else {
+        return false;
+    };
+    let label_offsets = SLBytecode::label_offsets(&function.code);
+    [then_label, else_label].into_iter().any(|label| {
+        let Some(start) = label_offsets.get(label) else {
+            return false;
+        };
+        for instr in function.code.iter().skip(*start as usize) {
+            if is_payout_call(module, function, instr, payouts) {
+                return true;
+            }
+            if matches!(
+                instr,
+                SLBytecode::Jump(..)
+                    | SLBytecode::Branch(..)
+                    | SLBytecode::Ret(..)
+                    | SLBytecode::Abort(..)
+            ) {
+                return false;
+            }
+        }
+        false
+    })
+}
+
+pub fn analyze(modules: &[ModuleAnalysis]) -> Vec<OracleFinding> {
+    let mut reports = Vec::new();
+
+    // Sinks are reported where they are, a helper of another module included, so the hits
+    // of every module are gathered before looking at any
+    let analysis = TaintAnalysis::run(modules, &WEAK_RANDOMNESS);
+    let mut hits: BTreeMap<(&str, &str, &str, usize), BTreeSet<CallSite>> = BTreeMap::new();
+    for module in modules {
+        for function in module.functions() {
+            let Some(summary) = analysis.summary(module, function) else {
+                continue;
+            };
+            for hit in summary.sinks.iter() {
+                let sources = hit.sources.iter().filter_map(|s| match s {
+                    TaintSource::Call(site) => Some(*site),
+                    TaintSource::Param(_) => None,
+                });
+                hits.entry((
+                    hit.module.as_str(),
+                    hit.kind,
+                    hit.function.as_str(),
+                    hit.offset,
+                ))
+                .or_default()
+                .extend(sources);
+            }
+        }
+    }
+
+    for module in modules {
+        let module_name = module.compiled.self_id().name().to_string();
+        let payouts = payout_functions(module);
+        let hits = hits
+            .iter()
+            .filter(|((m, ..), _)| *m == module_name)
+            .map(|((_, kind, name, offset), sources)| ((*kind, *name, *offset), sources));
+        let mut sinks = SinkCounter::default();
+        for ((kind, name, offset), sources) in hits {
+            let sink = sinks.next(kind, name);
+            if sources.is_empty() {
+                continue;
+            }
+            let Some(function) = module.functions().iter().find(|f| f.name == name) else {
+                continue;
+            };
+            if kind == "branch" && !selects_payout(module, function, offset, &payouts) {
+                continue;
+            }
+            let sources = sources.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            let usage = match kind {
+                "modulo" => "a modulo",
+                "branch" => "a branch selecting a payout",
+                _ => "a vector index",
+            };
+            reports.push(
+                OracleFinding::new(
+                    "StaticWeakRandomness",
+                    &format!("weak_randomness_{}", kind),
+                    Severity::Major,
+                    format!(
+                        "{} used as randomness in {}; use sui::random instead",
+                        sources.join(", "),
+                        usage
+                    ),
+                )
+                .with_location(module.function_location(function).with_sink(sink))
+                .with_extra(json!({
+                    "sources": sources,
+                    "sink_offset": offset,
+                    "recommendation": RECOMMENDATION,
+                })),
+            );
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::analyze;
+    use crate::sui::common::test_modules;
+
+    const LOTTERY: &str = r#"module test::lottery;
+
+use sui::balance::Balance;
+use sui::clock::Clock;
+use sui::coin;
+use sui::sui::SUI;
+use test::dice;
+
+public struct Lottery has key {
+    id: UID,
+    funds: Balance<SUI>,
+    players: vector<address>,
+}
+
+public fun draw(lottery: &mut Lottery, clock: &Clock, ctx: &mut TxContext) {
+    if (clock.timestamp_ms() % 2 == 0) {
+        transfer::public_transfer(coin::take(&mut lottery.funds, 10, ctx), ctx.sender());
+    }
+}
+
+public fun winner(lottery: &Lottery, ctx: &TxContext): address {
+    lottery.players[ctx.epoch() % lottery.players.length()]
+}
+
+public fun roll(clock: &Clock): u64 {
+    dice::roll(clock)
+}
+
+public fun check_deadline(clock: &Clock, deadline: u64) {
+    assert!(clock.timestamp_ms() <= deadline);
+}
+"#;
+
+    const DICE: &str = r#"module test::dice;
+
+use sui::clock::Clock;
+
+public(package) fun roll(clock: &Clock): u64 {
+    clock.timestamp_ms() % 6
+}
+"#;
+
+    fn flagged() -> BTreeSet<(String, String)> {
+        analyze(&test_modules(&[("lottery", LOTTERY), ("dice", DICE)]))
+            .into_iter()
+            .filter_map(|f| {
+                let location = f.location?;
+                Some((
+                    f.rule,
+                    format!("{}::{}", location.module?, location.function?),
+                ))
+            })
+            .collect()
+    }
+
+    fn has(flagged: &BTreeSet<(String, String)>, rule: &str, function: &str) -> bool {
+        flagged.contains(&(rule.to_string(), function.to_string()))
+    }
+
+    #[test]
+    fn test_timestamp_deciding_payout() {
+        let flagged = flagged();
+        assert!(has(&flagged, "weak_randomness_modulo", "lottery::draw"));
+        assert!(has(&flagged, "weak_randomness_branch", "lottery::draw"));
+        // Comparing the time to a deadline does not pick anyone
+        assert!(!flagged.iter().any(|(_, f)| f == "lottery::check_deadline"));
+    }
+
+    #[test]
+    fn test_epoch_as_index() {
+        let flagged = flagged();
+        assert!(has(&flagged, "weak_randomness_modulo", "lottery::winner"));
+        assert!(has(&flagged, "weak_randomness_index", "lottery::winner"));
+    }
+
+    #[test]
+    fn test_sink_in_another_module() {
+        let flagged = flagged();
+        assert!(has(&flagged, "weak_randomness_modulo", "dice::roll"));
+        assert!(!has(&flagged, "weak_randomness_modulo", "lottery::roll"));
+    }
+}
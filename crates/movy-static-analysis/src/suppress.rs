@@ -0,0 +1,337 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use movy_types::{
+    error::MovyError,
+    input::MoveAddress,
+    oracle::{FindingLocation, OracleFinding},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::sarif::rule_id;
+
+const BASELINE_VERSION: u32 = 3;
+const ALLOW_MARKER: &str = "movy::allow(";
+
+/// Findings accepted once, recognized by fingerprint on later runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub rule: String,
+    /// Only kept to make the baseline reviewable, matching is by fingerprint.
+    #[serde(default)]
+    pub location: Option<FindingLocation>,
+}
+
+impl Baseline {
+    pub fn from_findings(findings: &[OracleFinding]) -> Self {
+        let mut seen = BTreeSet::new();
+        let findings = findings
+            .iter()
+            .filter(|f| seen.insert(f.fingerprint.clone()))
+            .map(|f| BaselineEntry {
+                fingerprint: f.fingerprint.clone(),
+                rule: f.rule.clone(),
+                location: f
+                    .location
+                    .clone()
+                    .map(|l| FindingLocation { source: None, ..l }),
+            })
+            .collect();
+        Self {
+            version: BASELINE_VERSION,
+            findings,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, MovyError> {
+        let fp = std::fs::File::open(path)?;
+        let baseline: Self = serde_json::from_reader(fp)?;
+        if baseline.version < BASELINE_VERSION {
+            log::warn!(
+                "Baseline {} is of version {} and its fingerprints no longer match, run with --update-baseline to record it again",
+                path.display(),
+                baseline.version
+            );
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MovyError> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let fp = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(fp, self)?;
+        Ok(())
+    }
+
+    pub fn contains(&self, finding: &OracleFinding) -> bool {
+        self.findings
+            .iter()
+            .any(|e| e.fingerprint == finding.fingerprint)
+    }
+}
+
+/// `// movy::allow(rule, ...)` comments of one module. A comment right above a function
+/// (attributes and other comments in between are fine) silences its findings, anywhere
+/// else it silences the module wide findings such as unused constants. `all` matches
+/// every rule. Modules are keyed by their package as dependencies may have modules named
+/// as the local ones.
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    functions: BTreeMap<(MoveAddress, String), BTreeMap<String, BTreeSet<String>>>,
+    modules: BTreeMap<(MoveAddress, String), BTreeSet<String>>,
+}
+
+fn parse_allow(line: &str) -> Option<BTreeSet<String>> {
+    let (_, comment) = line.split_once("//")?;
+    let start = comment.find(ALLOW_MARKER)? + ALLOW_MARKER.len();
+    let end = start + comment[start..].find(')')?;
+    Some(
+        comment[start..end]
+            .split(',')
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect(),
+    )
+}
+
+/// Name of the function declared on `line`, if any.
+fn declared_function(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    words.find(|w| *w == "fun")?;
+    let name = words.next()?;
+    let name = name.split(['(', '<']).next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+impl Suppressions {
+    /// Collect the annotations of `module` of `package` from its source text.
+    pub fn add_module(&mut self, package: MoveAddress, module: &str, source: &str) {
+        let module = (package, module.to_string());
+        let mut pending: Option<BTreeSet<String>> = None;
+        for line in source.lines() {
+            let trimmed = line.trim();
+            if let Some(rules) = parse_allow(trimmed) {
+                pending.get_or_insert_default().extend(rules);
+                // A trailing comment applies to its own line
+                if !trimmed.starts_with("//") {
+                    self.attach(&module, trimmed, pending.take().unwrap_or_default());
+                }
+                continue;
+            }
+            if pending.is_none()
+                || trimmed.is_empty()
+                || trimmed.starts_with("//")
+                || trimmed.starts_with("#[")
+            {
+                continue;
+            }
+            self.attach(&module, trimmed, pending.take().unwrap_or_default());
+        }
+        if let Some(rules) = pending {
+            self.modules.entry(module).or_default().extend(rules);
+        }
+    }
+
+    fn attach(&mut self, module: &(MoveAddress, String), line: &str, rules: BTreeSet<String>) {
+        let target = match declared_function(line) {
+            Some(function) => self
+                .functions
+                .entry(module.clone())
+                .or_default()
+                .entry(function)
+                .or_default(),
+            None => self.modules.entry(module.clone()).or_default(),
+        };
+        target.extend(rules);
+    }
+
+    pub fn load_file(
+        &mut self,
+        package: MoveAddress,
+        module: &str,
+        path: &Path,
+    ) -> Result<(), MovyError> {
+        let source = std::fs::read_to_string(path)?;
+        self.add_module(package, module, &source);
+        Ok(())
+    }
+
+    pub fn is_suppressed(&self, finding: &OracleFinding) -> bool {
+        let Some(location) = &finding.location else {
+            return false;
+        };
+        let (Some(package), Some(module)) = (location.package, &location.module) else {
+            return false;
+        };
+        let module = (package, module.clone());
+        let rules = match &location.function {
+            Some(function) => self.functions.get(&module).and_then(|f| f.get(function)),
+            None => self.modules.get(&module),
+        };
+        let Some(rules) = rules else {
+            return false;
+        };
+        let legacy = rule_id(&finding.oracle);
+        rules
+            .iter()
+            .any(|r| r == "all" || *r == finding.rule || *r == legacy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeSet, str::FromStr};
+
+    use movy_types::{
+        input::MoveAddress,
+        oracle::{FindingLocation, OracleFinding, Severity},
+    };
+
+    use crate::suppress::{Baseline, Suppressions, declared_function, parse_allow};
+
+    const POOL: &str = r#"module pool::pool {
+    // movy::allow(precision_loss)
+    #[allow(unused)]
+    /// Quotes the swap
+    public fun quote(amount: u64): u64 {
+        amount / 3 * 3
+    }
+
+    public fun swap<T>(amount: u64): u64 { // movy::allow(all)
+        amount
+    }
+
+    public fun burn(amount: u64): u64 {
+        amount
+    }
+
+    const EUnused: u64 = 0; // movy::allow(unused_constant)
+}
+"#;
+
+    fn finding(oracle: &str, rule: &str, location: FindingLocation) -> OracleFinding {
+        OracleFinding::new(oracle, rule, Severity::Minor, "").with_location(location)
+    }
+
+    #[test]
+    fn test_parse_allow() {
+        assert_eq!(
+            parse_allow("// movy::allow(precision_loss, taint)").unwrap(),
+            BTreeSet::from(["precision_loss", "taint"].map(String::from))
+        );
+        assert!(
+            parse_allow("let a = 1; // movy::allow()")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_allow("// movy::allow(taint").is_none());
+        assert!(parse_allow("movy::allow(taint)").is_none());
+        assert_eq!(
+            declared_function("public fun swap<T>(a: u64)").unwrap(),
+            "swap"
+        );
+        assert_eq!(
+            declared_function("entry fun burn (a: u64)").unwrap(),
+            "burn"
+        );
+        assert!(declared_function("const EUnused: u64 = 0;").is_none());
+    }
+
+    #[test]
+    fn test_suppressions() {
+        let package = MoveAddress::from_str("0x42").unwrap();
+        let mut suppressions = Suppressions::default();
+        suppressions.add_module(package, "pool", POOL);
+        let pool = FindingLocation::module(package, "pool");
+
+        // Attributes and doc comments between the annotation and the function are fine
+        let quote = pool.clone().with_function("quote");
+        assert!(suppressions.is_suppressed(&finding(
+            "StaticPrecisionLoss",
+            "precision_loss",
+            quote.clone()
+        )));
+        assert!(!suppressions.is_suppressed(&finding("StaticTaint", "taint", quote)));
+
+        // A trailing comment applies to its own line
+        let swap = pool.clone().with_function("swap");
+        assert!(suppressions.is_suppressed(&finding("StaticTaint", "taint", swap)));
+        let burn = pool.clone().with_function("burn");
+        assert!(!suppressions.is_suppressed(&finding("StaticTaint", "taint", burn)));
+
+        // Module wide findings such as unused constants
+        assert!(suppressions.is_suppressed(&finding(
+            "StaticUnusedConstant",
+            "unused_constant",
+            pool.clone().with_sink("const:U64(0)")
+        )));
+
+        // A module of the same name in another package is not silenced
+        let other = FindingLocation::module(MoveAddress::from_str("0x43").unwrap(), "pool")
+            .with_function("swap");
+        assert!(!suppressions.is_suppressed(&finding("StaticTaint", "taint", other)));
+        assert!(!suppressions.is_suppressed(&OracleFinding::new(
+            "StaticTaint",
+            "taint",
+            Severity::Minor,
+            ""
+        )));
+    }
+
+    #[test]
+    fn test_baseline() {
+        let location = FindingLocation::module(MoveAddress::from_str("0x42").unwrap(), "pool")
+            .with_function("swap");
+        let local = location.clone().with_package_name("amm");
+        let accepted = finding("StaticTaint", "taint", local.clone().with_sink("a#0"));
+        let findings = [accepted.clone(), accepted.clone()];
+        let baseline = Baseline::from_findings(&findings);
+        assert_eq!(baseline.findings.len(), 1);
+        assert!(baseline.contains(&accepted));
+
+        // The same local package deployed elsewhere is still known
+        let redeployed = FindingLocation {
+            package: Some(MoveAddress::from_str("0x43").unwrap()),
+            ..local.clone()
+        };
+        assert!(baseline.contains(&finding(
+            "StaticTaint",
+            "taint",
+            redeployed.with_sink("a#0")
+        )));
+        assert!(!baseline.contains(&finding("StaticTaint", "taint", local.with_sink("a#1"))));
+
+        // Another package with the same module is not
+        let other = FindingLocation {
+            package: Some(MoveAddress::from_str("0x43").unwrap()),
+            ..location.clone()
+        };
+        let onchain = finding("StaticTaint", "taint", location.with_sink("a#0"));
+        let baseline = Baseline::from_findings(&[onchain.clone()]);
+        assert!(baseline.contains(&onchain));
+        assert!(!baseline.contains(&finding("StaticTaint", "taint", other.with_sink("a#0"))));
+        assert!(!baseline.contains(&accepted));
+
+        let path = std::env::temp_dir()
+            .join(format!("movy-baseline-{}", std::process::id()))
+            .join("baseline.json");
+        baseline.save(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(loaded.version, baseline.version);
+        assert!(loaded.contains(&accepted));
+    }
+}
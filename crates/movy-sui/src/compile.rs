@@ -1,26 +1,42 @@
-use std::{collections::BTreeSet, io::Write, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use color_eyre::eyre::eyre;
 use itertools::Itertools;
 use log::{debug, trace};
-use move_binary_format::CompiledModule;
+use move_binary_format::{CompiledModule, file_format::FunctionDefinitionIndex};
 use move_compiler::editions::Flavor;
 use movy_types::{
     abi::{MOVY_INIT, MOVY_ORACLE, MovePackageAbi},
     error::MovyError,
     input::MoveAddress,
+    oracle::SourceSpan,
 };
 use serde::{Deserialize, Serialize};
 use sui_move_build::{BuildConfig, CompiledPackage};
 use sui_types::{base_types::ObjectID, digests::get_mainnet_chain_identifier};
+use tempfile::TempDir;
 
 pub fn build_package_resolved(
     folder: &Path,
     test_mode: bool,
+) -> Result<(CompiledPackage, Vec<std::path::PathBuf>), MovyError> {
+    build_package(folder, test_mode, false)
+}
+
+fn build_package(
+    folder: &Path,
+    test_mode: bool,
+    skip_fetch: bool,
 ) -> Result<(CompiledPackage, Vec<std::path::PathBuf>), MovyError> {
     let mut cfg = BuildConfig::new_for_testing();
     cfg.config.default_flavor = Some(Flavor::Sui);
     cfg.config.test_mode = test_mode;
+    // Git dependencies already checked out are used as is instead of being updated
+    cfg.config.skip_fetch_latest_git_deps = skip_fetch;
     cfg.config.silence_warnings = true;
     cfg.run_bytecode_verifier = false;
     cfg.print_diags_to_stderr = false;
@@ -180,9 +196,44 @@ impl SuiCompiledPackage {
         test_mode: bool,
     ) -> Result<SuiCompiledPackage, MovyError> {
         let (artifacts, _) = build_package_resolved(folder, test_mode)?;
+        Self::from_artifacts(folder, &artifacts).map(|(package, _)| package)
+    }
+
+    // Same as build_all_unpublished_from_folder, but also returns the compiled modules of
+    // all the other (published) dependencies and the sources of the package from the same
+    // build, e.g. for analyses without any chain state.
+    pub fn build_all_unpublished_with_dependencies_from_folder(
+        folder: &Path,
+        test_mode: bool,
+    ) -> Result<(SuiCompiledPackage, Vec<CompiledModule>, SuiPackageSources), MovyError> {
+        let (artifacts, _) = build_package_resolved(folder, test_mode)?;
+        let (package, dependencies) = Self::from_artifacts(folder, &artifacts)?;
+        let sources = SuiPackageSources::from_compiled(&artifacts)?;
+        Ok((package, dependencies, sources))
+    }
+
+    // Same as build_all_unpublished_with_dependencies_from_folder, without updating the git
+    // dependencies already checked out, so that no network round trip is needed once they are.
+    pub fn build_all_unpublished_offline_from_folder(
+        folder: &Path,
+    ) -> Result<(SuiCompiledPackage, Vec<CompiledModule>, SuiPackageSources), MovyError> {
+        let (artifacts, _) = build_package(folder, false, true)?;
+        let (package, dependencies) = Self::from_artifacts(folder, &artifacts)?;
+        let sources = SuiPackageSources::from_compiled(&artifacts)?;
+        Ok((package, dependencies, sources))
+    }
+
+    fn from_artifacts(
+        folder: &Path,
+        artifacts: &CompiledPackage,
+    ) -> Result<(SuiCompiledPackage, Vec<CompiledModule>), MovyError> {
         debug!("published: {:?}", artifacts.dependency_ids.published);
 
-        let root_address = artifacts.published_at.unwrap_or(ObjectID::ZERO);
+        let root_address = artifacts
+            .published_at
+            .as_ref()
+            .copied()
+            .unwrap_or(ObjectID::ZERO);
         debug!("Root address is {}", root_address);
         let package_name = artifacts
             .package
@@ -216,6 +267,12 @@ impl SuiCompiledPackage {
             .into());
         }
         debug!("Package {} has {} modules", root_address, modules.len());
+        let dependency_modules = artifacts
+            .package
+            .all_compiled_units()
+            .filter(|m| root_address != m.address.into_inner().into())
+            .map(|m| m.module.clone())
+            .collect::<Vec<_>>();
         // let deps = modules
         //     .iter()
         //     .flat_map(|m| {
@@ -239,7 +296,7 @@ impl SuiCompiledPackage {
             root_address,
             deps.iter().map(|t| t.to_string()).join(",")
         );
-        Ok(SuiCompiledPackage {
+        let package = SuiCompiledPackage {
             package_id: (*root_address).into(),
             package_name,
             package_names,
@@ -251,11 +308,18 @@ impl SuiCompiledPackage {
                 .values()
                 .cloned()
                 .collect(),
-        })
+        };
+        Ok((package, dependency_modules))
     }
 
     pub fn build_quick(package: &str, module: &str, content: &str) -> Result<Self, MovyError> {
-        let dir = tempfile::TempDir::new()?;
+        let dir = Self::quick_package(package, &[(module, content)])?;
+        Self::build_all_unpublished_from_folder(dir.path(), false)
+    }
+
+    /// Writes a package of `(module, content)` sources into a temporary folder.
+    pub fn quick_package(package: &str, modules: &[(&str, &str)]) -> Result<TempDir, MovyError> {
+        let dir = TempDir::new()?;
 
         let toml = format!(
             r#"[package]
@@ -275,10 +339,209 @@ impl SuiCompiledPackage {
 
         std::fs::create_dir_all(dir.path().join("sources"))?;
 
-        let mut fp = std::fs::File::create(dir.path().join(format!("sources/{}.move", module)))?;
-        fp.write_all(content.as_bytes())?;
+        for (module, content) in modules {
+            let mut fp =
+                std::fs::File::create(dir.path().join(format!("sources/{}.move", module)))?;
+            fp.write_all(content.as_bytes())?;
+        }
+        Ok(dir)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FunctionSources {
+    definition: (u32, u32),
+    code: Vec<Option<(u32, u32)>>,
+    // Parameters followed by locals, in the order of local indexes
+    locals: Vec<String>,
+}
 
-        Self::build_all_unpublished_from_folder(dir.path(), false)
+#[derive(Debug, Clone)]
+struct ModuleSources {
+    path: PathBuf,
+    source: String,
+    line_starts: Vec<u32>,
+    functions: BTreeMap<String, FunctionSources>,
+}
+
+impl ModuleSources {
+    fn span(&self, (start, end): (u32, u32)) -> SourceSpan {
+        let position = |offset: u32| {
+            let line = self.line_starts.partition_point(|s| *s <= offset).max(1);
+            (line as u32, offset - self.line_starts[line - 1] + 1)
+        };
+        let (start_line, start_column) = position(start);
+        let (end_line, end_column) = position(end);
+        SourceSpan {
+            file: self.path.display().to_string(),
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+/// Source locations of the functions and bytecode offsets of a local package, resolved
+/// from the source maps emitted by the compiler.
+#[derive(Debug, Clone, Default)]
+pub struct SuiPackageSources {
+    name: String,
+    // The folder holding the Move.toml
+    root: PathBuf,
+    modules: BTreeMap<String, ModuleSources>,
+}
+
+impl SuiPackageSources {
+    pub fn from_folder(folder: &Path) -> Result<Self, MovyError> {
+        let (artifacts, _) = build_package_resolved(folder, false)?;
+        Self::from_compiled(&artifacts)
+    }
+
+    /// The sources of the root package of a build.
+    pub fn from_compiled(artifacts: &CompiledPackage) -> Result<Self, MovyError> {
+        let root_address = artifacts
+            .published_at
+            .as_ref()
+            .copied()
+            .unwrap_or(ObjectID::ZERO);
+        let mut modules = BTreeMap::new();
+        for unit in artifacts.package.all_compiled_units_with_source() {
+            if root_address != unit.unit.address.into_inner().into() {
+                continue;
+            }
+            let module = &unit.unit.module;
+            let source = std::fs::read_to_string(&unit.source_path)?;
+            let line_starts = std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(idx, _)| idx as u32 + 1))
+                .collect();
+            let mut functions = BTreeMap::new();
+            for (idx, def) in module.function_defs().iter().enumerate() {
+                let name = module.identifier_at(module.function_handle_at(def.function).name);
+                let Ok(function_map) = unit
+                    .unit
+                    .source_map
+                    .get_function_source_map(FunctionDefinitionIndex(idx as u16))
+                else {
+                    continue;
+                };
+                let code = def
+                    .code
+                    .as_ref()
+                    .map(|code| {
+                        (0..code.code.len())
+                            .map(|offset| {
+                                function_map
+                                    .get_code_location(offset as u16)
+                                    .map(|loc| (loc.start(), loc.end()))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let locals = function_map
+                    .parameters
+                    .iter()
+                    .chain(function_map.locals.iter())
+                    .map(|(name, _)| name.to_string())
+                    .collect();
+                let definition = &function_map.definition_location;
+                functions.insert(
+                    name.to_string(),
+                    FunctionSources {
+                        definition: (definition.start(), definition.end()),
+                        code,
+                        locals,
+                    },
+                );
+            }
+            debug!(
+                "Loaded source map of {} from {}",
+                module.self_id().name(),
+                unit.source_path.display()
+            );
+            modules.insert(
+                module.self_id().name().to_string(),
+                ModuleSources {
+                    path: unit.source_path.clone(),
+                    source,
+                    line_starts,
+                    functions,
+                },
+            );
+        }
+        let root = modules
+            .values()
+            .find_map(|module| {
+                module
+                    .path
+                    .ancestors()
+                    .find(|path| path.join("Move.toml").exists())
+            })
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Ok(Self {
+            name: artifacts
+                .package
+                .compiled_package_info
+                .package_name
+                .to_string(),
+            root,
+            modules,
+        })
+    }
+
+    /// The name of the package in its Move.toml.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The folder of the package, holding its Move.toml.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn contains_module(&self, module: &str) -> bool {
+        self.modules.contains_key(module)
+    }
+
+    /// `(module, path)` of every source file of the package.
+    pub fn source_files(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.modules
+            .iter()
+            .map(|(name, module)| (name.as_str(), module.path.as_path()))
+    }
+
+    /// Locate a function, or one of its instructions if `offset` is given.
+    pub fn locate(&self, module: &str, function: &str, offset: Option<u16>) -> Option<SourceSpan> {
+        let module = self.modules.get(module)?;
+        let function = module.functions.get(function)?;
+        let range = offset
+            .and_then(|offset| function.code.get(offset as usize).copied().flatten())
+            .unwrap_or(function.definition);
+        Some(module.span(range))
+    }
+
+    /// The source name of a parameter or local of a function.
+    pub fn local_name(&self, module: &str, function: &str, idx: usize) -> Option<&str> {
+        self.modules
+            .get(module)?
+            .functions
+            .get(function)?
+            .locals
+            .get(idx)
+            .map(String::as_str)
+    }
+
+    /// The 1-based `line` of the source file of a module, without the line break.
+    pub fn line(&self, module: &str, line: u32) -> Option<&str> {
+        let module = self.modules.get(module)?;
+        let start = *module.line_starts.get((line as usize).checked_sub(1)?)? as usize;
+        let end = module
+            .line_starts
+            .get(line as usize)
+            .map(|end| *end as usize)
+            .unwrap_or(module.source.len());
+        module.source.get(start..end).map(|v| v.trim_end())
     }
 }
 
@@ -80,6 +80,60 @@ pub(crate) mod txns_query {
     pub struct Uint53(pub String);
 }
 
+// query PackageTransactions(
+//   $last: Int, $before: String, $function: String, $beforeCheckpoint: UInt53
+// ) {
+//   transactions(
+//     last: $last,
+//     before: $before,
+//     filter: {function: $function, beforeCheckpoint: $beforeCheckpoint}
+//   ) {
+//     nodes {
+//       transactionBcs
+//       effects {
+//         checkpoint {
+//           sequenceNumber
+//         }
+//         effectsBcs
+//       }
+//     }
+//     pageInfo {
+//       hasPreviousPage
+//       startCursor
+//     }
+//   }
+// }
+
+pub(crate) mod package_txns_query {
+    use super::*;
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct PackageTransactionsVariables {
+        pub last: Option<i32>,
+        pub before: Option<String>,
+        pub function: Option<String>,
+        pub before_checkpoint: Option<u64>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "Query", variables = "PackageTransactionsVariables")]
+    pub struct PackageTransactions {
+        #[arguments(last: $last, before: $before, filter: { function: $function, beforeCheckpoint: $before_checkpoint })]
+        pub transactions: Option<TransactionConnection>,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct TransactionConnection {
+        pub nodes: Vec<txns_query::Transaction>,
+        pub page_info: PageInfo,
+    }
+
+    #[derive(cynic::QueryFragment, Debug)]
+    pub struct PageInfo {
+        pub has_previous_page: bool,
+        pub start_cursor: Option<String>,
+    }
+}
+
 // query Objects($keys: [ObjectKey!]!) {
 //   multiGetObjects(keys: $keys) {
 //     objectBcs
@@ -210,6 +264,18 @@ pub(crate) mod epoches_query {
     pub struct Uint53(pub String);
 }
 
+// query ChainIdentifierQuery {
+//   chainIdentifier
+// }
+pub(crate) mod chain_identifier_query {
+    use super::*;
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "Query")]
+    pub struct ChainIdentifierQuery {
+        pub chain_identifier: String,
+    }
+}
+
 // query ListDynamicFields($first: Int = 10, $after: String = "", $address: SuiAddress!, $checkpoint: UInt53!) {
 //   address(address: $address) {
 //     dynamicFields(first: $first, after: $after) {
@@ -429,6 +495,31 @@ pub struct TransactionGraphQlResponse {
     pub checkpoint: u64,
 }
 
+impl TryFrom<txns_query::Transaction> for TransactionGraphQlResponse {
+    type Error = MovyError;
+
+    fn try_from(value: txns_query::Transaction) -> Result<Self, Self::Error> {
+        let tx: TransactionData =
+            base64_to_object(&value.transaction_bcs.ok_or_else(|| eyre!("no tx data"))?.0)?;
+        let effects = value.effects.ok_or_else(|| eyre!("no effects"))?;
+        let checkpoint = effects
+            .checkpoint
+            .ok_or_else(|| eyre!("no tx ckpt"))?
+            .sequence_number;
+        let effects: TransactionEffects = base64_to_object(
+            &effects
+                .effects_bcs
+                .ok_or_else(|| eyre!("no effects bcs"))?
+                .0,
+        )?;
+        Ok(Self {
+            tx,
+            effects,
+            checkpoint,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EpochData {
     pub epoch: u64,
@@ -690,30 +781,10 @@ impl GraphQlClientInner {
         let mut mp = vec![];
         for (idx, tx_resp) in transactions.into_iter().enumerate() {
             if let Some(tx_resp) = tx_resp {
-                let tx_data: TransactionData = base64_to_object(
-                    &tx_resp
-                        .transaction_bcs
-                        .ok_or_else(|| eyre!("no tx data {:?}", digests))?
-                        .0,
-                )?;
-                let effects = tx_resp
-                    .effects
-                    .ok_or_else(|| eyre!("no effects {:?}", digests))?;
-                let tx_effects: TransactionEffects = base64_to_object(
-                    &effects
-                        .effects_bcs
-                        .ok_or_else(|| eyre!("no effects bcs {:?}", digests))?
-                        .0,
-                )?;
-                let tx_checkpoint = effects
-                    .checkpoint
-                    .ok_or_else(|| eyre!("no tx ckpt {:?}", digests))?
-                    .sequence_number;
-                mp.push(TransactionGraphQlResponse {
-                    tx: tx_data,
-                    effects: tx_effects,
-                    checkpoint: tx_checkpoint,
-                });
+                mp.push(
+                    TransactionGraphQlResponse::try_from(tx_resp)
+                        .map_err(|e| eyre!("{} for {:?}", e, digests))?,
+                );
             } else {
                 log::debug!("Got a none in tx resp, probably {:?}", digests.get(idx));
             }
@@ -722,6 +793,59 @@ impl GraphQlClientInner {
         Ok(mp)
     }
 
+    /// The last `limit` transactions calling into `function`, which is a `package`,
+    /// `package::module` or `package::module::name`, ordered from the oldest.
+    pub async fn query_function_transactions(
+        &self,
+        function: String,
+        before_checkpoint: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<TransactionGraphQlResponse>, MovyError> {
+        let mut out = vec![];
+
+        let mut before = None;
+        while out.len() < limit {
+            let query = package_txns_query::PackageTransactions::build(
+                package_txns_query::PackageTransactionsVariables {
+                    last: Some(MAX_PER_PAGE.min((limit - out.len()) as i32)),
+                    before,
+                    function: Some(function.clone()),
+                    before_checkpoint,
+                },
+            );
+            let response = self
+                .run_query(&query)
+                .await
+                .map_err(|e| eyre!("Failed to run transaction query: {}", e))?;
+            let transactions = response
+                .data
+                .and_then(|v| v.transactions)
+                .ok_or_else(|| eyre!("no transactions from request"))?;
+
+            // Pages are walked backwards, so prepend each older page
+            let mut page = transactions
+                .nodes
+                .into_iter()
+                .map(TransactionGraphQlResponse::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            page.append(&mut out);
+            out = page;
+
+            if transactions.page_info.has_previous_page {
+                before = Some(
+                    transactions
+                        .page_info
+                        .start_cursor
+                        .ok_or_else(|| eyre!("has previous but not cursor?!"))?,
+                );
+            } else {
+                break;
+            }
+        }
+
+        Ok(out)
+    }
+
     pub async fn query_objects(
         &self,
         keys: Vec<objects_query::ObjectKey>,
@@ -754,6 +878,19 @@ impl GraphQlClientInner {
         Ok(out)
     }
 
+    /// The hex of the first four bytes of the genesis checkpoint digest of the network.
+    pub async fn query_chain_identifier(&self) -> Result<String, MovyError> {
+        let query = chain_identifier_query::ChainIdentifierQuery::build(());
+        let response = self
+            .run_query(&query)
+            .await
+            .map_err(|e| eyre!("Failed to run chain identifier query: {}", e))?;
+        Ok(response
+            .data
+            .ok_or_else(|| eyre!("no chain identifier"))?
+            .chain_identifier)
+    }
+
     pub async fn query_epoches(&self, keys: Vec<u64>) -> Result<Vec<EpochData>, MovyError> {
         let query = epoches_query::EpochesQuery::build(epoches_query::EpochesQueryVariables {
             keys: keys.clone(),
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display},
     str::FromStr,
 };
@@ -9,15 +10,21 @@ use serde::{Deserialize, Serialize};
 use sui_types::{
     Identifier, TypeTag,
     base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress},
+    gas_coin::GasCoin,
     programmable_transaction_builder::ProgrammableTransactionBuilder,
+    storage::ObjectStore,
     transaction::{
-        Argument, Command, ObjectArg, ProgrammableMoveCall, ProgrammableTransaction,
+        Argument, CallArg, Command, ObjectArg, ProgrammableMoveCall, ProgrammableTransaction,
         SharedObjectMutability,
     },
     type_input::{StructInput, TypeInput},
 };
 
-use crate::{abi::MoveModuleId, error::MovyError};
+use crate::{
+    abi::{MoveModuleId, MovePackageAbi},
+    error::MovyError,
+    object::MoveObjectInfo,
+};
 
 #[derive(
     Copy, Debug, Clone, Hash, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default,
@@ -263,6 +270,49 @@ pub enum InputArgument {
     Signer(MoveAddress),
     Address(MoveAddress),
     Object(MoveTypeTag, SuiObjectInputArgument), // TODO: Gated via `sui` feature
+    /// `0x1::string::String` or `0x1::ascii::String`, tagged by its type.
+    String(MoveTypeTag, String),
+    /// `0x1::option::Option<T>`, tagged by `T`.
+    Option(MoveTypeTag, Option<Box<InputArgument>>),
+}
+
+fn is_std_struct(tag: &MoveStructTag, module: &str, name: &str) -> bool {
+    tag.address == MoveAddress::one() && tag.module == module && tag.name == name
+}
+
+fn read_uleb128(bytes: &mut &[u8]) -> Result<u64, MovyError> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let [b] = take::<1>(bytes)?;
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+        if shift > 28 {
+            return Err(MovyError::InvalidSeed("invalid uleb128 length".to_string()));
+        }
+    }
+}
+
+fn write_uleb128(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], MovyError> {
+    if bytes.len() < N {
+        return Err(MovyError::InvalidSeed(
+            "unexpected end of bcs bytes".to_string(),
+        ));
+    }
+    let (head, tail) = bytes.split_at(N);
+    *bytes = tail;
+    Ok(head.try_into().expect("split at N"))
 }
 
 impl InputArgument {
@@ -279,8 +329,148 @@ impl InputArgument {
             Self::U64(_) => MoveTypeTag::U64,
             Self::U128(_) => MoveTypeTag::U128,
             Self::U256(_) => MoveTypeTag::U256,
+            Self::String(ty, _) => ty.clone(),
+            Self::Option(ty, _) => MoveTypeTag::Struct(MoveStructTag {
+                address: MoveAddress::one(),
+                module: "option".to_string(),
+                name: "Option".to_string(),
+                tys: vec![ty.clone()],
+            }),
         }
     }
+
+    /// The BCS bytes of a pure input, i.e. any but objects.
+    pub fn to_bcs(&self) -> Result<Vec<u8>, MovyError> {
+        let mut out = vec![];
+        self.encode_bcs(&mut out)?;
+        Ok(out)
+    }
+
+    fn encode_bcs(&self, out: &mut Vec<u8>) -> Result<(), MovyError> {
+        match self {
+            Self::Bool(v) => out.push(*v as u8),
+            Self::U8(v) => out.push(*v),
+            Self::U16(v) => out.extend(v.to_le_bytes()),
+            Self::U32(v) => out.extend(v.to_le_bytes()),
+            Self::U64(v) => out.extend(v.to_le_bytes()),
+            Self::U128(v) => out.extend(v.to_le_bytes::<16>()),
+            Self::U256(v) => out.extend(v.to_le_bytes::<32>()),
+            Self::Address(v) | Self::Signer(v) => out.extend(AccountAddress::from(*v).into_bytes()),
+            Self::Vector(_, vs) => {
+                write_uleb128(vs.len() as u64, out);
+                for v in vs.iter() {
+                    v.encode_bcs(out)?;
+                }
+            }
+            Self::String(_, v) => {
+                write_uleb128(v.len() as u64, out);
+                out.extend(v.as_bytes());
+            }
+            Self::Option(_, v) => match v {
+                Some(v) => {
+                    out.push(1);
+                    v.encode_bcs(out)?;
+                }
+                None => out.push(0),
+            },
+            Self::Object(ty, _) => {
+                return Err(MovyError::Unsupported(format!("bcs of object {}", ty)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a pure input from its BCS bytes, `ty` is the type the input is used as.
+    pub fn from_bcs(ty: &MoveTypeTag, bytes: &[u8]) -> Result<Self, MovyError> {
+        let mut rest = bytes;
+        let arg = Self::decode_bcs(ty, &mut rest)?;
+        if !rest.is_empty() {
+            return Err(MovyError::InvalidSeed(format!(
+                "{} trailing bytes when decoding {} from {}",
+                rest.len(),
+                ty,
+                const_hex::encode(bytes)
+            )));
+        }
+        Ok(arg)
+    }
+
+    fn decode_bcs(ty: &MoveTypeTag, bytes: &mut &[u8]) -> Result<Self, MovyError> {
+        let v = match ty {
+            MoveTypeTag::Bool => match take::<1>(bytes)? {
+                [0] => Self::Bool(false),
+                [1] => Self::Bool(true),
+                [b] => return Err(MovyError::InvalidSeed(format!("invalid bool {}", b))),
+            },
+            MoveTypeTag::U8 => Self::U8(u8::from_le_bytes(take(bytes)?)),
+            MoveTypeTag::U16 => Self::U16(u16::from_le_bytes(take(bytes)?)),
+            MoveTypeTag::U32 => Self::U32(u32::from_le_bytes(take(bytes)?)),
+            MoveTypeTag::U64 => Self::U64(u64::from_le_bytes(take(bytes)?)),
+            MoveTypeTag::U128 => Self::U128(U128::from_le_bytes(take::<16>(bytes)?)),
+            MoveTypeTag::U256 => Self::U256(U256::from_le_bytes(take::<32>(bytes)?)),
+            MoveTypeTag::Address => Self::Address(AccountAddress::new(take(bytes)?).into()),
+            MoveTypeTag::Signer => Self::Signer(AccountAddress::new(take(bytes)?).into()),
+            MoveTypeTag::Vector(elem) => {
+                let len = read_uleb128(bytes)?;
+                let elems = (0..len)
+                    .map(|_| Self::decode_bcs(elem, bytes))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self::Vector(elem.as_ref().clone(), elems)
+            }
+            // ID shares the layout with address and pure inputs are only checked by layout
+            MoveTypeTag::Struct(tag)
+                if tag.address == MoveAddress::two()
+                    && tag.module == "object"
+                    && tag.name == "ID" =>
+            {
+                Self::Address(AccountAddress::new(take(bytes)?).into())
+            }
+            // Strings are vectors of bytes checked to be utf8 or ascii
+            MoveTypeTag::Struct(tag)
+                if is_std_struct(tag, "string", "String")
+                    || is_std_struct(tag, "ascii", "String") =>
+            {
+                let len = read_uleb128(bytes)? as usize;
+                if bytes.len() < len {
+                    return Err(MovyError::InvalidSeed(
+                        "unexpected end of bcs bytes".to_string(),
+                    ));
+                }
+                let (head, tail) = bytes.split_at(len);
+                *bytes = tail;
+                let v = String::from_utf8(head.to_vec())
+                    .map_err(|e| MovyError::InvalidSeed(format!("invalid {}: {}", ty, e)))?;
+                if tag.module == "ascii" && !v.is_ascii() {
+                    return Err(MovyError::InvalidSeed(format!("invalid {}: {}", ty, v)));
+                }
+                Self::String(ty.clone(), v)
+            }
+            // Options are vectors of at most one element
+            MoveTypeTag::Struct(tag) if is_std_struct(tag, "option", "Option") => {
+                let [elem] = tag.tys.as_slice() else {
+                    return Err(MovyError::InvalidSeed(format!(
+                        "invalid option type {}",
+                        ty
+                    )));
+                };
+                let v = match read_uleb128(bytes)? {
+                    0 => None,
+                    1 => Some(Box::new(Self::decode_bcs(elem, bytes)?)),
+                    len => {
+                        return Err(MovyError::InvalidSeed(format!(
+                            "option of {} elements",
+                            len
+                        )));
+                    }
+                };
+                Self::Option(elem.clone(), v)
+            }
+            _ => {
+                return Err(MovyError::Unsupported(format!("pure input of type {}", ty)));
+            }
+        };
+        Ok(v)
+    }
 }
 
 impl Display for InputArgument {
@@ -304,6 +494,11 @@ impl Display for InputArgument {
             InputArgument::Signer(addr) => write!(f, "Signer({})", addr),
             InputArgument::Address(addr) => write!(f, "Address({})", addr),
             InputArgument::Object(ty, obj) => write!(f, "Object<{}>({})", ty, obj),
+            InputArgument::String(ty, v) => write!(f, "{}({:?})", ty, v),
+            InputArgument::Option(ty, v) => match v {
+                Some(v) => write!(f, "Option<{}>({})", ty, v),
+                None => write!(f, "Option<{}>(None)", ty),
+            },
         }
     }
 }
@@ -714,6 +909,76 @@ pub struct MoveSequence {
     pub commands: Vec<MoveSequenceCall>,
 }
 
+fn sequence_arg(arg: &Argument) -> SequenceArgument {
+    match arg {
+        Argument::GasCoin => SequenceArgument::GasCoin,
+        Argument::Input(idx) => SequenceArgument::Input(*idx),
+        Argument::Result(idx) => SequenceArgument::Result(*idx),
+        Argument::NestedResult(idx, nested) => SequenceArgument::NestedResult(*idx, *nested),
+    }
+}
+
+fn sequence_args(args: &[Argument]) -> Vec<SequenceArgument> {
+    args.iter().map(sequence_arg).collect()
+}
+
+fn ptb_type_tag(ty: &TypeInput) -> Result<MoveTypeTag, MovyError> {
+    Ok(ty
+        .to_type_tag()
+        .map_err(|e| MovyError::InvalidSeed(format!("invalid type {:?}: {}", ty, e)))?
+        .into())
+}
+
+fn ptb_object_arg(arg: &ObjectArg) -> SuiObjectInputArgument {
+    match arg {
+        ObjectArg::ImmOrOwnedObject(r) => SuiObjectInputArgument::ImmOrOwnedObject(*r),
+        ObjectArg::Receiving(r) => SuiObjectInputArgument::Receiving(*r),
+        ObjectArg::SharedObject {
+            id,
+            initial_shared_version,
+            mutability,
+        } => SuiObjectInputArgument::SharedObject {
+            id: *id,
+            initial_shared_version: *initial_shared_version,
+            mutable: !matches!(mutability, SharedObjectMutability::Immutable),
+        },
+    }
+}
+
+fn ptb_command_name(cmd: &Command) -> &'static str {
+    match cmd {
+        Command::MoveCall(_) => "MoveCall",
+        Command::TransferObjects(_, _) => "TransferObjects",
+        Command::SplitCoins(_, _) => "SplitCoins",
+        Command::MergeCoins(_, _) => "MergeCoins",
+        Command::Publish(_, _) => "Publish",
+        Command::MakeMoveVec(_, _) => "MakeMoveVec",
+        Command::Upgrade(_, _, _, _) => "Upgrade",
+    }
+}
+
+/// The type of an argument as far as it is known from the object inputs and the results of
+/// the former commands.
+fn ptb_argument_type(
+    arg: &Argument,
+    object_types: &BTreeMap<u16, MoveTypeTag>,
+    results: &[Vec<Option<MoveTypeTag>>],
+) -> Option<MoveTypeTag> {
+    match arg {
+        Argument::GasCoin => Some(TypeTag::Struct(Box::new(GasCoin::type_())).into()),
+        Argument::Input(idx) => object_types.get(idx).cloned(),
+        Argument::Result(idx) => match results.get(*idx as usize)?.as_slice() {
+            [ty] => ty.clone(),
+            _ => None,
+        },
+        Argument::NestedResult(idx, nested) => results
+            .get(*idx as usize)?
+            .get(*nested as usize)
+            .cloned()
+            .flatten(),
+    }
+}
+
 impl MoveSequence {
     fn sui_builder_input_arg(
         builder: &mut ProgrammableTransactionBuilder,
@@ -746,25 +1011,67 @@ impl MoveSequence {
                 Ok(builder.command(Command::MakeMoveVec(Some(ty.clone().into()), args)))
             }
             InputArgument::Object(_, v) => builder.obj(v.clone().into()),
+            InputArgument::String(_, v) => builder.pure(v.clone()),
+            InputArgument::Option(_, _) => Ok(builder.pure_bytes(arg.to_bcs()?, false)),
         };
         Ok(v?)
     }
+    fn vector_commands(arg: &InputArgument) -> u16 {
+        match arg {
+            InputArgument::Vector(_, vs) => 1 + vs.iter().map(Self::vector_commands).sum::<u16>(),
+            _ => 0,
+        }
+    }
+
+    /// The number of commands building the vector inputs ahead of the ones of the sequence in
+    /// [`Self::to_ptb`].
+    fn input_commands(&self) -> u16 {
+        self.inputs.iter().map(Self::vector_commands).sum()
+    }
+
+    /// The index into `commands` of the `command`-th command of [`Self::to_ptb`], e.g. the
+    /// one an execution fails at, or None if it builds a vector input.
+    pub fn sequence_command(&self, command: usize) -> Option<usize> {
+        command
+            .checked_sub(self.input_commands() as usize)
+            .filter(|idx| *idx < self.commands.len())
+    }
+
     pub fn to_ptb(&self) -> Result<ProgrammableTransaction, MovyError> {
         let mut builder = ProgrammableTransactionBuilder::new();
 
-        for input in self.inputs.iter() {
-            Self::sui_builder_input_arg(&mut builder, input)?;
-        }
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| Self::sui_builder_input_arg(&mut builder, input))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The builder dedups pure inputs and vectors are built by commands ahead of ours, so
+        // the arguments are mapped to where the inputs and results actually are in the ptb.
+        let offset = self.input_commands();
+        let arg = |v: &SequenceArgument| match v {
+            SequenceArgument::Input(idx) => inputs
+                .get(*idx as usize)
+                .copied()
+                .unwrap_or(Argument::Input(*idx)),
+            SequenceArgument::Result(idx) => Argument::Result(idx + offset),
+            SequenceArgument::NestedResult(idx, nested) => {
+                Argument::NestedResult(idx + offset, *nested)
+            }
+            SequenceArgument::GasCoin => Argument::GasCoin,
+        };
 
         for cmd in self.commands.iter() {
             match cmd {
                 MoveSequenceCall::Call(call) => {
-                    builder.command(Command::MoveCall(Box::new(call.clone().into())));
+                    let mut mc = ProgrammableMoveCall::from(call.clone());
+                    mc.arguments = call.arguments.iter().map(arg).collect();
+                    builder.command(Command::MoveCall(Box::new(mc)));
                 }
                 MoveSequenceCall::MakeMoveVec(ty, args) => {
                     builder.command(Command::MakeMoveVec(
                         Some(ty.clone().into()),
-                        args.iter().map(|v| (*v).into()).collect(),
+                        args.iter().map(arg).collect(),
                     ));
                 }
                 MoveSequenceCall::Publish(modules, address) => {
@@ -774,23 +1081,23 @@ impl MoveSequence {
                     ));
                 }
                 MoveSequenceCall::TransferObjects(args, dst) => {
-                    let src = args.iter().map(|v| (*v).into()).collect();
-                    builder.command(Command::TransferObjects(src, (*dst).into()));
+                    let src = args.iter().map(arg).collect();
+                    builder.command(Command::TransferObjects(src, arg(dst)));
                 }
                 MoveSequenceCall::MergeCoins(dst, src) => {
-                    let src = src.iter().map(|v| (*v).into()).collect();
-                    builder.command(Command::MergeCoins((*dst).into(), src));
+                    let src = src.iter().map(arg).collect();
+                    builder.command(Command::MergeCoins(arg(dst), src));
                 }
                 MoveSequenceCall::SplitCoins(src, amounts) => {
-                    let amounts = amounts.iter().map(|v| (*v).into()).collect();
-                    builder.command(Command::SplitCoins((*src).into(), amounts));
+                    let amounts = amounts.iter().map(arg).collect();
+                    builder.command(Command::SplitCoins(arg(src), amounts));
                 }
                 MoveSequenceCall::Upgrade(modules, deps, package, ticket) => {
                     builder.command(Command::Upgrade(
                         modules.clone(),
                         deps.iter().map(|v| (*v).into()).collect(),
                         (*package).into(),
-                        (*ticket).into(),
+                        arg(ticket),
                     ));
                 }
             }
@@ -798,6 +1105,190 @@ impl MoveSequence {
 
         Ok(builder.finish())
     }
+
+    /// Convert a ptb into a sequence. Object inputs are typed by the objects in `db` and pure
+    /// inputs by the first command consuming them, e.g. the parameter of a move call.
+    pub fn try_from_ptb<T: ObjectStore>(
+        db: &T,
+        ptb: &ProgrammableTransaction,
+    ) -> Result<Self, MovyError> {
+        let mut object_types = BTreeMap::new();
+        for (idx, input) in ptb.inputs.iter().enumerate() {
+            if let CallArg::Object(arg) = input {
+                let id = ptb_object_arg(arg).id();
+                let object = db
+                    .get_object(&id)
+                    .ok_or_else(|| MovyError::InvalidSeed(format!("object {} not found", id)))?;
+                object_types.insert(idx as u16, MoveObjectInfo::try_from(&object)?.ty);
+            }
+        }
+
+        let mut abis: BTreeMap<MoveAddress, MovePackageAbi> = BTreeMap::new();
+        let mut pure_types: BTreeMap<u16, MoveTypeTag> = BTreeMap::new();
+        let mut consumers: BTreeMap<u16, &'static str> = BTreeMap::new();
+        let mut results: Vec<Vec<Option<MoveTypeTag>>> = vec![];
+        let mut commands = vec![];
+        for (cmd_idx, cmd) in ptb.commands.iter().enumerate() {
+            let name = ptb_command_name(cmd);
+            let mut used_as = |arg: &Argument, ty: Option<MoveTypeTag>| {
+                if let Argument::Input(idx) = arg {
+                    consumers.entry(*idx).or_insert(name);
+                    if let Some(ty) = ty {
+                        pure_types.entry(*idx).or_insert(ty);
+                    }
+                }
+            };
+            let arg_type = |arg: &Argument| ptb_argument_type(arg, &object_types, &results);
+            let (cmd, returns) = match cmd {
+                Command::MoveCall(call) => {
+                    let package: MoveAddress = call.package.into();
+                    let type_arguments = call
+                        .type_arguments
+                        .iter()
+                        .map(ptb_type_tag)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if !abis.contains_key(&package) {
+                        let object = db.get_object(&call.package).ok_or_else(|| {
+                            MovyError::InvalidSeed(format!("package {} not found", package))
+                        })?;
+                        abis.insert(package, MovePackageAbi::from_sui_object(&object)?);
+                    }
+                    let function = abis[&package]
+                        .modules
+                        .iter()
+                        .find(|md| md.module_id.module_name == call.module)
+                        .and_then(|md| md.functions.iter().find(|f| f.name == call.function))
+                        .ok_or_else(|| {
+                            MovyError::InvalidSeed(format!(
+                                "function {}::{}::{} not found",
+                                package, call.module, call.function
+                            ))
+                        })?;
+                    let ty_args = type_arguments
+                        .iter()
+                        .cloned()
+                        .enumerate()
+                        .map(|(idx, ty)| (idx as u16, ty))
+                        .collect();
+                    for (arg, param) in call.arguments.iter().zip(function.parameters.iter()) {
+                        used_as(arg, param.subst(&ty_args));
+                    }
+                    let returns = function
+                        .return_paramters
+                        .iter()
+                        .map(|ty| ty.subst(&ty_args))
+                        .collect();
+                    let cmd = MoveSequenceCall::Call(MoveCall {
+                        module_id: package,
+                        module_name: call.module.clone(),
+                        function: call.function.clone(),
+                        type_arguments,
+                        arguments: sequence_args(&call.arguments),
+                    });
+                    (cmd, returns)
+                }
+                Command::TransferObjects(objects, dst) => {
+                    for object in objects.iter() {
+                        used_as(object, None);
+                    }
+                    used_as(dst, Some(MoveTypeTag::Address));
+                    let cmd = MoveSequenceCall::TransferObjects(
+                        sequence_args(objects),
+                        sequence_arg(dst),
+                    );
+                    (cmd, vec![])
+                }
+                Command::SplitCoins(src, amounts) => {
+                    used_as(src, None);
+                    for amount in amounts.iter() {
+                        used_as(amount, Some(MoveTypeTag::U64));
+                    }
+                    let returns = vec![arg_type(src); amounts.len()];
+                    let cmd =
+                        MoveSequenceCall::SplitCoins(sequence_arg(src), sequence_args(amounts));
+                    (cmd, returns)
+                }
+                Command::MergeCoins(dst, srcs) => {
+                    used_as(dst, None);
+                    for src in srcs.iter() {
+                        used_as(src, None);
+                    }
+                    let cmd = MoveSequenceCall::MergeCoins(sequence_arg(dst), sequence_args(srcs));
+                    (cmd, vec![])
+                }
+                Command::Publish(modules, deps) => {
+                    let cmd = MoveSequenceCall::Publish(
+                        modules.clone(),
+                        deps.iter().map(|dep| (*dep).into()).collect(),
+                    );
+                    (cmd, vec![])
+                }
+                Command::MakeMoveVec(ty, elems) => {
+                    let ty = match ty {
+                        Some(ty) => ptb_type_tag(ty)?,
+                        // The type can only be omitted for non-empty vectors of objects, which
+                        // are either object inputs or results of the former commands
+                        None => elems.first().and_then(arg_type).ok_or_else(|| {
+                            MovyError::Unsupported(format!(
+                                "can not infer the element type of MakeMoveVec at command {}",
+                                cmd_idx
+                            ))
+                        })?,
+                    };
+                    for elem in elems.iter() {
+                        used_as(elem, Some(ty.clone()));
+                    }
+                    let returns = vec![Some(MoveTypeTag::Vector(Box::new(ty.clone())))];
+                    (
+                        MoveSequenceCall::MakeMoveVec(ty, sequence_args(elems)),
+                        returns,
+                    )
+                }
+                Command::Upgrade(modules, deps, package, ticket) => {
+                    used_as(ticket, None);
+                    let cmd = MoveSequenceCall::Upgrade(
+                        modules.clone(),
+                        deps.iter().map(|dep| (*dep).into()).collect(),
+                        (*package).into(),
+                        sequence_arg(ticket),
+                    );
+                    (cmd, vec![])
+                }
+            };
+            commands.push(cmd);
+            results.push(returns);
+        }
+
+        let inputs = ptb
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                let idx = idx as u16;
+                match input {
+                    CallArg::Pure(bytes) => {
+                        let ty = pure_types.get(&idx).ok_or_else(|| {
+                            MovyError::InvalidSeed(match consumers.get(&idx) {
+                                Some(name) => format!(
+                                    "can not infer the type of pure input {} only consumed by {}",
+                                    idx, name
+                                ),
+                                None => format!("pure input {} is never consumed", idx),
+                            })
+                        })?;
+                        InputArgument::from_bcs(ty, bytes)
+                    }
+                    CallArg::Object(arg) => Ok(InputArgument::Object(
+                        object_types[&idx].clone(),
+                        ptb_object_arg(arg),
+                    )),
+                    _ => Err(MovyError::Unsupported(format!("input {:?}", input))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { inputs, commands })
+    }
 }
 
 impl Display for MoveSequence {
@@ -841,3 +1332,372 @@ impl Display for MoveSequence {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use alloy_primitives::U256;
+    use sui_types::{
+        base_types::{ObjectID, SuiAddress},
+        digests::TransactionDigest,
+        object::{MoveObject, OBJECT_START_VERSION, Object, Owner},
+        transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableTransaction},
+    };
+
+    use crate::{
+        error::MovyError,
+        input::{
+            InputArgument, MoveAddress, MoveCall, MoveSequence, MoveSequenceCall, MoveTypeTag,
+            SequenceArgument, SuiObjectInputArgument,
+        },
+    };
+
+    fn call(function: &str, arguments: Vec<SequenceArgument>) -> MoveSequenceCall {
+        MoveSequenceCall::Call(MoveCall {
+            module_id: MoveAddress::two(),
+            module_name: "test".to_string(),
+            function: function.to_string(),
+            type_arguments: vec![],
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_to_ptb_remaps_arguments() {
+        let sequence = MoveSequence {
+            inputs: vec![
+                InputArgument::U64(1),
+                InputArgument::Vector(
+                    MoveTypeTag::U64,
+                    vec![InputArgument::U64(2), InputArgument::U64(3)],
+                ),
+                InputArgument::U64(1),
+            ],
+            commands: vec![
+                call(
+                    "consume",
+                    vec![
+                        SequenceArgument::Input(0),
+                        SequenceArgument::Input(1),
+                        SequenceArgument::Input(2),
+                    ],
+                ),
+                call(
+                    "chain",
+                    vec![
+                        SequenceArgument::Result(0),
+                        SequenceArgument::NestedResult(0, 1),
+                    ],
+                ),
+            ],
+        };
+        let ptb = sequence.to_ptb().unwrap();
+
+        // The second 1 is deduplicated by the builder into the first pure input
+        assert_eq!(ptb.inputs.len(), 3);
+        for (input, value) in ptb.inputs.iter().zip([1u64, 2, 3]) {
+            assert_eq!(input, &CallArg::Pure(value.to_le_bytes().to_vec()));
+        }
+
+        // The vector is built by a command ahead of the ones of the sequence
+        assert_eq!(ptb.commands.len(), 3);
+        assert_eq!(
+            ptb.commands[0],
+            Command::MakeMoveVec(
+                Some(MoveTypeTag::U64.into()),
+                vec![Argument::Input(1), Argument::Input(2)]
+            )
+        );
+        let Command::MoveCall(consume) = &ptb.commands[1] else {
+            panic!("{:?} is not a move call", ptb.commands[1]);
+        };
+        assert_eq!(
+            consume.arguments,
+            vec![Argument::Input(0), Argument::Result(0), Argument::Input(0)]
+        );
+        let Command::MoveCall(chain) = &ptb.commands[2] else {
+            panic!("{:?} is not a move call", ptb.commands[2]);
+        };
+        assert_eq!(
+            chain.arguments,
+            vec![Argument::Result(1), Argument::NestedResult(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_sequence_command() {
+        let sequence = MoveSequence {
+            inputs: vec![
+                InputArgument::Vector(
+                    MoveTypeTag::Vector(Box::new(MoveTypeTag::U64)),
+                    vec![InputArgument::Vector(
+                        MoveTypeTag::U64,
+                        vec![InputArgument::U64(2)],
+                    )],
+                ),
+                InputArgument::U64(1),
+            ],
+            commands: vec![
+                call("consume", vec![SequenceArgument::Input(0)]),
+                call("fail", vec![SequenceArgument::Input(1)]),
+            ],
+        };
+        assert_eq!(sequence.to_ptb().unwrap().commands.len(), 4);
+
+        // A failure of the later call is reported at its ptb index
+        assert_eq!(sequence.sequence_command(3), Some(1));
+        assert_eq!(sequence.sequence_command(2), Some(0));
+        // The commands building the nested vectors are not in the sequence
+        assert_eq!(sequence.sequence_command(1), None);
+        assert_eq!(sequence.sequence_command(0), None);
+        assert_eq!(sequence.sequence_command(4), None);
+    }
+
+    #[test]
+    fn test_from_bcs() {
+        let decode = |ty: &str, bytes: Vec<u8>| {
+            InputArgument::from_bcs(&MoveTypeTag::from_str(ty).unwrap(), &bytes)
+        };
+        assert_eq!(
+            decode("bool", bcs::to_bytes(&true).unwrap()).unwrap(),
+            InputArgument::Bool(true)
+        );
+        assert_eq!(
+            decode("u64", bcs::to_bytes(&42u64).unwrap()).unwrap(),
+            InputArgument::U64(42)
+        );
+        assert_eq!(
+            decode("u256", [1u8; 32].to_vec()).unwrap(),
+            InputArgument::U256(U256::from_le_bytes([1u8; 32]))
+        );
+        let address = MoveAddress::two();
+        assert_eq!(
+            decode(
+                "address",
+                bcs::to_bytes(&SuiAddress::from(address)).unwrap()
+            )
+            .unwrap(),
+            InputArgument::Address(address)
+        );
+        assert_eq!(
+            decode(
+                "0x2::object::ID",
+                bcs::to_bytes(&SuiAddress::from(address)).unwrap()
+            )
+            .unwrap(),
+            InputArgument::Address(address)
+        );
+        assert_eq!(
+            decode(
+                "vector<vector<u16>>",
+                bcs::to_bytes(&vec![vec![1u16, 2], vec![]]).unwrap()
+            )
+            .unwrap(),
+            InputArgument::Vector(
+                MoveTypeTag::Vector(Box::new(MoveTypeTag::U16)),
+                vec![
+                    InputArgument::Vector(
+                        MoveTypeTag::U16,
+                        vec![InputArgument::U16(1), InputArgument::U16(2)]
+                    ),
+                    InputArgument::Vector(MoveTypeTag::U16, vec![]),
+                ]
+            )
+        );
+        // Longer than 127 elements takes more than one byte of length
+        let long = vec![7u8; 200];
+        let InputArgument::Vector(_, elems) =
+            decode("vector<u8>", bcs::to_bytes(&long).unwrap()).unwrap()
+        else {
+            panic!("not a vector");
+        };
+        assert_eq!(elems.len(), 200);
+
+        let string = MoveTypeTag::from_str("0x1::string::String").unwrap();
+        let ascii = MoveTypeTag::from_str("0x1::ascii::String").unwrap();
+        assert_eq!(
+            decode("0x1::string::String", bcs::to_bytes("héllo").unwrap()).unwrap(),
+            InputArgument::String(string.clone(), "héllo".to_string())
+        );
+        assert_eq!(
+            decode("0x1::ascii::String", bcs::to_bytes("hello").unwrap()).unwrap(),
+            InputArgument::String(ascii, "hello".to_string())
+        );
+        assert_eq!(
+            decode(
+                "0x1::option::Option<u64>",
+                bcs::to_bytes(&Some(7u64)).unwrap()
+            )
+            .unwrap(),
+            InputArgument::Option(MoveTypeTag::U64, Some(Box::new(InputArgument::U64(7))))
+        );
+        assert_eq!(
+            decode(
+                "0x1::option::Option<0x1::string::String>",
+                bcs::to_bytes(&None::<String>).unwrap()
+            )
+            .unwrap(),
+            InputArgument::Option(string, None)
+        );
+        // Encoding them back yields the same bytes
+        for (ty, bytes) in [
+            ("0x1::string::String", bcs::to_bytes("héllo").unwrap()),
+            (
+                "0x1::option::Option<vector<u8>>",
+                bcs::to_bytes(&Some(vec![1u8, 2])).unwrap(),
+            ),
+            (
+                "vector<0x1::option::Option<u16>>",
+                bcs::to_bytes(&vec![None, Some(3u16)]).unwrap(),
+            ),
+        ] {
+            assert_eq!(decode(ty, bytes.clone()).unwrap().to_bcs().unwrap(), bytes);
+        }
+        assert!(matches!(
+            decode("0x1::string::String", bcs::to_bytes(&vec![0xffu8]).unwrap()),
+            Err(MovyError::InvalidSeed(_))
+        ));
+        assert!(matches!(
+            decode("0x1::ascii::String", bcs::to_bytes("héllo").unwrap()),
+            Err(MovyError::InvalidSeed(_))
+        ));
+        assert!(matches!(
+            decode(
+                "0x1::option::Option<u8>",
+                bcs::to_bytes(&vec![1u8, 2]).unwrap()
+            ),
+            Err(MovyError::InvalidSeed(_))
+        ));
+
+        assert!(matches!(
+            decode("bool", vec![2]),
+            Err(MovyError::InvalidSeed(_))
+        ));
+        assert!(matches!(
+            decode("u64", vec![1, 2, 3]),
+            Err(MovyError::InvalidSeed(_))
+        ));
+        assert!(matches!(
+            decode("u8", vec![1, 2]),
+            Err(MovyError::InvalidSeed(_))
+        ));
+        assert!(matches!(
+            decode("0x2::coin::Coin<0x2::sui::SUI>", vec![0; 40]),
+            Err(MovyError::Unsupported(_))
+        ));
+    }
+
+    fn gas_coin(db: &mut BTreeMap<ObjectID, Object>, id: ObjectID) -> CallArg {
+        let coin = Object::new_move(
+            MoveObject::new_gas_coin(OBJECT_START_VERSION, id, 100),
+            Owner::AddressOwner(SuiAddress::ZERO),
+            TransactionDigest::genesis_marker(),
+        );
+        let arg = CallArg::Object(ObjectArg::ImmOrOwnedObject(coin.compute_object_reference()));
+        db.insert(id, coin);
+        arg
+    }
+
+    #[test]
+    fn test_try_from_ptb() {
+        let mut db = BTreeMap::new();
+        let coin = gas_coin(&mut db, ObjectID::from_single_byte(0x42));
+        let recipient = MoveAddress::two();
+        let ptb = ProgrammableTransaction {
+            inputs: vec![
+                coin,
+                CallArg::Pure(bcs::to_bytes(&10u64).unwrap()),
+                CallArg::Pure(bcs::to_bytes(&SuiAddress::from(recipient)).unwrap()),
+            ],
+            commands: vec![
+                Command::SplitCoins(Argument::Input(0), vec![Argument::Input(1); 2]),
+                Command::MakeMoveVec(
+                    None,
+                    vec![Argument::NestedResult(0, 0), Argument::NestedResult(0, 1)],
+                ),
+                Command::TransferObjects(vec![Argument::Result(1)], Argument::Input(2)),
+            ],
+        };
+        let sequence = MoveSequence::try_from_ptb(&db, &ptb).unwrap();
+
+        // Pure inputs are typed by their consumers and objects by the store
+        let sui = MoveTypeTag::from_str("0x2::coin::Coin<0x2::sui::SUI>").unwrap();
+        assert_eq!(sequence.inputs.len(), 3);
+        let InputArgument::Object(ty, SuiObjectInputArgument::ImmOrOwnedObject(_)) =
+            &sequence.inputs[0]
+        else {
+            panic!("{} is not an owned object", sequence.inputs[0]);
+        };
+        assert_eq!(ty, &sui);
+        assert_eq!(sequence.inputs[1], InputArgument::U64(10));
+        assert_eq!(sequence.inputs[2], InputArgument::Address(recipient));
+
+        // The vector without a type takes the one of the split coins
+        let MoveSequenceCall::MakeMoveVec(ty, elems) = &sequence.commands[1] else {
+            panic!("{} is not a vector", sequence.commands[1]);
+        };
+        assert_eq!(ty, &sui);
+        assert_eq!(
+            elems,
+            &vec![
+                SequenceArgument::NestedResult(0, 0),
+                SequenceArgument::NestedResult(0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_from_ptb_fails_clearly() {
+        let mut db = BTreeMap::new();
+        let coin = gas_coin(&mut db, ObjectID::from_single_byte(0x42));
+        let amount = CallArg::Pure(bcs::to_bytes(&10u64).unwrap());
+
+        // A pure input merged as a coin has no type to decode it with
+        let ptb = ProgrammableTransaction {
+            inputs: vec![coin.clone(), amount.clone()],
+            commands: vec![Command::MergeCoins(
+                Argument::Input(0),
+                vec![Argument::Input(1)],
+            )],
+        };
+        let err = MoveSequence::try_from_ptb(&db, &ptb).unwrap_err();
+        assert!(
+            matches!(&err, MovyError::InvalidSeed(msg) if msg.contains("only consumed by MergeCoins")),
+            "{}",
+            err
+        );
+
+        let ptb = ProgrammableTransaction {
+            inputs: vec![coin.clone(), amount],
+            commands: vec![],
+        };
+        let err = MoveSequence::try_from_ptb(&db, &ptb).unwrap_err();
+        assert!(matches!(&err, MovyError::InvalidSeed(msg) if msg.contains("never consumed")));
+
+        // Nothing is known about the results of a transfer
+        let ptb = ProgrammableTransaction {
+            inputs: vec![coin],
+            commands: vec![
+                Command::TransferObjects(vec![Argument::Input(0)], Argument::GasCoin),
+                Command::MakeMoveVec(None, vec![Argument::Result(0)]),
+            ],
+        };
+        assert!(matches!(
+            MoveSequence::try_from_ptb(&db, &ptb),
+            Err(MovyError::Unsupported(_))
+        ));
+
+        let ptb = ProgrammableTransaction {
+            inputs: vec![gas_coin(
+                &mut BTreeMap::new(),
+                ObjectID::from_single_byte(0x43),
+            )],
+            commands: vec![],
+        };
+        assert!(matches!(
+            MoveSequence::try_from_ptb(&db, &ptb),
+            Err(MovyError::InvalidSeed(_))
+        ));
+    }
+}
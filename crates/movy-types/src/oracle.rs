@@ -1,8 +1,9 @@
 use std::fmt::Display;
 
+use fastcrypto::hash::{HashFunction, Sha256};
 use serde::{Deserialize, Serialize};
 
-use crate::input::MoveStructTag;
+use crate::input::{FunctionIdent, MoveAddress, MoveStructTag};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum Severity {
@@ -14,13 +15,175 @@ pub enum Severity {
     Critical,
 }
 
+/// A 1-based line/column range in a Move source file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceSpan {
+    pub file: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.start_line, self.start_column)
+    }
+}
+
+/// Where a finding is, as precise as the detector knows.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FindingLocation {
+    #[serde(default)]
+    pub package: Option<MoveAddress>,
+    /// The name of `package` in its Move.toml, known for local packages whose address changes
+    /// with every deployment.
+    #[serde(default)]
+    pub package_name: Option<String>,
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub function: Option<String>,
+    /// Bytecode offset in `function`.
+    #[serde(default)]
+    pub offset: Option<u16>,
+    /// Tells apart findings of a rule on different sinks of `function` when there is no
+    /// bytecode offset, e.g. `transfer_recipient:pool::swap#1` for its second transfer.
+    #[serde(default)]
+    pub sink: Option<String>,
+    #[serde(default)]
+    pub source: Option<SourceSpan>,
+}
+
+impl FindingLocation {
+    pub fn module(package: MoveAddress, module: &str) -> Self {
+        Self {
+            package: Some(package),
+            module: Some(module.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn function(function: &FunctionIdent, offset: Option<u16>) -> Self {
+        Self {
+            package: Some(function.0.module_address),
+            package_name: None,
+            module: Some(function.0.module_name.clone()),
+            function: Some(function.1.clone()),
+            offset,
+            sink: None,
+            source: None,
+        }
+    }
+
+    pub fn with_function(mut self, function: &str) -> Self {
+        self.function = Some(function.to_string());
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u16) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_package_name(mut self, name: &str) -> Self {
+        self.package_name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_sink(mut self, sink: impl Into<String>) -> Self {
+        self.sink = Some(sink.into());
+        self
+    }
+}
+
+impl Display for FindingLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(package) = &self.package {
+            parts.push(package.to_string());
+        }
+        parts.extend(self.module.iter().cloned());
+        parts.extend(self.function.iter().cloned());
+        write!(f, "{}", parts.join("::"))?;
+        if let Some(offset) = self.offset {
+            write!(f, "@{}", offset)?;
+        }
+        if let Some(sink) = &self.sink {
+            write!(f, " [{}]", sink)?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, " ({})", source)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct OracleFinding {
     pub oracle: String,
+    /// Stable id of the check, e.g. `bool_judgement`.
+    #[serde(default)]
+    pub rule: String,
     pub severity: Severity,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub location: Option<FindingLocation>,
+    /// Identifies the same issue across runs, derived from the rule and the location.
+    #[serde(default)]
+    pub fingerprint: String,
     pub extra: serde_json::Value,
 }
 
+impl OracleFinding {
+    pub fn new(oracle: &str, rule: &str, severity: Severity, message: impl Into<String>) -> Self {
+        let mut finding = Self {
+            oracle: oracle.to_string(),
+            rule: rule.to_string(),
+            severity,
+            message: message.into(),
+            location: None,
+            fingerprint: String::new(),
+            extra: serde_json::Value::Null,
+        };
+        finding.fingerprint = finding.compute_fingerprint();
+        finding
+    }
+
+    pub fn with_location(mut self, location: FindingLocation) -> Self {
+        self.location = Some(location);
+        self.fingerprint = self.compute_fingerprint();
+        self
+    }
+
+    pub fn with_extra(mut self, extra: serde_json::Value) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// The source span is left out so that a finding keeps its fingerprint whether or not
+    /// it was mapped back to the sources. Packages are identified by their name if known, so
+    /// that findings of a local package match across deployments, or by their address.
+    pub fn compute_fingerprint(&self) -> String {
+        let location = self.location.clone().unwrap_or_default();
+        let package = location
+            .package_name
+            .or_else(|| location.package.map(|p| p.to_string()))
+            .unwrap_or_default();
+        let key = format!(
+            "{}|{}|{}|{}|{}|{}",
+            self.rule,
+            package,
+            location.module.unwrap_or_default(),
+            location.function.unwrap_or_default(),
+            location.offset.map(|o| o.to_string()).unwrap_or_default(),
+            location.sink.unwrap_or_default(),
+        );
+        const_hex::encode(&Sha256::digest(key.as_bytes()).digest[..16])
+    }
+}
+
 impl Display for Severity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let severity_str = match self {
@@ -39,8 +202,16 @@ impl Display for OracleFinding {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "OracleFinding {{ oracle: {}, severity: {}, extra: {} }}",
-            self.oracle, self.severity, self.extra
+            "OracleFinding {{ oracle: {}, rule: {}, severity: {}, message: {}, location: {}, extra: {} }}",
+            self.oracle,
+            self.rule,
+            self.severity,
+            self.message,
+            self.location
+                .as_ref()
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            self.extra
         )
     }
 }
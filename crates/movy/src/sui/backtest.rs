@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::Args;
+use color_eyre::eyre::eyre;
+use movy_fuzz::{
+    meta::{FuzzMetadata, TargetFilters},
+    operations::sui_fuzz,
+    oracles::sui::OracleConfig,
+    utils::{SuperRand, random_seed},
+};
+use movy_replay::{
+    db::{ObjectStoreCachedStore, ObjectStoreInfo, ObjectStoreMintObject},
+    env::SuiTestingEnv,
+};
+use movy_sui::{
+    database::{cache::CachedStore, graphql::GraphQlDatabase},
+    rpc::graphql::GraphQlClient,
+};
+use movy_types::{
+    error::MovyError,
+    input::{MoveAddress, MoveSequence, MoveTypeTag},
+    object::MoveOwner,
+};
+use sui_types::{
+    base_types::ObjectID,
+    digests::TransactionDigest,
+    effects::TransactionEffectsAPI,
+    transaction::{CallArg, Command, TransactionDataAPI, TransactionKind},
+};
+
+use crate::sui::utils::{SuiProtocolArgs, may_save_bytes, may_save_json_value, read_value};
+
+#[derive(Args)]
+pub struct SuiBacktestArgs {
+    #[arg(short, long, help = "The exploit transaction digest")]
+    pub tx: TransactionDigest,
+    #[arg(
+        short,
+        long,
+        help = "deployer to use",
+        default_value = "0xb64151ee0dd0f7bab72df320c5f8e0c4b784958e7411a6c37d352fe9e176092f"
+    )]
+    pub deployer: MoveAddress,
+    #[arg(
+        long,
+        help = "Do not seed the corpus with the exploit, see if the fuzzer finds it from scratch"
+    )]
+    pub no_seed: bool,
+    #[arg(
+        long,
+        help = "Time limit of the fuzzing campaign",
+        default_value_t = 600
+    )]
+    pub time_limit: u64,
+    #[arg(long, help = "rng seeds")]
+    pub seed: Option<u64>,
+    #[arg(short, long, help = "Ouput directory to save all contents")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Force removal of the output directory",
+        env = "MOVY_FORCE_REMOVAL"
+    )]
+    pub force_removal: bool,
+    #[arg(
+        long,
+        help = "Path to an oracle config in JSON, e.g. the expected aborts allowlist and event invariants"
+    )]
+    pub oracle_config: Option<PathBuf>,
+    #[clap(flatten)]
+    pub protocol: SuiProtocolArgs,
+}
+
+impl SuiBacktestArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        if let Some(output) = &self.output {
+            if output.exists() {
+                log::info!("We will remove {}", output.display());
+                if self.force_removal {
+                    std::fs::remove_dir_all(output)?;
+                } else {
+                    return Err(eyre!("The given output is already there, pass -f or env MOVY_FORCE_REMOVAl to always remove it").into());
+                }
+            }
+            std::fs::create_dir_all(output)?;
+        }
+        let seed = if let Some(seed) = self.seed {
+            seed
+        } else {
+            random_seed()
+        };
+        let mut rand = SuperRand::new(seed);
+
+        let graphql = GraphQlClient::new_mystens();
+        let mut txs = graphql
+            .query_transactions(vec![self.tx.to_string()])
+            .await?;
+        let tx = txs.pop().ok_or_else(|| eyre!("tx {} not found", self.tx))?;
+        let TransactionKind::ProgrammableTransaction(ptb) = tx.tx.kind() else {
+            return Err(eyre!("tx {} is not a programmable transaction", self.tx).into());
+        };
+        let attacker: MoveAddress = tx.tx.sender().into();
+        let fork_ckpt = tx.checkpoint - 1;
+        let (_, fork_ckpt_summary) = graphql
+            .query_checkpoint(Some(fork_ckpt))
+            .await?
+            .ok_or_else(|| eyre!("fail to fectch ckpt {}", fork_ckpt))?;
+        let protocol = self
+            .protocol
+            .resolve(Some(&graphql), fork_ckpt_summary.epoch)
+            .await?;
+        log::info!(
+            "Backtesting {} sent by {} from ckpt {}",
+            self.tx,
+            attacker,
+            fork_ckpt
+        );
+
+        let env = CachedStore::new(GraphQlDatabase::new_client(graphql.clone(), fork_ckpt));
+        let gas_id = ObjectID::random_from_rng(&mut rand);
+        env.mint_coin_id(
+            MoveTypeTag::from_str("0x2::sui::SUI").unwrap(),
+            MoveOwner::AddressOwner(attacker),
+            gas_id.into(),
+            100_000_000_000,
+        )?;
+        let testing_env = SuiTestingEnv::new(env);
+        testing_env.mock_testing_std()?;
+
+        // Everything the exploit touched, the packages it called become the fuzz targets
+        let mut target_packages = BTreeSet::new();
+        for cmd in ptb.commands.iter() {
+            if let Command::MoveCall(call) = cmd {
+                let package: MoveAddress = call.package.into();
+                if !package.is_sui_std() {
+                    target_packages.insert(package);
+                }
+            }
+        }
+        let mut objects = tx
+            .effects
+            .modified_at_versions()
+            .into_iter()
+            .map(|(id, _)| MoveAddress::from(id))
+            .collect::<BTreeSet<_>>();
+        for input in ptb.inputs.iter() {
+            if let CallArg::Object(arg) = input {
+                objects.insert(arg.id().into());
+            }
+        }
+        for package in target_packages.iter() {
+            log::info!("Deploying onchain address {} to env...", package);
+            testing_env.deploy_address(*package).await?;
+        }
+        for object in objects.iter() {
+            testing_env.inner().load_object(*object).await?;
+        }
+        log::info!("Loading inner types...");
+        testing_env.load_inner_types().await?;
+
+        let mut abis = BTreeMap::new();
+        for package in target_packages.iter() {
+            let abi = testing_env
+                .inner()
+                .get_package_info(*package)?
+                .ok_or_else(|| eyre!("package {} not found", package))?;
+            abis.insert(*package, abi);
+        }
+
+        let seeds = if self.no_seed {
+            vec![]
+        } else {
+            let exploit = MoveSequence::try_from_ptb(testing_env.inner(), ptb)?;
+            log::info!("Exploit converted to:\n{}", exploit);
+            // Such that the metadata covers every package the exploit calls
+            testing_env.load_sequence(&exploit).await?;
+            may_save_json_value(&self.output, "exploit.json", &exploit)?;
+            vec![exploit]
+        };
+
+        let oracle_config: OracleConfig = if let Some(path) = &self.oracle_config {
+            read_value(path)?
+        } else {
+            OracleConfig::default()
+        };
+        let meta = FuzzMetadata::from_env(
+            &testing_env,
+            rand,
+            vec![],
+            target_packages.into_iter().collect(),
+            attacker,
+            self.deployer,
+            gas_id.into(),
+            abis,
+            Default::default(),
+            fork_ckpt,
+            fork_ckpt_summary.epoch,
+            fork_ckpt_summary.timestamp_ms,
+            protocol,
+            TargetFilters::default(),
+            oracle_config,
+        )
+        .await?;
+
+        may_save_json_value(&self.output, "fuzz_meta.json", &meta)?;
+        may_save_bytes(&self.output, "env.bin", &testing_env.inner().dump().await?)?;
+
+        let output = self.output.clone();
+        let time_limit = self.time_limit;
+        let summary = tokio::task::spawn_blocking(move || {
+            let inner = testing_env.into_inner();
+            let env = SuiTestingEnv::new(Arc::new(inner));
+            sui_fuzz::fuzz(
+                meta,
+                env,
+                &output,
+                Some(time_limit),
+                false,
+                false,
+                false,
+                seeds,
+            )
+        })
+        .await??;
+
+        println!("Backtest of {} forked at ckpt {}", self.tx, fork_ckpt);
+        println!("Executions: {}", summary.executions);
+        if !self.no_seed {
+            println!(
+                "Oracle findings on the exploit itself: {}",
+                summary.seed_solutions
+            );
+        }
+        match summary.first_solution {
+            Some(elapsed) => println!(
+                "Rediscovered: first finding after {:.2}s, {} findings in total",
+                elapsed.as_secs_f64(),
+                summary.solutions
+            ),
+            None => println!("Not rediscovered within {}s", self.time_limit),
+        }
+        Ok(())
+    }
+}
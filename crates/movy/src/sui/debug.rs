@@ -0,0 +1,96 @@
+use std::{path::PathBuf, sync::Arc};
+
+use clap::Args;
+use color_eyre::eyre::eyre;
+use movy_fuzz::{
+    input::MoveFuzzInput, meta::FuzzMetadata, operations::sui_replay::sui_debug_replay_sequence,
+};
+use movy_replay::{env::SuiTestingEnv, tracer::debug::Breakpoint};
+use movy_sui::{
+    database::{
+        cache::{CachedSnapshot, CachedStore},
+        empty::EmptyStore,
+    },
+    rpc::graphql::GraphQlClient,
+};
+use movy_types::{error::MovyError, input::MoveSequence};
+use sui_types::{
+    digests::TransactionDigest,
+    transaction::{TransactionDataAPI, TransactionKind},
+};
+
+use crate::sui::utils::{read_bcs_value, read_value};
+
+#[derive(Args)]
+pub struct SuiDebugArgs {
+    #[arg(short, long, help = "Path to a seed file", conflicts_with = "sequence")]
+    pub seed: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Path to a sequence file, e.g. the exploit.json of `sui backtest`"
+    )]
+    pub sequence: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Digest of a transaction to debug on the snapshot, e.g. the exploit of `sui backtest`",
+        conflicts_with_all = ["seed", "sequence"]
+    )]
+    pub tx: Option<TransactionDigest>,
+    #[arg(short, long, help = "Path to an env file, usually env.bin")]
+    pub env: PathBuf,
+    #[arg(short, long, help = "Path to a fuzz meta, usually fuzz_meta.json")]
+    pub meta: PathBuf,
+    #[arg(
+        short,
+        long = "break",
+        help = "Breakpoints, [package::]module::function, [package::]module::function:pc or abort. Stop at the first instruction if none"
+    )]
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl SuiDebugArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        log::info!("Loading the snapshot {}", self.env.display());
+        let env: CachedSnapshot = read_bcs_value(&self.env)?;
+        log::info!("Loading the fuzz metadata {}", self.meta.display());
+        let meta: FuzzMetadata = read_value(&self.meta)?;
+        // Never reach out to the chain, everything the execution needs is in the snapshot
+        let db = CachedStore::new(EmptyStore);
+        db.restore_snapshot(env);
+
+        let sequence = match (&self.seed, &self.sequence, &self.tx) {
+            (Some(seed), _, _) => {
+                log::info!("Loading the seed {}", seed.display());
+                read_value::<MoveFuzzInput>(seed)?.sequence
+            }
+            (None, Some(sequence), _) => {
+                log::info!("Loading the sequence {}", sequence.display());
+                read_value::<MoveSequence>(sequence)?
+            }
+            (None, None, Some(digest)) => {
+                log::info!("Fetching the transaction {}", digest);
+                let tx = GraphQlClient::new_mystens()
+                    .query_transactions(vec![digest.to_string()])
+                    .await?
+                    .pop()
+                    .ok_or_else(|| eyre!("tx {} not found", digest))?;
+                let TransactionKind::ProgrammableTransaction(ptb) = tx.tx.kind() else {
+                    return Err(eyre!("tx {} is not a programmable transaction", digest).into());
+                };
+                // The objects are resolved on the snapshot, whose owned ones are newer
+                let sequence = MoveSequence::try_from_ptb(&db, ptb)?;
+                meta.check_seed(&db, sequence)?
+            }
+            (None, None, None) => {
+                return Err(eyre!("Either a seed, a sequence or a tx is required").into());
+            }
+        };
+        let env = SuiTestingEnv::new(Arc::new(db));
+        println!("Debugging:\n{}\nType help for the commands", sequence);
+        tokio::task::spawn_blocking(move || {
+            sui_debug_replay_sequence(env, meta, sequence, self.breakpoints)
+        })
+        .await??;
+        Ok(())
+    }
+}
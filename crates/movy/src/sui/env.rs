@@ -9,7 +9,9 @@ use movy_replay::{
     env::SuiTestingEnv,
 };
 use movy_sui::{
-    compile::SuiCompiledPackage, database::cache::ObjectSuiStoreCommit, rpc::graphql::GraphQlClient,
+    compile::{SuiCompiledPackage, SuiPackageSources},
+    database::cache::ObjectSuiStoreCommit,
+    rpc::graphql::GraphQlClient,
 };
 use movy_types::{
     abi::{MoveModuleId, MovePackageAbi},
@@ -63,7 +65,12 @@ impl SuiTargetArgs {
     ) -> Result<
         (
             Vec<MoveAddress>,
-            Vec<(MovePackageAbi, MovePackageAbi, Vec<String>)>,
+            Vec<(
+                MovePackageAbi,
+                MovePackageAbi,
+                Vec<String>,
+                SuiPackageSources,
+            )>,
             BTreeMap<String, MoveAddress>,
         ),
         MovyError,
@@ -96,13 +103,13 @@ impl SuiTargetArgs {
         let mut local_abis = vec![];
         for local in self.locals.iter().flatten() {
             log::info!("Deploying the local package at {}", local.display());
-            let (target_package, testing_abi, abi, package_names) = env
+            let (target_package, testing_abi, abi, package_names, sources) = env
                 .load_local(local, deployer, attacker, epoch, epoch_ms, gas.into())
                 .await?;
             for name in package_names.iter() {
                 local_name_map.insert(name.clone(), target_package);
             }
-            local_abis.push((testing_abi, abi, package_names));
+            local_abis.push((testing_abi, abi, package_names, sources));
             target_packages.push(target_package);
         }
 
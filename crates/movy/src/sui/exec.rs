@@ -0,0 +1,140 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clap::Args;
+use movy_fuzz::oracles::sui::get_balance_changes_from_effect;
+use movy_replay::{
+    db::{ObjectStoreInfo, ObjectStoreMintObject},
+    diff::diff_transaction_objects,
+    exec::{ExecutionTracedResults, SuiExecutor},
+    layout::ObjectLayoutResolver,
+    tracer::tree::TreeTracer,
+};
+use movy_sui::{
+    database::{
+        cache::{CachedStore, ObjectSuiStoreCommit},
+        graphql::GraphQlDatabase,
+    },
+    rpc::graphql::GraphQlClient,
+};
+use movy_types::{
+    error::MovyError,
+    input::{MoveAddress, MoveSequence, MoveTypeTag},
+    object::MoveOwner,
+};
+use sui_types::{base_types::ObjectID, effects::TransactionEffectsAPI};
+
+use crate::sui::utils::{SuiOnchainArguments, SuiSourceArgs, read_value};
+
+#[derive(Args)]
+pub struct SuiExecArgs {
+    #[arg(
+        long,
+        help = "Path to the PTB in JSON, the same format as the seeds, e.g. produced by `sui import-seeds`"
+    )]
+    pub ptb: PathBuf,
+    #[arg(long, help = "The sender of the PTB, who is given a gas coin")]
+    pub sender: MoveAddress,
+    #[arg(long, help = "Print the call trace")]
+    pub trace: bool,
+    #[clap(flatten)]
+    pub sources: SuiSourceArgs,
+    #[clap(flatten)]
+    pub onchain: SuiOnchainArguments,
+}
+
+impl SuiExecArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        let sources = self.sources.load()?;
+        let mut sequence: MoveSequence = read_value(&self.ptb)?;
+        let graphql = GraphQlClient::new_mystens();
+        let primitives = self
+            .onchain
+            .resolve_onchain_primitives(Some(&graphql))
+            .await?;
+        log::info!(
+            "Executing on the fork at ckpt {} with {:?}",
+            primitives.checkpoint,
+            primitives.protocol
+        );
+
+        let db = CachedStore::new(GraphQlDatabase::new_client(
+            graphql.clone(),
+            primitives.checkpoint,
+        ));
+        let gas_id = ObjectID::random();
+        db.mint_coin_id(
+            MoveTypeTag::from_str("0x2::sui::SUI").unwrap(),
+            MoveOwner::AddressOwner(self.sender),
+            gas_id.into(),
+            100_000_000_000,
+        )?;
+        // Seeds keep the references to owned objects at the checkpoint they are recorded
+        db.refresh_object_inputs(&mut sequence)?;
+        let executor = SuiExecutor::new_with_protocol(db, primitives.protocol)?;
+        let tracer = if self.trace {
+            Some(TreeTracer::new())
+        } else {
+            None
+        };
+        let ExecutionTracedResults { results, tracer } = executor.run_ptb_with_gas(
+            sequence.to_ptb()?,
+            primitives.epoch,
+            primitives.epoch_ms,
+            self.sender.into(),
+            gas_id,
+            tracer,
+        )?;
+        let effects = results.effects;
+
+        println!("Executing:\n{}", sequence);
+        println!("The result is {:?}", effects.status());
+        println!("Gas used: {}", effects.gas_cost_summary().net_gas_usage());
+
+        println!("\nChanged Objects:\n");
+        for diff in diff_transaction_objects(&executor.db, &effects, &results.store.written)? {
+            println!("{}", diff);
+        }
+
+        println!("\nEvents:\n");
+        let mut resolver = ObjectLayoutResolver::new(&executor.db);
+        for event in results.store.events.data.iter() {
+            match resolver.decode_event(event)? {
+                Some((ty, fields)) => {
+                    println!("{}: {}", ty.to_canonical_string(true), fields);
+                }
+                None => println!(
+                    "{}: <not decoded, {} bytes>",
+                    event.type_.to_canonical_string(true),
+                    event.contents.len()
+                ),
+            }
+        }
+
+        // Commit to a layer such that both versions of the coins are visible, the minted gas
+        // coin is excluded as it does not exist onchain
+        println!("\nBalance Changes:\n");
+        let committed = CachedStore::new(&executor.db);
+        committed.commit_store(results.store, &effects)?;
+        match get_balance_changes_from_effect(&committed, &effects, vec![], gas_id) {
+            Some(changes) => {
+                for change in changes {
+                    println!(
+                        "{} {} {}",
+                        change.owner,
+                        change.coin_type.to_canonical_string(true),
+                        change.amount
+                    );
+                }
+            }
+            None => println!("<some coins are missing>"),
+        }
+
+        if let Some(tracer) = tracer {
+            println!(
+                "\nTrace:\n{}",
+                self.sources.pprint(&tracer.take_inner(), &sources)
+            );
+        }
+        Ok(())
+    }
+}
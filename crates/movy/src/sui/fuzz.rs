@@ -2,10 +2,11 @@ use std::{collections::BTreeMap, path::PathBuf, str::FromStr, sync::Arc};
 
 use clap::Args;
 use color_eyre::eyre::eyre;
-use log::debug;
+use log::{debug, warn};
 use movy_fuzz::{
     meta::{FuzzMetadata, TargetFilters},
     operations::sui_fuzz,
+    oracles::sui::OracleConfig,
     utils::{SuperRand, random_seed},
 };
 use movy_replay::{
@@ -19,7 +20,7 @@ use movy_sui::{
 use movy_types::{
     abi::MoveModuleId,
     error::MovyError,
-    input::{MoveAddress, MoveTypeTag},
+    input::{MoveAddress, MoveSequence, MoveTypeTag},
     object::MoveOwner,
 };
 use serde::{Deserialize, Serialize};
@@ -27,7 +28,7 @@ use sui_types::base_types::ObjectID;
 
 use crate::sui::{
     env::{FunctionSelector, FuzzTargetArgs, ModuleSelector, PackageSelector, SuiTargetArgs},
-    utils::{SuiOnchainArguments, may_save_bytes, may_save_json_value},
+    utils::{SuiOnchainArguments, may_save_bytes, may_save_json_value, read_value},
 };
 
 fn resolve_modules(
@@ -161,6 +162,16 @@ pub struct SuiFuzzArgs {
         default_value_t = false
     )]
     pub disable_defects_oracle: bool,
+    #[arg(
+        long,
+        help = "Path to an oracle config in JSON, e.g. opting in abort reports with their allowlist and event invariants"
+    )]
+    pub oracle_config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Path to the initial corpus in JSON, e.g. produced by `sui import-seeds`"
+    )]
+    pub seeds: Option<PathBuf>,
 }
 
 impl SuiFuzzArgs {
@@ -220,7 +231,7 @@ impl SuiFuzzArgs {
         let mut abis = BTreeMap::new();
         let mut testing_abis = BTreeMap::new();
 
-        for (testing_abi, abi, names) in local_abis {
+        for (testing_abi, abi, names, _) in local_abis {
             let testing_pkg = testing_abi.package_id;
             abis.insert(abi.package_id, abi);
             testing_abis.insert(testing_pkg, testing_abi);
@@ -259,6 +270,25 @@ impl SuiFuzzArgs {
             exclude_types: resolve_type_tags(&self.filters.exclude_types, &local_name_map)?,
         };
 
+        // The packages and objects of the seeds are loaded ahead such that the metadata
+        // covers them, the ones still unknown are skipped when fuzzing
+        let seeds: Vec<MoveSequence> = if let Some(path) = &self.seeds {
+            read_value(path)?
+        } else {
+            vec![]
+        };
+        for (idx, seed) in seeds.iter().enumerate() {
+            if let Err(e) = testing_env.load_sequence(seed).await {
+                warn!("Can not load seed {}: {}", idx, e);
+            }
+        }
+
+        let oracle_config: OracleConfig = if let Some(path) = &self.oracle_config {
+            read_value(path)?
+        } else {
+            OracleConfig::default()
+        };
+
         let meta = FuzzMetadata::from_env(
             &testing_env,
             rand,
@@ -272,7 +302,9 @@ impl SuiFuzzArgs {
             primitives.checkpoint,
             primitives.epoch,
             primitives.epoch_ms,
+            primitives.protocol,
             filters,
+            oracle_config,
         )
         .await?;
 
@@ -290,6 +322,7 @@ impl SuiFuzzArgs {
                 self.typed_bug_abort,
                 self.disable_profit_oracle,
                 self.disable_defects_oracle,
+                seeds,
             )
         })
         .await??;
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use movy_sui::{
+    database::{cache::CachedStore, graphql::GraphQlDatabase},
+    rpc::graphql::GraphQlClient,
+};
+use movy_types::{error::MovyError, input::MoveSequence};
+use sui_types::transaction::{TransactionDataAPI, TransactionKind};
+
+use crate::sui::utils::SuiOnchainArguments;
+
+#[derive(Args)]
+pub struct SuiImportSeedsArgs {
+    #[arg(
+        short,
+        long,
+        help = "Import transactions calling into this package, package::module or package::module::function"
+    )]
+    pub function: String,
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 100,
+        help = "How many of the latest transactions to import"
+    )]
+    pub count: usize,
+    #[arg(short, long, help = "Write the seeds to this json file")]
+    pub output: PathBuf,
+    #[clap(flatten)]
+    pub onchain: SuiOnchainArguments,
+}
+
+impl SuiImportSeedsArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        let graphql = GraphQlClient::new_mystens();
+        let primitives = self
+            .onchain
+            .resolve_onchain_primitives(Some(&graphql))
+            .await?;
+        let txs = graphql
+            .query_function_transactions(
+                self.function.clone(),
+                Some(primitives.checkpoint + 1),
+                self.count,
+            )
+            .await?;
+        log::info!(
+            "Got {} transactions calling {} until ckpt {}",
+            txs.len(),
+            self.function,
+            primitives.checkpoint
+        );
+
+        // Resolve the inputs against the state to fuzz at, rather than when they were executed
+        let db = CachedStore::new(GraphQlDatabase::new_client(
+            graphql.clone(),
+            primitives.checkpoint,
+        ));
+        let mut seeds = vec![];
+        for tx in txs.iter() {
+            let digest = tx.tx.digest();
+            let TransactionKind::ProgrammableTransaction(ptb) = tx.tx.kind() else {
+                log::debug!("Skip {} as it is not a programmable transaction", digest);
+                continue;
+            };
+            match MoveSequence::try_from_ptb(&db, ptb) {
+                Ok(sequence) => {
+                    log::debug!("Imported {}:\n{}", digest, sequence);
+                    seeds.push(sequence);
+                }
+                Err(e) => log::warn!("Skip {} due to {}", digest, e),
+            }
+        }
+
+        println!(
+            "Imported {} seeds from {} transactions",
+            seeds.len(),
+            txs.len()
+        );
+        let fp = std::fs::File::create(&self.output)?;
+        serde_json::to_writer_pretty(fp, &seeds)?;
+        Ok(())
+    }
+}
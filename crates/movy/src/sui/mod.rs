@@ -2,15 +2,22 @@ use clap::{Args, Subcommand};
 use movy_types::error::MovyError;
 
 use crate::sui::{
-    fuzz::SuiFuzzArgs, replay::SuiReplaySeedArgs, static_analysis::SuiStaticAnalysisArgs,
-    trace::SuiTraceArgs,
+    backtest::SuiBacktestArgs, debug::SuiDebugArgs, exec::SuiExecArgs, fuzz::SuiFuzzArgs,
+    import_seeds::SuiImportSeedsArgs, replay::SuiReplaySeedArgs, replay_range::SuiReplayRangeArgs,
+    static_analysis::SuiStaticAnalysisArgs, trace::SuiTraceArgs, upgrade::SuiUpgradeCheckArgs,
 };
 
+pub mod backtest;
+pub mod debug;
 pub mod env;
+pub mod exec;
 pub mod fuzz;
+pub mod import_seeds;
 pub mod replay;
+pub mod replay_range;
 pub mod static_analysis;
 pub mod trace;
+pub mod upgrade;
 pub mod utils;
 
 #[derive(Subcommand)]
@@ -18,7 +25,13 @@ pub enum SuiSubcommand {
     TraceTx(SuiTraceArgs),
     Fuzz(SuiFuzzArgs),
     ReplaySeed(SuiReplaySeedArgs),
+    ReplayRange(SuiReplayRangeArgs),
+    Backtest(SuiBacktestArgs),
+    ImportSeeds(SuiImportSeedsArgs),
+    Debug(SuiDebugArgs),
+    Exec(SuiExecArgs),
     StaticAnalysis(SuiStaticAnalysisArgs),
+    UpgradeCheck(SuiUpgradeCheckArgs),
 }
 
 #[derive(Args)]
@@ -34,6 +47,12 @@ impl SuiArgs {
             SuiSubcommand::Fuzz(args) => args.run().await?,
             SuiSubcommand::StaticAnalysis(args) => args.run().await?,
             SuiSubcommand::ReplaySeed(args) => args.run().await?,
+            SuiSubcommand::ReplayRange(args) => args.run().await?,
+            SuiSubcommand::Backtest(args) => args.run().await?,
+            SuiSubcommand::ImportSeeds(args) => args.run().await?,
+            SuiSubcommand::Debug(args) => args.run().await?,
+            SuiSubcommand::Exec(args) => args.run().await?,
+            SuiSubcommand::UpgradeCheck(args) => args.run().await?,
         }
         Ok(())
     }
@@ -14,7 +14,7 @@ use movy_sui::database::{
 };
 use movy_types::error::MovyError;
 
-use crate::sui::utils::{read_bcs_value, read_value};
+use crate::sui::utils::{SuiSourceArgs, read_bcs_value, read_value};
 
 #[derive(Args)]
 pub struct SuiReplaySeedArgs {
@@ -34,6 +34,8 @@ pub struct SuiReplaySeedArgs {
         help = "Replay the seed on the top of testing environment, without any fuzzing information"
     )]
     pub trace: bool,
+    #[clap(flatten)]
+    pub sources: SuiSourceArgs,
 }
 
 impl SuiReplaySeedArgs {
@@ -55,7 +57,10 @@ impl SuiReplaySeedArgs {
         if self.fuzz {
             sui_fuzz_replay_seed(env, meta, seed)?;
         } else {
-            sui_plain_replay_seed(env, meta, seed, self.trace)?;
+            let sources = self.sources.load()?;
+            if let Some(trace) = sui_plain_replay_seed(env, meta, seed, self.trace)? {
+                println!("Trace:\n{}", self.sources.pprint(&trace, &sources));
+            }
         }
 
         Ok(())
@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use clap::Args;
+use color_eyre::eyre::eyre;
+use movy_replay::{exec::SuiExecutor, tracer::NopTracer};
+use movy_sui::{
+    database::{
+        cache::{CachedStore, ObjectSuiStoreCommit},
+        graphql::GraphQlDatabase,
+    },
+    rpc::graphql::GraphQlClient,
+};
+use movy_types::error::MovyError;
+use serde_json::json;
+use sui_types::{effects::TransactionEffectsAPI, message_envelope::Message};
+
+use crate::sui::utils::SuiProtocolArgs;
+
+const TXS_PER_QUERY: usize = 50;
+
+#[derive(Args)]
+pub struct SuiReplayRangeArgs {
+    #[arg(long, help = "The first checkpoint to replay")]
+    pub from: u64,
+    #[arg(long, help = "The last checkpoint to replay, inclusive")]
+    pub to: u64,
+    #[arg(long, help = "Stop at the first divergence")]
+    pub fail_fast: bool,
+    #[arg(short, long, help = "Write divergences to this json file")]
+    pub output: Option<PathBuf>,
+    #[clap(flatten)]
+    pub protocol: SuiProtocolArgs,
+}
+
+impl SuiReplayRangeArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        if self.from == 0 || self.from > self.to {
+            return Err(eyre!("invalid checkpoint range {}..={}", self.from, self.to).into());
+        }
+        let graphql = GraphQlClient::new_mystens();
+        let fork_ckpt = self.from - 1;
+        let (_, mut prev_summary) = graphql
+            .query_checkpoint(Some(fork_ckpt))
+            .await?
+            .ok_or_else(|| eyre!("fail to fectch ckpt {}", fork_ckpt))?;
+
+        let graphql_db = GraphQlDatabase::new_client(graphql.clone(), fork_ckpt);
+        let cache_db = CachedStore::new(graphql_db);
+        let mut epoch = prev_summary.epoch;
+        let mut protocol = self.protocol.resolve(Some(&graphql), epoch).await?;
+        let mut executor = SuiExecutor::new_with_protocol(cache_db, protocol)?;
+
+        let mut total = 0;
+        let mut divergences = vec![];
+        'ckpts: for ckpt in self.from..=self.to {
+            let (contents, summary) = graphql
+                .query_checkpoint(Some(ckpt))
+                .await?
+                .ok_or_else(|| eyre!("fail to fectch ckpt {}", ckpt))?;
+            let digests = contents.iter().copied().collect::<Vec<_>>();
+            // The range may cross epochs, each of them possibly with a new protocol version
+            if summary.epoch != epoch {
+                epoch = summary.epoch;
+                let next = self.protocol.resolve(Some(&graphql), epoch).await?;
+                if next != protocol {
+                    log::info!(
+                        "Epoch {} from ckpt {} runs protocol {}",
+                        epoch,
+                        ckpt,
+                        next.version
+                    );
+                    executor.set_protocol(next)?;
+                    protocol = next;
+                }
+            }
+
+            let mut txs = BTreeMap::new();
+            for chunk in digests.chunks(TXS_PER_QUERY) {
+                let resp = graphql
+                    .query_transactions(chunk.iter().map(|d| d.transaction.to_string()).collect())
+                    .await?;
+                for tx in resp {
+                    txs.insert(tx.tx.digest(), tx);
+                }
+            }
+            log::info!("Replaying {} transactions of ckpt {}", digests.len(), ckpt);
+
+            for expected in digests {
+                total += 1;
+                let tx = txs
+                    .remove(&expected.transaction)
+                    .ok_or_else(|| eyre!("tx {} not found", expected.transaction))?;
+                let onchain_status = format!("{:?}", tx.effects.status());
+                let divergence = match executor.run_tx_trace::<NopTracer>(
+                    tx.tx,
+                    epoch,
+                    prev_summary.timestamp_ms,
+                    None,
+                ) {
+                    Ok(results) => {
+                        let results = results.results;
+                        let local = results.effects.digest();
+                        let divergence = (local != expected.effects).then(|| {
+                            json!({
+                                "checkpoint": ckpt,
+                                "tx": expected.transaction.to_string(),
+                                "onchain_effects": expected.effects.to_string(),
+                                "local_effects": local.to_string(),
+                                "onchain_status": onchain_status,
+                                "local_status": format!("{:?}", results.effects.status()),
+                                "onchain_gas": tx.effects.gas_cost_summary(),
+                                "local_gas": results.effects.gas_cost_summary(),
+                            })
+                        });
+                        // Keep going on the local state, the next transactions see our
+                        // own writes even after a divergence
+                        executor.db.commit_store(results.store, &results.effects)?;
+                        divergence
+                    }
+                    Err(e) => Some(json!({
+                        "checkpoint": ckpt,
+                        "tx": expected.transaction.to_string(),
+                        "onchain_effects": expected.effects.to_string(),
+                        "onchain_status": onchain_status,
+                        "error": e.to_string(),
+                    })),
+                };
+                if let Some(divergence) = divergence {
+                    println!("Divergence: {}", divergence);
+                    divergences.push(divergence);
+                    if self.fail_fast {
+                        break 'ckpts;
+                    }
+                }
+            }
+            prev_summary = summary;
+        }
+
+        println!(
+            "Replayed {} transactions of ckpts {}..={}, {} diverged",
+            total,
+            self.from,
+            self.to,
+            divergences.len()
+        );
+        if let Some(output) = self.output {
+            let fp = std::fs::File::create(&output)?;
+            serde_json::to_writer_pretty(fp, &divergences)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,11 +1,21 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use color_eyre::eyre::eyre;
+use log::{info, warn};
+use move_binary_format::CompiledModule;
 use movy_fuzz::utils::{SuperRand, random_seed};
 use movy_replay::{db::ObjectStoreMintObject, env::SuiTestingEnv};
-use movy_static_analysis::sui as static_sui;
+use movy_static_analysis::{
+    sarif, sui as static_sui,
+    suppress::{Baseline, Suppressions},
+};
 use movy_sui::{
+    compile::{SuiCompiledPackage, SuiPackageSources},
     database::{cache::CachedStore, graphql::GraphQlDatabase},
     rpc::{graphql::GraphQlClient, grpc::SuiGrpcArg},
 };
@@ -13,11 +23,18 @@ use movy_types::{
     error::MovyError,
     input::{MoveAddress, MoveTypeTag},
     object::MoveOwner,
+    oracle::OracleFinding,
 };
 use sui_types::base_types::ObjectID;
 
 use crate::sui::env::SuiTargetArgs;
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaticAnalysisFormat {
+    Json,
+    Sarif,
+}
+
 #[derive(Args)]
 pub struct SuiStaticAnalysisArgs {
     #[arg(
@@ -38,12 +55,113 @@ pub struct SuiStaticAnalysisArgs {
     pub checkpoint: Option<u64>,
     #[arg(short, long, help = "write findings to this folder")]
     pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = StaticAnalysisFormat::Json,
+        help = "output format, sarif maps findings of --locals back to their sources"
+    )]
+    pub format: StaticAnalysisFormat,
+    #[arg(long, help = "baseline file, findings recorded in it are not reported")]
+    pub baseline: Option<PathBuf>,
+    #[arg(
+        long,
+        requires = "baseline",
+        help = "record the current findings into the baseline file instead of reporting them"
+    )]
+    pub update_baseline: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "folders of compiled .mv modules to analyze, modules in subfolders are dependencies"
+    )]
+    pub bytecode: Option<Vec<PathBuf>>,
     #[clap(flatten)]
     pub target: SuiTargetArgs,
 }
 
 impl SuiStaticAnalysisArgs {
-    pub async fn run(self) -> Result<(), MovyError> {
+    /// Without any on-chain target, the local packages can be analyzed from their build
+    /// alone, skipping forking and deploying.
+    fn is_offline(&self) -> bool {
+        self.target.onchains.is_none()
+            && self.target.histories.is_none()
+            && self.target.objects.is_none()
+            && (self.target.locals.is_some() || self.bytecode.is_some())
+    }
+
+    /// The findings, and the sources of the `--locals` keyed by the addresses they are
+    /// analyzed at, in order.
+    fn run_offline(
+        &self,
+    ) -> Result<(Vec<OracleFinding>, Vec<(MoveAddress, SuiPackageSources)>), MovyError> {
+        // Unpublished packages are all at 0x0, so their modules are told apart by name only
+        let mut targets: BTreeMap<(MoveAddress, String), (CompiledModule, PathBuf)> =
+            BTreeMap::new();
+        let mut add_target = |module: CompiledModule, origin: &Path| -> Result<(), MovyError> {
+            let key = (
+                MoveAddress::from(*module.address()),
+                module.self_id().name().to_string(),
+            );
+            match targets.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert((module, origin.to_path_buf()));
+                }
+                // A local package bundled with another as an unpublished dependency
+                Entry::Occupied(entry) if entry.get().0 == module => {}
+                Entry::Occupied(entry) => {
+                    return Err(eyre!(
+                        "module {}::{} is defined differently in {} and {}, analyze them separately",
+                        entry.key().0,
+                        entry.key().1,
+                        entry.get().1.display(),
+                        origin.display()
+                    )
+                    .into());
+                }
+            }
+            Ok(())
+        };
+        let mut dependencies = vec![];
+        let mut local_packages = vec![];
+        for local in self.target.locals.iter().flatten() {
+            let (package, deps, sources) =
+                SuiCompiledPackage::build_all_unpublished_offline_from_folder(local)?;
+            local_packages.push((package.package_id.into(), sources));
+            for module in package.all_modules_iter() {
+                add_target(module.clone(), local)?;
+            }
+            dependencies.extend(deps);
+        }
+        for folder in self.bytecode.iter().flatten() {
+            for path in glob::glob(&format!("{}/*.mv", folder.display()))? {
+                let path = path?;
+                add_target(read_compiled_module(&path)?, &path)?;
+            }
+            for path in glob::glob(&format!("{}/*/**/*.mv", folder.display()))? {
+                dependencies.push(read_compiled_module(&path?)?);
+            }
+        }
+        let targets = targets
+            .into_values()
+            .map(|(module, _)| module)
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return Err(eyre!("no modules found to analyze").into());
+        }
+
+        Ok((
+            static_sui::run_local(&targets, &dependencies)?,
+            local_packages,
+        ))
+    }
+
+    async fn run_forked(
+        &self,
+    ) -> Result<(Vec<OracleFinding>, Vec<(MoveAddress, SuiPackageSources)>), MovyError> {
+        if self.bytecode.is_some() {
+            return Err(eyre!("--bytecode can not be combined with on-chain targets").into());
+        }
         let mut rand = SuperRand::new(random_seed());
         let graphql = GraphQlClient::new_mystens();
         let _rpc = self.rpc.grpc().await?;
@@ -66,7 +184,7 @@ impl SuiStaticAnalysisArgs {
         )?;
         let testing_env = SuiTestingEnv::new(env);
         testing_env.mock_testing_std()?;
-        let (target_packages, _, _) = self
+        let (target_packages, local_abis, _) = self
             .target
             .build_env(
                 &testing_env,
@@ -80,7 +198,110 @@ impl SuiStaticAnalysisArgs {
             )
             .await?;
 
-        let reports = static_sui::run_all(&testing_env, &target_packages).await?;
+        // The locals are deployed after all the onchain packages, with the sources from
+        // the build done to deploy them
+        let onchains = self.target.onchains.iter().flatten().count();
+        let local_packages = target_packages[onchains..]
+            .iter()
+            .copied()
+            .zip(local_abis.into_iter().map(|(_, _, _, sources)| sources))
+            .collect();
+        Ok((
+            static_sui::run_all(&testing_env, &target_packages).await?,
+            local_packages,
+        ))
+    }
+
+    pub async fn run(self) -> Result<(), MovyError> {
+        // Keyed by package as dependencies may have modules named as the local ones
+        let (mut reports, sources) = if self.is_offline() {
+            self.run_offline()?
+        } else {
+            self.run_forked().await?
+        };
+
+        let mut suppressions = Suppressions::default();
+        for (package, package_sources) in sources.iter() {
+            for (module, path) in package_sources.source_files() {
+                suppressions.load_file(*package, module, path)?;
+            }
+        }
+        for finding in reports.iter_mut() {
+            let Some(location) = finding.location.as_mut() else {
+                continue;
+            };
+            let Some(module) = location.module.clone() else {
+                continue;
+            };
+            let Some((_, package_sources)) = sources.iter().find(|(package, s)| {
+                location.package == Some(*package) && s.contains_module(&module)
+            }) else {
+                continue;
+            };
+            // Local packages are named to keep their fingerprints across deployments
+            location.package_name = Some(package_sources.name().to_string());
+            if let Some(function) = &location.function {
+                location.source = package_sources.locate(&module, function, location.offset);
+            }
+            finding.fingerprint = finding.compute_fingerprint();
+        }
+
+        let total = reports.len();
+        reports.retain(|f| !suppressions.is_suppressed(f));
+        if total != reports.len() {
+            info!(
+                "{} findings suppressed by source annotations",
+                total - reports.len()
+            );
+        }
+        if let Some(baseline_path) = &self.baseline {
+            if self.update_baseline {
+                Baseline::from_findings(&reports).save(baseline_path)?;
+                println!(
+                    "Recorded {} findings into baseline {}",
+                    reports.len(),
+                    baseline_path.display()
+                );
+                return Ok(());
+            }
+            if baseline_path.exists() {
+                let baseline = Baseline::load(baseline_path)?;
+                let total = reports.len();
+                reports.retain(|f| !baseline.contains(f));
+                info!(
+                    "{} findings already in baseline {}",
+                    total - reports.len(),
+                    baseline_path.display()
+                );
+            } else {
+                warn!(
+                    "Baseline {} does not exist, run with --update-baseline to create it",
+                    baseline_path.display()
+                );
+            }
+        }
+
+        if self.format == StaticAnalysisFormat::Sarif {
+            let roots = sources
+                .iter()
+                .map(|(_, s)| (s.name().to_string(), s.root().to_path_buf()))
+                .collect();
+            let log = sarif::to_sarif(&reports, &roots);
+            if let Some(output) = self.output {
+                std::fs::create_dir_all(&output)?;
+                let sarif_path = output.join("static_analysis.sarif");
+                let fp = std::fs::File::create(&sarif_path)?;
+                serde_json::to_writer_pretty(fp, &log)?;
+                println!(
+                    "Static analysis finished with {} findings -> {}",
+                    reports.len(),
+                    sarif_path.display()
+                );
+            } else {
+                println!("{}", serde_json::to_string_pretty(&log)?);
+            }
+            return Ok(());
+        }
 
         if let Some(output) = self.output {
             std::fs::create_dir_all(&output)?;
@@ -103,3 +324,9 @@ impl SuiStaticAnalysisArgs {
         Ok(())
     }
 }
+
+fn read_compiled_module(path: &Path) -> Result<CompiledModule, MovyError> {
+    let bytes = std::fs::read(path)?;
+    CompiledModule::deserialize_with_defaults(&bytes)
+        .map_err(|e| eyre!("failed to deserialize {}: {}", path.display(), e).into())
+}
@@ -1,12 +1,32 @@
-use clap::Args;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
 use color_eyre::eyre::eyre;
-use movy_replay::{exec::SuiExecutor, tracer::tree::TreeTracer};
+use itertools::Itertools;
+use movy_replay::{
+    diff::{ObjectDiff, diff_transaction_objects},
+    exec::{ExecutionTracedResults, SuiExecutor},
+    tracer::tree::{TraceWeight, TreeTracer},
+};
 use movy_sui::{
+    compile::{SuiCompiledPackage, SuiPackageSources},
     database::{cache::CachedStore, graphql::GraphQlDatabase},
     rpc::graphql::GraphQlClient,
 };
-use movy_types::error::MovyError;
-use sui_types::{digests::TransactionDigest, effects::TransactionEffectsAPI};
+use movy_types::{error::MovyError, input::MoveAddress};
+use sui_types::{digests::TransactionDigest, effects::TransactionEffectsAPI, storage::ObjectStore};
+
+use crate::sui::utils::{PackagePath, SuiProtocolArgs, SuiSourceArgs};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    Tree,
+    Flamegraph,
+    Chrome,
+}
 
 #[derive(Args)]
 pub struct SuiTraceArgs {
@@ -16,10 +36,268 @@ pub struct SuiTraceArgs {
     pub sequence: bool,
     #[arg(long)]
     pub trace: bool,
+    #[arg(
+        long,
+        help = "Replay again with the package at the address replaced by a local build, <pkg_addr>=<path>"
+    )]
+    pub patch: Vec<PackagePath>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TraceFormat::Tree,
+        help = "Trace format, flamegraph gives folded stacks and chrome gives Chrome Trace Event JSON, both load in speedscope or perfetto"
+    )]
+    pub format: TraceFormat,
+    #[arg(
+        long,
+        default_value = "gas",
+        help = "Weight frames of the flamegraph and chrome formats by instructions or gas"
+    )]
+    pub weight: TraceWeight,
+    #[arg(
+        short,
+        long,
+        help = "Write the flamegraph or chrome trace to this file instead of stdout, the patched one is prefixed with patched."
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Write the decoded object changes to this json file, the patched one is prefixed with patched."
+    )]
+    pub diff_json: Option<PathBuf>,
+    #[clap(flatten)]
+    pub sources: SuiSourceArgs,
+    #[clap(flatten)]
+    pub protocol: SuiProtocolArgs,
+}
+
+// Widest left column of a side by side comparison, longer lines push the right one out
+const MAX_COLUMN_WIDTH: usize = 100;
+// Above this many line pairs, traces are compared line by line instead of aligned
+const MAX_ALIGN_CELLS: usize = 4_000_000;
+
+type Row = (Option<String>, Option<String>);
+
+/// Pair the lines of two texts, matching the longest common subsequence and putting what
+/// differs in between side by side.
+fn align_lines(left: &[String], right: &[String]) -> Vec<Row> {
+    if left.len().saturating_mul(right.len()) > MAX_ALIGN_CELLS {
+        return left
+            .iter()
+            .cloned()
+            .map(Some)
+            .zip_longest(right.iter().cloned().map(Some))
+            .map(|pair| pair.or(None, None))
+            .collect();
+    }
+    // common[i][j] is the length of the common subsequence of left[i..] and right[j..]
+    let mut common = vec![vec![0u32; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            common[i][j] = if left[i] == right[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut rows = vec![];
+    let (mut removed, mut added) = (vec![], vec![]);
+    let flush = |rows: &mut Vec<Row>, removed: &mut Vec<String>, added: &mut Vec<String>| {
+        rows.extend(
+            removed
+                .drain(..)
+                .map(Some)
+                .zip_longest(added.drain(..).map(Some))
+                .map(|pair| pair.or(None, None)),
+        );
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            flush(&mut rows, &mut removed, &mut added);
+            rows.push((Some(left[i].clone()), Some(right[j].clone())));
+            (i, j) = (i + 1, j + 1);
+        } else if j == right.len() || (i < left.len() && common[i + 1][j] >= common[i][j + 1]) {
+            removed.push(left[i].clone());
+            i += 1;
+        } else {
+            added.push(right[j].clone());
+            j += 1;
+        }
+    }
+    flush(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+/// Print rows in two columns, marked like `sdiff`: `|` for lines that differ, `<` and `>`
+/// for lines on one side only.
+fn print_columns(rows: &[Row]) {
+    let width = rows
+        .iter()
+        .filter_map(|(left, _)| left.as_ref().map(|l| l.chars().count()))
+        .max()
+        .unwrap_or_default()
+        .min(MAX_COLUMN_WIDTH);
+    for (left, right) in rows {
+        let marker = match (left, right) {
+            (Some(l), Some(r)) if l == r => ' ',
+            (Some(_), Some(_)) => '|',
+            (Some(_), None) => '<',
+            _ => '>',
+        };
+        println!(
+            "{:<width$} {} {}",
+            left.as_deref().unwrap_or_default(),
+            marker,
+            right.as_deref().unwrap_or_default(),
+            width = width
+        );
+    }
+}
+
+fn patched_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("patched.{}", name))
 }
 
 impl SuiTraceArgs {
+    fn output_path(&self, path: &Option<PathBuf>, patched: bool) -> Option<PathBuf> {
+        path.as_ref().map(|path| {
+            if patched {
+                patched_path(path)
+            } else {
+                path.clone()
+            }
+        })
+    }
+
+    fn export_trace(&self, tracer: &TreeTracer, output: Option<&Path>) -> Result<(), MovyError> {
+        let exported = match self.format {
+            TraceFormat::Chrome => {
+                serde_json::to_string_pretty(&tracer.inner.to_chrome_trace(self.weight))?
+            }
+            _ => tracer.inner.to_folded_stacks(self.weight),
+        };
+        if let Some(output) = output {
+            std::fs::write(output, exported)?;
+            println!("The trace is written to {}", output.display());
+        } else {
+            println!("{}", exported);
+        }
+        Ok(())
+    }
+
+    fn print_results<T: ObjectStore>(
+        &self,
+        results: &ExecutionTracedResults<&mut TreeTracer>,
+        db: &T,
+        sources: &BTreeMap<MoveAddress, SuiPackageSources>,
+        patched: bool,
+    ) -> Result<(), MovyError> {
+        println!("The result is {:?}", results.effects.status());
+        if self.format != TraceFormat::Tree {
+            if let Some(tracer) = &results.tracer {
+                self.export_trace(tracer, self.output_path(&self.output, patched).as_deref())?;
+            }
+        } else if self.trace {
+            if let Some(tracer) = &results.tracer {
+                println!(
+                    "The trace is:\n{}",
+                    self.sources.pprint(&tracer.inner, sources)
+                );
+            }
+        } else {
+            let diffs = self.object_diffs(results, db, patched)?;
+            println!("Changed Objects:\n");
+            for diff in diffs.iter() {
+                println!("{}", diff);
+            }
+        }
+        Ok(())
+    }
+
+    /// The decoded object changes of a run, also written to the `--diff-json` file.
+    fn object_diffs<T: ObjectStore>(
+        &self,
+        results: &ExecutionTracedResults<&mut TreeTracer>,
+        db: &T,
+        patched: bool,
+    ) -> Result<Vec<ObjectDiff>, MovyError> {
+        // The db is never committed, so it still holds the objects before the tx
+        let diffs = diff_transaction_objects(db, &results.effects, &results.store.written)?;
+        if let Some(path) = self.output_path(&self.diff_json, patched) {
+            let fp = std::fs::File::create(&path)?;
+            serde_json::to_writer_pretty(fp, &diffs)?;
+            println!("The object changes are written to {}", path.display());
+        }
+        Ok(diffs)
+    }
+
+    /// Print the original and patched runs side by side: the status, then either the traces
+    /// or the changes of every object touched by one of them.
+    fn print_comparison<T: ObjectStore, P: ObjectStore>(
+        &self,
+        (original, db): (&ExecutionTracedResults<&mut TreeTracer>, &T),
+        (patched, patched_db): (&ExecutionTracedResults<&mut TreeTracer>, &P),
+        sources: &BTreeMap<MoveAddress, SuiPackageSources>,
+    ) -> Result<(), MovyError> {
+        print_columns(&[
+            (Some("Original".to_string()), Some("Patched".to_string())),
+            (
+                Some(format!("{:?}", original.effects.status())),
+                Some(format!("{:?}", patched.effects.status())),
+            ),
+        ]);
+        println!();
+        if self.format != TraceFormat::Tree {
+            for (results, is_patched) in [(original, false), (patched, true)] {
+                if let Some(tracer) = &results.tracer {
+                    self.export_trace(
+                        tracer,
+                        self.output_path(&self.output, is_patched).as_deref(),
+                    )?;
+                }
+            }
+        } else if self.trace {
+            let lines = |results: &ExecutionTracedResults<&mut TreeTracer>| {
+                results
+                    .tracer
+                    .as_ref()
+                    .map(|tracer| self.sources.pprint(&tracer.inner, sources))
+                    .unwrap_or_default()
+                    .lines()
+                    .map(str::to_string)
+                    .collect_vec()
+            };
+            print_columns(&align_lines(&lines(original), &lines(patched)));
+        } else {
+            let by_id = |diffs: Vec<ObjectDiff>| {
+                diffs
+                    .into_iter()
+                    .map(|diff| (diff.id, diff.to_string()))
+                    .collect::<BTreeMap<_, _>>()
+            };
+            let before = by_id(self.object_diffs(original, db, false)?);
+            let after = by_id(self.object_diffs(patched, patched_db, true)?);
+            let lines = |diff: Option<&String>| {
+                diff.map(|d| d.lines().map(str::to_string).collect_vec())
+                    .unwrap_or_else(|| vec!["-".to_string()])
+            };
+            for id in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+                print_columns(&align_lines(&lines(before.get(id)), &lines(after.get(id))));
+                println!();
+            }
+        }
+        Ok(())
+    }
+
     pub async fn run(self) -> Result<(), MovyError> {
+        let sources = self.sources.load()?;
         let graphql = GraphQlClient::new_mystens();
         let mut txs = graphql
             .query_transactions(vec![self.tx.to_string()])
@@ -34,28 +312,49 @@ impl SuiTraceArgs {
 
         let graphql_db = GraphQlDatabase::new_client(graphql.clone(), fork_ckpt);
         let cache_db = CachedStore::new(graphql_db);
-        let executor = SuiExecutor::new(cache_db)?;
+        let protocol = self
+            .protocol
+            .resolve(Some(&graphql), fork_tx_ckpt_summary.epoch)
+            .await?;
+        let executor = SuiExecutor::new_with_protocol(cache_db, protocol)?;
 
         let mut tracer = TreeTracer::new();
         let results = executor.run_tx_trace(
-            tx.tx,
+            tx.tx.clone(),
             fork_tx_ckpt_summary.epoch,
             fork_tx_ckpt_summary.timestamp_ms,
             Some(&mut tracer),
         )?;
 
-        println!("The result is {:?}", results.effects.status());
-        if self.trace {
-            let trace = tracer.take_inner().pprint();
-            println!("The trace is:\n{}", trace);
-        } else {
-            let effects = results.results.effects;
+        if self.patch.is_empty() {
+            self.print_results(&results, &executor.db, &sources, false)?;
+            return Ok(());
+        }
 
-            println!("Changed Objects:\n");
-            for (obj, _, kind) in effects.all_changed_objects() {
-                println!("{}: {:?}", obj.0, &kind);
-            }
+        // Replay on a fresh store seeded with everything fetched so far, such that the
+        // original run is not affected by the patched packages
+        let patched_db = CachedStore::new(GraphQlDatabase::new_client(graphql.clone(), fork_ckpt));
+        patched_db.restore_snapshot(executor.db.dump_snapshot());
+        let patched_executor = SuiExecutor::new_with_protocol(patched_db, protocol)?;
+        for patch in self.patch.iter() {
+            log::info!("Patching {} with {}", patch.package, patch.path.display());
+            let package =
+                SuiCompiledPackage::build_all_unpublished_from_folder(&patch.path, false)?;
+            patched_executor.patch_package(patch.package.into(), package)?;
         }
+        let mut patched_tracer = TreeTracer::new();
+        let patched_results = patched_executor.run_tx_trace(
+            tx.tx,
+            fork_tx_ckpt_summary.epoch,
+            fork_tx_ckpt_summary.timestamp_ms,
+            Some(&mut patched_tracer),
+        )?;
+
+        self.print_comparison(
+            (&results, &executor.db),
+            (&patched_results, &patched_executor.db),
+            &sources,
+        )?;
 
         Ok(())
     }
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::eyre::eyre;
+use movy_static_analysis::sui as static_sui;
+use movy_sui::{
+    compile::SuiCompiledPackage, database::graphql::GraphQlDatabase, rpc::graphql::GraphQlClient,
+};
+use movy_types::{error::MovyError, input::MoveAddress};
+use sui_types::{base_types::ObjectID, storage::ObjectStore};
+
+#[derive(Args)]
+pub struct SuiUpgradeCheckArgs {
+    #[arg(long, help = "address of the currently published package")]
+    pub old: MoveAddress,
+    #[arg(long, help = "local folder of the package to upgrade to")]
+    pub new: PathBuf,
+    #[arg(short, long, help = "checkpoint to read the old package at")]
+    pub checkpoint: Option<u64>,
+    #[arg(short, long, help = "write findings to this folder")]
+    pub output: Option<PathBuf>,
+}
+
+impl SuiUpgradeCheckArgs {
+    pub async fn run(self) -> Result<(), MovyError> {
+        let graphql = GraphQlClient::new_mystens();
+        let (_ckpt_contents, ckpt_summary) = graphql
+            .query_checkpoint(self.checkpoint)
+            .await?
+            .ok_or_else(|| eyre!("no ckpt {:?} from grahql", self.checkpoint))?;
+        let db = GraphQlDatabase::new_client(graphql.clone(), ckpt_summary.sequence_number);
+
+        let object = db
+            .get_object(&ObjectID::from(self.old))
+            .ok_or_else(|| eyre!("package {} not found", self.old))?;
+        let old = object
+            .data
+            .try_as_package()
+            .ok_or_else(|| eyre!("{} is not a package", self.old))?;
+        let new = SuiCompiledPackage::build_all_unpublished_from_folder(&self.new, false)?;
+        let new_modules = new.all_modules_iter().cloned().collect::<Vec<_>>();
+
+        let reports = static_sui::check_upgrade(old, &new_modules)?;
+
+        if let Some(output) = self.output {
+            std::fs::create_dir_all(&output)?;
+            let report_path = output.join("upgrade_check.json");
+            let fp = std::fs::File::create(&report_path)?;
+            serde_json::to_writer_pretty(fp, &reports)?;
+            println!(
+                "Upgrade check finished with {} findings -> {}",
+                reports.len(),
+                report_path.display()
+            );
+        } else if reports.is_empty() {
+            println!("No findings from upgrade check.");
+        } else {
+            for finding in &reports {
+                println!("{finding}");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,18 @@
 use std::{
+    collections::BTreeMap,
     io::Write,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use clap::Args;
 use color_eyre::eyre::eyre;
-use movy_sui::rpc::graphql::GraphQlClient;
-use movy_types::error::MovyError;
+use movy_replay::{
+    exec::{SuiChain, SuiProtocol},
+    tracer::tree::TreeTraceResult,
+};
+use movy_sui::{compile::SuiPackageSources, rpc::graphql::GraphQlClient};
+use movy_types::{error::MovyError, input::MoveAddress};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 pub fn read_value<T: DeserializeOwned>(path: &Path) -> Result<T, MovyError> {
@@ -43,6 +49,72 @@ pub fn may_save_json_value<V: Serialize>(
     Ok(())
 }
 
+#[derive(Debug, Clone)]
+pub struct PackagePath {
+    pub package: MoveAddress,
+    pub path: PathBuf,
+}
+
+impl FromStr for PackagePath {
+    type Err = MovyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (package, path) = s
+            .split_once('=')
+            .ok_or_else(|| eyre!("can not parse {}, expect <pkg_addr>=<path>", s))?;
+        let package = MoveAddress::from_str(package)
+            .map_err(|e| eyre!("can not parse package {} with {}", package, e))?;
+        Ok(Self {
+            package,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct SuiSourceArgs {
+    #[arg(
+        long,
+        help = "Annotate the trace with the sources of the package at the address, <pkg_addr>=<path>"
+    )]
+    pub source: Vec<PackagePath>,
+    #[arg(
+        long,
+        help = "Also print the executed instructions, source lines and locals of the packages with sources"
+    )]
+    pub instructions: bool,
+}
+
+impl SuiSourceArgs {
+    pub fn load(&self) -> Result<BTreeMap<MoveAddress, SuiPackageSources>, MovyError> {
+        let mut sources = BTreeMap::new();
+        for source in self.source.iter() {
+            log::info!(
+                "Loading sources of {} from {}",
+                source.package,
+                source.path.display()
+            );
+            sources.insert(
+                source.package,
+                SuiPackageSources::from_folder(&source.path)?,
+            );
+        }
+        Ok(sources)
+    }
+
+    pub fn pprint(
+        &self,
+        trace: &TreeTraceResult,
+        sources: &BTreeMap<MoveAddress, SuiPackageSources>,
+    ) -> String {
+        if sources.is_empty() {
+            trace.pprint()
+        } else {
+            trace.pprint_with_sources(sources, self.instructions)
+        }
+    }
+}
+
 #[derive(Args, Clone, Debug, Serialize, Deserialize)]
 pub struct SuiOnchainArguments {
     #[arg(
@@ -54,6 +126,9 @@ pub struct SuiOnchainArguments {
     pub epoch: Option<u64>,
     #[arg(long, help = "The timestamp of the epoch")]
     pub epoch_ms: Option<u64>,
+    #[clap(flatten)]
+    #[serde(default)]
+    pub protocol: SuiProtocolArgs,
 }
 
 #[derive(Copy, Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +136,63 @@ pub struct SuiOnchainPrimitives {
     pub epoch: u64,
     pub epoch_ms: u64,
     pub checkpoint: u64,
+    pub protocol: SuiProtocol,
+}
+
+#[derive(Args, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SuiProtocolArgs {
+    #[arg(
+        long,
+        help = "Protocol version to execute with, if not specified, use the one of the forked epoch"
+    )]
+    pub protocol_version: Option<u64>,
+    #[arg(
+        long,
+        help = "Chain of the protocol config, mainnet, testnet or devnet, if not specified, infer it from the forked network"
+    )]
+    pub chain: Option<SuiChain>,
+}
+
+impl SuiProtocolArgs {
+    pub async fn resolve(
+        &self,
+        gql: Option<&GraphQlClient>,
+        epoch: u64,
+    ) -> Result<SuiProtocol, MovyError> {
+        let version = if let Some(version) = self.protocol_version {
+            version
+        } else {
+            let gql = gql.ok_or_else(|| {
+                eyre!(
+                    "no rpc given to infer the protocol version of epoch {}",
+                    epoch
+                )
+            })?;
+            gql.query_epoches(vec![epoch])
+                .await?
+                .pop()
+                .ok_or_else(|| eyre!("epoch {} not present", epoch))?
+                .protocol_version
+        };
+        Ok(SuiProtocol {
+            version,
+            chain: self.resolve_chain(gql).await?,
+        })
+    }
+
+    pub async fn resolve_chain(&self, gql: Option<&GraphQlClient>) -> Result<SuiChain, MovyError> {
+        if let Some(chain) = self.chain {
+            return Ok(chain);
+        }
+        let Some(gql) = gql else {
+            log::debug!("No rpc given to infer the chain, assume mainnet");
+            return Ok(SuiChain::Mainnet);
+        };
+        let identifier = gql.query_chain_identifier().await?;
+        let chain = SuiChain::from_chain_identifier(&identifier);
+        log::info!("Network {} is inferred as {:?}", identifier, chain);
+        Ok(chain)
+    }
 }
 
 impl SuiOnchainArguments {
@@ -73,6 +205,7 @@ impl SuiOnchainArguments {
                 checkpoint: self.checkpoint.unwrap(),
                 epoch: self.epoch.unwrap(),
                 epoch_ms: self.epoch_ms.unwrap(),
+                protocol: self.protocol.resolve(gql, self.epoch.unwrap()).await?,
             });
         }
 
@@ -100,6 +233,13 @@ impl SuiOnchainArguments {
             checkpoint: summary.sequence_number,
             epoch: summary.epoch,
             epoch_ms: epoch.start_timestamp.timestamp().try_into().unwrap(),
+            protocol: SuiProtocol {
+                version: self
+                    .protocol
+                    .protocol_version
+                    .unwrap_or(epoch.protocol_version),
+                chain: self.protocol.resolve_chain(Some(gql)).await?,
+            },
         })
     }
 }